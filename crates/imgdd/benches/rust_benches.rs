@@ -25,9 +25,11 @@ fn benchmark_hash(c: &mut Criterion) {
         b.iter(|| {
             let result = hash(
                 black_box(dir_path.clone()),
-                Some("nearest"),
-                Some("dhash"),
-                Some(false),
+                &HashOptions {
+                    filter: Some("nearest"),
+                    algo: Some("dhash"),
+                    ..Default::default()
+                },
             );
             let _ = black_box(result).is_ok(); // Ignore the result
         });
@@ -41,9 +43,11 @@ fn benchmark_dupes(c: &mut Criterion) {
         b.iter(|| {
             let result = dupes(
                 black_box(dir_path.clone()),
-                Some("nearest"),
-                Some("dhash"),
-                false,
+                &DupesOptions {
+                    filter: Some("nearest"),
+                    algo: Some("dhash"),
+                    ..Default::default()
+                },
             );
             let _ = black_box(result).is_ok(); // Ignore the result
         });
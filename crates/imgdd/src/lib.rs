@@ -1,12 +1,15 @@
 //! Rust interface for fast and efficient image deduplication.
 //! Leverages perceptual hashing algorithms to identify duplicate or visually similar images in a directory.
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use image::imageops::FilterType;
+use image::ImageFormat;
 use imgddcore::dedupe::*;
+use imgddcore::hashing::ImageHash;
+use imgddcore::journal::{self, Journal};
+use imgddcore::normalize::{AlphaPolicy, NormalizePipeline, ResizeMode, Trim};
 use imgddcore::validate::*;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Converts a string to a `FilterType`.
 ///
@@ -21,11 +24,11 @@ use std::path::PathBuf;
 #[inline]
 pub fn select_filter_type(filter: Option<&str>) -> FilterType {
     match filter.unwrap_or("nearest") {
-        ref f if f.eq_ignore_ascii_case("nearest") => FilterType::Nearest,
-        ref f if f.eq_ignore_ascii_case("triangle") => FilterType::Triangle,
-        ref f if f.eq_ignore_ascii_case("catmullrom") => FilterType::CatmullRom,
-        ref f if f.eq_ignore_ascii_case("gaussian") => FilterType::Gaussian,
-        ref f if f.eq_ignore_ascii_case("lanczos3") => FilterType::Lanczos3,
+        f if f.eq_ignore_ascii_case("nearest") => FilterType::Nearest,
+        f if f.eq_ignore_ascii_case("triangle") => FilterType::Triangle,
+        f if f.eq_ignore_ascii_case("catmullrom") => FilterType::CatmullRom,
+        f if f.eq_ignore_ascii_case("gaussian") => FilterType::Gaussian,
+        f if f.eq_ignore_ascii_case("lanczos3") => FilterType::Lanczos3,
         other => panic!("Unsupported filter type: {}", other),
     }
 }
@@ -35,7 +38,7 @@ pub fn select_filter_type(filter: Option<&str>) -> FilterType {
 /// # Arguments
 ///
 /// - `algo` - String specifying the hashing algorithm.
-///     - **Options:** [`aHash`, `mHash`, `dHash`, `pHash`, `wHash`]
+///     - **Options:** [`aHash`, `mHash`, `dHash`, `verticalDHash`, `doubleDHash`, `pHash`, `wHash`, `colorHash`, `PDQ`, `blockHash`, `marrHildreth`, `blockMean`, `blockMeanOverlap`]
 ///
 /// # Returns
 ///
@@ -44,27 +47,319 @@ pub fn select_filter_type(filter: Option<&str>) -> FilterType {
 pub fn select_algo(algo: Option<&str>) -> &'static str {
     match algo.unwrap_or("dhash") {
         input if input.eq_ignore_ascii_case("dhash") => "dhash",
+        input if input.eq_ignore_ascii_case("verticaldhash") => "verticaldhash",
+        input if input.eq_ignore_ascii_case("doubledhash") => "doubledhash",
         input if input.eq_ignore_ascii_case("ahash") => "ahash",
         input if input.eq_ignore_ascii_case("mhash") => "mhash",
         input if input.eq_ignore_ascii_case("phash") => "phash",
         input if input.eq_ignore_ascii_case("whash") => "whash",
+        input if input.eq_ignore_ascii_case("colorhash") => "colorhash",
+        input if input.eq_ignore_ascii_case("pdq") => "pdq",
+        input if input.eq_ignore_ascii_case("blockhash") => "blockhash",
+        input if input.eq_ignore_ascii_case("marrhildreth") => "marrhildreth",
+        input if input.eq_ignore_ascii_case("blockmean") => "blockmean",
+        input if input.eq_ignore_ascii_case("blockmeanoverlap") => "blockmeanoverlap",
         other => panic!("Unsupported algorithm: {}", other),
     }
 }
 
+/// Converts a string to an `AlphaPolicy`.
+///
+/// # Arguments
+///
+/// - `alpha` - String specifying how transparent pixels are treated.
+///     - **Options:** [`ignore`, `white`, `black`] or a background color in hex, e.g. `#808080`
+///
+/// # Returns
+///
+/// - An `AlphaPolicy` enum corresponding to the input string.
+#[inline]
+pub fn select_alpha_policy(alpha: Option<&str>) -> AlphaPolicy {
+    match alpha.unwrap_or("ignore") {
+        a if a.eq_ignore_ascii_case("ignore") => AlphaPolicy::Ignore,
+        a if a.eq_ignore_ascii_case("white") => AlphaPolicy::White,
+        a if a.eq_ignore_ascii_case("black") => AlphaPolicy::Black,
+        other => AlphaPolicy::Color(
+            parse_hex_color(other).unwrap_or_else(|| panic!("Unsupported alpha policy: {}", other)),
+        ),
+    }
+}
+
+/// Parses a `#RRGGBB` or `RRGGBB` hex color.
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Converts a string to a `ResizeMode`.
+///
+/// # Arguments
+///
+/// - `resize` - String specifying how images are fitted to the hash grid.
+///     - **Options:** [`stretch`, `center_crop`, `pad`]
+///
+/// # Returns
+///
+/// - A `ResizeMode` enum corresponding to the input string.
+#[inline]
+pub fn select_resize_mode(resize: Option<&str>) -> ResizeMode {
+    match resize.unwrap_or("stretch") {
+        r if r.eq_ignore_ascii_case("stretch") => ResizeMode::Stretch,
+        r if r.eq_ignore_ascii_case("center_crop") => ResizeMode::CenterCrop,
+        r if r.eq_ignore_ascii_case("pad") => ResizeMode::Pad,
+        other => panic!("Unsupported resize mode: {}", other),
+    }
+}
+
+/// Builds the preprocessing pipeline applied to every image before it is hashed.
+///
+/// # Arguments
+///
+/// - `filter` - String specifying the resize filter, see [`select_filter_type`].
+/// - `orient` - Boolean to determine if images are turned upright according to their EXIF orientation.
+///     - **Default:** false
+/// - `alpha` - String specifying how transparent pixels are treated, see [`select_alpha_policy`].
+///     - **Default:** "ignore"
+/// - `trim` - Tolerance of the uniform borders cropped before hashing, or `None` to keep them.
+///     - **Default:** None
+/// - `resize` - String specifying how images are fitted to the hash grid, see [`select_resize_mode`].
+///     - **Default:** "stretch"
+///
+/// # Returns
+///
+/// - A `NormalizePipeline` that resizes with the selected filter and converts to grayscale.
+#[inline]
+pub fn select_pipeline(
+    filter: Option<&str>,
+    orient: Option<bool>,
+    alpha: Option<&str>,
+    trim: Option<u8>,
+    resize: Option<&str>,
+) -> NormalizePipeline {
+    let pipeline = NormalizePipeline::new(select_filter_type(filter))
+        .with_resize_mode(select_resize_mode(resize));
+    let pipeline = if orient.unwrap_or(false) {
+        pipeline.oriented()
+    } else {
+        pipeline
+    };
+    pipeline
+        .with_alpha(select_alpha_policy(alpha))
+        .with_trim(trim)
+}
+
+/// Selects a policy deciding which duplicate survives removal.
+///
+/// # Arguments
+///
+/// - `keep` - String specifying the keep policy.
+///     - **Options:** [`first`, `largest_resolution`, `largest_size`, `oldest`, `newest`, `format`, `path`]
+/// - `priority` - Ordered preferences used by the `format` (file extensions) and `path` (directories) policies.
+///
+/// # Returns
+///
+/// - A `KeepPolicy` enum corresponding to the input string.
+#[inline]
+pub fn select_keep_policy(keep: Option<&str>, priority: Option<&[&str]>) -> KeepPolicy {
+    let priority = priority.unwrap_or_default();
+    match keep.unwrap_or("first") {
+        k if k.eq_ignore_ascii_case("first") => KeepPolicy::First,
+        k if k.eq_ignore_ascii_case("largest_resolution") => KeepPolicy::LargestResolution,
+        k if k.eq_ignore_ascii_case("largest_size") => KeepPolicy::LargestFileSize,
+        k if k.eq_ignore_ascii_case("oldest") => KeepPolicy::Oldest,
+        k if k.eq_ignore_ascii_case("newest") => KeepPolicy::Newest,
+        k if k.eq_ignore_ascii_case("format") => KeepPolicy::PreferredFormat(
+            priority
+                .iter()
+                .map(|ext| {
+                    ImageFormat::from_extension(ext)
+                        .unwrap_or_else(|| panic!("Unsupported image format: {}", ext))
+                })
+                .collect(),
+        ),
+        k if k.eq_ignore_ascii_case("path") => {
+            KeepPolicy::PathPriority(priority.iter().map(PathBuf::from).collect())
+        }
+        other => panic!("Unsupported keep policy: {}", other),
+    }
+}
+
+/// Converts a string to a `LinkKind`.
+///
+/// # Arguments
+///
+/// - `link` - String specifying the kind of link that replaces duplicates.
+///     - **Options:** [`hard`, `symbolic`, `reflink`]
+///
+/// # Returns
+///
+/// - A `LinkKind` enum corresponding to the input string, or `None` if no link is requested.
+#[inline]
+pub fn select_link_kind(link: Option<&str>) -> Option<LinkKind> {
+    link.map(|link| match link {
+        l if l.eq_ignore_ascii_case("hard") => LinkKind::Hard,
+        l if l.eq_ignore_ascii_case("symbolic") => LinkKind::Symbolic,
+        l if l.eq_ignore_ascii_case("reflink") => LinkKind::Reflink,
+        other => panic!("Unsupported link kind: {}", other),
+    })
+}
+
+/// Settings for [`hash`] and [`hash_trimmed`].
+///
+/// # Usage
+/// ```rust
+/// use imgdd::HashOptions;
+///
+/// let options = HashOptions {
+///     algo: Some("pHash"),
+///     hash_size: 16,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct HashOptions<'a> {
+    /// Resize filter, see [`select_filter_type`].
+    ///     - **Default:** "Triangle"
+    pub filter: Option<&'a str>,
+    /// Hashing algorithm, see [`select_algo`].
+    ///     - **Options:** [`aHash`, `mHash`, `dHash`, `verticalDHash`, `doubleDHash`, `pHash`, `wHash`, `colorHash`, `PDQ`, `blockHash`, `marrHildreth`, `blockMean`, `blockMeanOverlap`]
+    ///     - **Default:** "dHash"
+    pub algo: Option<&'a str>,
+    /// Whether the hashes are sorted by value.
+    ///     - **Default:** false
+    pub sort: bool,
+    /// Side length of the hash grid, e.g. 8 for 64-bit and 16 for 256-bit hashes.
+    ///     - `wHash` requires a power of two and `blockHash` an even size.
+    ///     - `doubleDHash` combines both dHash directions into hashes twice as long, e.g. 128 bits for 8.
+    ///     - `colorHash`, `PDQ`, `marrHildreth`, `blockMean` and `blockMeanOverlap` ignore it
+    ///       and always produce 42-bit, 256-bit, 576-bit, 256-bit and 961-bit hashes.
+    ///     - **Default:** 8
+    pub hash_size: u32,
+    /// Whether images are turned upright according to their EXIF orientation before hashing,
+    /// so rotated phone photos match their upright copies.
+    ///     - **Default:** false
+    pub orient: bool,
+    /// How transparent pixels are treated before hashing, see [`select_alpha_policy`].
+    ///     - **Options:** [`ignore`, `white`, `black`] or a background color in hex, e.g. `#808080`
+    ///     - **Default:** "ignore"
+    pub alpha: Option<&'a str>,
+    /// How images are fitted to the hash grid before hashing, see [`select_resize_mode`].
+    ///     - **Options:** [`stretch`, `center_crop`, `pad`]
+    ///     - `center_crop` matches thumbnails cut from the middle of wider images,
+    ///       `pad` keeps the whole image undistorted. Algorithms hashing the full image ignore it.
+    ///     - **Default:** "stretch"
+    pub resize: Option<&'a str>,
+}
+
+impl Default for HashOptions<'_> {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            algo: None,
+            sort: false,
+            hash_size: 8,
+            orient: false,
+            alpha: None,
+            resize: None,
+        }
+    }
+}
+
+/// Settings for [`dupes`] and [`dupes_dry_run`].
+///
+/// # Usage
+/// ```rust
+/// use imgdd::DupesOptions;
+/// use std::path::PathBuf;
+///
+/// let options = DupesOptions {
+///     max_distance: 4,
+///     remove: true,
+///     keep: Some("largest_resolution"),
+///     quarantine: Some(PathBuf::from("path/to/quarantine")),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct DupesOptions<'a> {
+    /// Resize filter, see [`select_filter_type`].
+    ///     - **Default:** "Triangle"
+    pub filter: Option<&'a str>,
+    /// Hashing algorithm, see [`HashOptions::algo`].
+    ///     - **Default:** "dHash"
+    pub algo: Option<&'a str>,
+    /// Maximum Hamming distance for two images to be considered duplicates.
+    ///     - **Default:** 0 (exact matches only)
+    pub max_distance: u32,
+    /// Whether duplicate files are removed. Ignored by [`dupes_dry_run`].
+    ///     - **Default:** false
+    pub remove: bool,
+    /// Which duplicate survives removal, see [`select_keep_policy`].
+    ///     - **Options:** [`first`, `largest_resolution`, `largest_size`, `oldest`, `newest`, `format`, `path`]
+    ///     - **Default:** "first"
+    pub keep: Option<&'a str>,
+    /// Ordered file extensions (`format`) or directories (`path`) to prefer when keeping a file.
+    pub priority: Option<&'a [&'a str]>,
+    /// Directory that removed duplicates are moved into instead of being deleted.
+    ///     - Paths relative to `path` are preserved, e.g. `a/b/c.jpg` is moved to `quarantine/a/b/c.jpg`.
+    ///     - Must be outside of `path`, so quarantined files are not scanned again.
+    pub quarantine: Option<PathBuf>,
+    /// JSON lines file recording every removed, moved or linked duplicate, see [`undo`].
+    ///     - Only written when `remove` is set.
+    pub journal: Option<PathBuf>,
+    /// Replace removed duplicates with a link to the kept file instead of deleting them.
+    ///     - **Options:** [`hard`, `symbolic`, `reflink`]
+    ///     - Cannot be combined with `quarantine`.
+    pub link: Option<&'a str>,
+    /// Side length of the hash grid, see [`HashOptions::hash_size`].
+    ///     - **Default:** 8
+    pub hash_size: u32,
+    /// Whether images are turned upright according to their EXIF orientation before hashing.
+    ///     - **Default:** false
+    pub orient: bool,
+    /// How transparent pixels are treated before hashing, see [`HashOptions::alpha`].
+    ///     - **Default:** "ignore"
+    pub alpha: Option<&'a str>,
+    /// Tolerance for cropping uniform borders, such as letterboxing, before hashing.
+    /// Letterboxed copies then match their originals. See [`hash_trimmed`] to audit the cropped amounts.
+    ///     - **Default:** None (borders are kept)
+    pub trim: Option<u8>,
+    /// How images are fitted to the hash grid before hashing, see [`HashOptions::resize`].
+    ///     - **Default:** "stretch"
+    pub resize: Option<&'a str>,
+}
+
+impl Default for DupesOptions<'_> {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            algo: None,
+            max_distance: 0,
+            remove: false,
+            keep: None,
+            priority: None,
+            quarantine: None,
+            journal: None,
+            link: None,
+            hash_size: 8,
+            orient: false,
+            alpha: None,
+            trim: None,
+            resize: None,
+        }
+    }
+}
+
 /// Calculates hashes for all images in a directory recursively.
 ///
 /// # Arguments
 ///
 /// - `path` - String representing the directory containing images.
-/// - `filter` - String specifying the resize filter to use.
-///     - **Options:** [`Nearest`, `Triangle`, `CatmullRom`, `Gaussian`, `Lanczos3`]
-///     - **Default:** "Triangle"
-/// - `algo` - String specifying the hashing algorithm to use.
-///     - **Options:** [`aHash`, `mHash`, `dHash`, `pHash`, `wHash`]
-///     - **Default:** "dHash"
-/// - `sort` - Boolean to determine if the hashes should be sorted.
-///     - **Default:** false
+/// - `options` - How images are hashed, see [`HashOptions`].
 ///
 /// # Returns
 ///
@@ -77,76 +372,267 @@ pub fn select_algo(algo: Option<&str>) -> &'static str {
 ///
 /// let result = hash(
 ///     PathBuf::from("path/to/images"),
-///     Some("Triangle"), // Optional: default = "Triangle"
-///     Some("dHash"),   // Optional: default = "dHash"
-///     Some(false),     // Optional: default = false
+///     &HashOptions {
+///         filter: Some("Triangle"), // Optional: default = "Triangle"
+///         algo: Some("dHash"),      // Optional: default = "dHash"
+///         sort: false,              // Optional: default = false
+///         hash_size: 8,             // Optional: default = 8
+///         orient: true,             // Optional: default = false
+///         alpha: Some("white"),     // Optional: default = "ignore"
+///         resize: Some("pad"),      // Optional: default = "stretch"
+///     },
 /// );
 ///
 /// println!("{:#?}", result);
 /// ```
-pub fn hash(
-    path: PathBuf,
-    filter: Option<&str>,
-    algo: Option<&str>,
-    sort: Option<bool>,
-) -> Result<Vec<(u64, PathBuf)>, Error> {
+pub fn hash(path: PathBuf, options: &HashOptions) -> Result<Vec<(ImageHash, PathBuf)>, Error> {
     let validated_path = validate_path(&path)?;
-    let filter_type = select_filter_type(filter);
-    let selected_algo = select_algo(algo);
+    let pipeline = select_pipeline(
+        options.filter,
+        Some(options.orient),
+        options.alpha,
+        None,
+        options.resize,
+    );
+    let selected_algo = select_algo(options.algo);
 
-    let mut hash_paths = collect_hashes(validated_path, filter_type, selected_algo)?;
+    let mut hash_paths =
+        collect_hashes_with(validated_path, selected_algo, options.hash_size, &pipeline)?;
 
     // Optionally sort hashes
-    if sort.unwrap_or(false) {
+    if options.sort {
         sort_hashes(&mut hash_paths);
     }
 
     Ok(hash_paths)
 }
 
-/// Finds duplicate images in a directory.
+/// Calculates hashes for all images in a directory recursively after cropping uniform borders,
+/// reporting how much was cropped from each image.
 ///
 /// # Arguments
 ///
 /// - `path` - String representing the directory containing images.
-/// - `filter` - String specifying the resize filter to use.
-///     - **Options:** [`Nearest`, `Triangle`, `CatmullRom`, `Gaussian`, `Lanczos3`]
-///     - **Default:** "Triangle"
-/// - `algo` - String specifying the hashing algorithm to use.
-///     - **Options:** [`aHash`, `mHash`, `dHash`, `pHash`, `wHash`]
-///     - **Default:** "dhash"
-/// - `remove` - Boolean indicating whether duplicate files should be removed.
+/// - `tolerance` - Largest channel difference from the top left pixel still counted as border,
+///   e.g. 10.
+/// - `options` - How images are hashed, see [`HashOptions`].
 ///
 /// # Returns
 ///
-/// * A hashmap of hash values to lists of file paths.
+/// * A vector of tuples where each tuple contains a hash value, the corresponding file path
+///   and the number of pixels trimmed from each edge.
 ///
 /// # Usage
 /// ```rust
 /// use imgdd::*;
 /// use std::path::PathBuf;
 ///
-/// let result = dupes(
+/// let result = hash_trimmed(
 ///     PathBuf::from("path/to/images"),
-///     Some("Triangle"), // Optional: default = "Triangle"
-///     Some("dHash"),   // Optional: default = "dHash"
-///     false,
+///     10,
+///     &HashOptions {
+///         alpha: Some("white"), // Optional: default = "ignore"
+///         ..Default::default()
+///     },
 /// );
 ///
 /// println!("{:#?}", result);
 /// ```
-pub fn dupes(
+pub fn hash_trimmed(
     path: PathBuf,
-    filter: Option<&str>,
-    algo: Option<&str>,
-    remove: bool,
-) -> Result<HashMap<u64, Vec<PathBuf>>, Error> {
+    tolerance: u8,
+    options: &HashOptions,
+) -> Result<Vec<(ImageHash, PathBuf, Trim)>, Error> {
     let validated_path = validate_path(&path)?;
-    let filter_type = select_filter_type(filter);
-    let selected_algo = select_algo(algo);
+    let pipeline = select_pipeline(
+        options.filter,
+        Some(options.orient),
+        options.alpha,
+        None,
+        options.resize,
+    );
+    let selected_algo = select_algo(options.algo);
+
+    let mut hash_paths = collect_trimmed_hashes(
+        validated_path,
+        selected_algo,
+        options.hash_size,
+        tolerance,
+        &pipeline,
+    )?;
+
+    // Optionally sort hashes
+    if options.sort {
+        hash_paths.sort_by(|(hash1, ..), (hash2, ..)| hash1.cmp(hash2));
+    }
+
+    Ok(hash_paths)
+}
+
+/// Hashes the images of a directory and groups duplicates without acting on them.
+fn find_groups(
+    path: &Path,
+    options: &DupesOptions,
+    action: &DuplicateAction,
+    keep_policy: &KeepPolicy,
+    journal: Option<&mut Journal>,
+) -> Result<Vec<DuplicateGroup>, Error> {
+    let pipeline = select_pipeline(
+        options.filter,
+        Some(options.orient),
+        options.alpha,
+        options.trim,
+        options.resize,
+    );
 
-    let mut hash_paths = collect_hashes(validated_path, filter_type, selected_algo)?;
+    let mut hash_paths = collect_hashes_with(
+        path,
+        select_algo(options.algo),
+        options.hash_size,
+        &pipeline,
+    )?;
     sort_hashes(&mut hash_paths);
 
-    Ok(find_duplicates(&hash_paths, remove)?)
+    find_duplicates(
+        &hash_paths,
+        options.max_distance,
+        action,
+        keep_policy,
+        journal,
+    )
+}
+
+/// Finds duplicate images in a directory.
+///
+/// # Arguments
+///
+/// - `path` - String representing the directory containing images.
+/// - `options` - How images are hashed, matched and removed, see [`DupesOptions`].
+///
+/// # Returns
+///
+/// * A vector of duplicate groups, each listing its unique members sorted by path
+///   with their hash, file size and dimensions.
+///
+/// # Usage
+/// ```rust
+/// use imgdd::*;
+/// use std::path::PathBuf;
+///
+/// let result = dupes(
+///     PathBuf::from("path/to/images"),
+///     &DupesOptions {
+///         algo: Some("dHash"),             // Optional: default = "dHash"
+///         max_distance: 0,                 // Optional: default = 0
+///         remove: false,                   // Optional: default = false
+///         keep: Some("format"),            // Optional: default = "first"
+///         priority: Some(&["png", "jpg"]), // Optional: used by "format" and "path"
+///         trim: Some(10),                  // Optional: default = None (keep borders)
+///         ..Default::default()
+///     },
+/// );
+///
+/// println!("{:#?}", result);
+/// ```
+pub fn dupes(path: PathBuf, options: &DupesOptions) -> Result<Vec<DuplicateGroup>, Error> {
+    let validated_path = validate_path(&path)?;
+    let selected_algo = select_algo(options.algo);
+    let keep_policy = select_keep_policy(options.keep, options.priority);
+    let action = match (&options.quarantine, select_link_kind(options.link)) {
+        _ if !options.remove => DuplicateAction::Report,
+        (Some(_), Some(_)) => return Err(anyhow!("Cannot both quarantine and link duplicates")),
+        (Some(dir), None) => {
+            check_quarantine_dir(validated_path, dir)?;
+            DuplicateAction::Quarantine {
+                root: validated_path.clone(),
+                dir: dir.clone(),
+            }
+        }
+        (None, Some(kind)) => DuplicateAction::Link(kind),
+        (None, None) => DuplicateAction::Remove,
+    };
+
+    // Only destructive runs are journaled, so a report never creates the journal file
+    let mut journal = match &options.journal {
+        Some(journal_path) if action != DuplicateAction::Report => {
+            Some(Journal::open(journal_path, selected_algo)?)
+        }
+        _ => None,
+    };
+
+    find_groups(
+        validated_path,
+        options,
+        &action,
+        &keep_policy,
+        journal.as_mut(),
+    )
+}
+
+/// Plans the removal of duplicate images in a directory without touching the filesystem.
+///
+/// This is the dry run of [`dupes`]: it reports which file of each group would be kept,
+/// which files would be removed, why, and how many bytes would be reclaimed.
+///
+/// # Arguments
+///
+/// - `path` - String representing the directory containing images.
+/// - `options` - How images are hashed and matched, see [`DupesOptions`].
+///   `remove`, `quarantine`, `journal` and `link` are ignored.
+///
+/// # Returns
+///
+/// * A vector of planned actions, one per group of duplicates.
+///
+/// # Usage
+/// ```rust
+/// use imgdd::*;
+/// use std::path::PathBuf;
+///
+/// let result = dupes_dry_run(
+///     PathBuf::from("path/to/images"),
+///     &DupesOptions {
+///         keep: Some("largest_resolution"), // Optional: default = "first"
+///         ..Default::default()
+///     },
+/// );
+///
+/// println!("{:#?}", result);
+/// ```
+pub fn dupes_dry_run(path: PathBuf, options: &DupesOptions) -> Result<Vec<PlannedAction>, Error> {
+    let validated_path = validate_path(&path)?;
+    let keep_policy = select_keep_policy(options.keep, options.priority);
+
+    let duplicates = find_groups(
+        validated_path,
+        options,
+        &DuplicateAction::Report,
+        &keep_policy,
+        None,
+    )?;
+
+    Ok(plan_duplicates(&duplicates, &keep_policy))
+}
+
+/// Restores quarantined duplicates recorded in a journal.
+///
+/// # Arguments
+///
+/// - `journal` - The JSON lines journal written by [`dupes`].
+///
+/// # Returns
+///
+/// * A vector of the restored file paths. Removed files cannot be restored.
+///
+/// # Usage
+/// ```rust
+/// use imgdd::*;
+/// use std::path::PathBuf;
+///
+/// let result = undo(PathBuf::from("path/to/journal.jsonl"));
+///
+/// println!("{:#?}", result);
+/// ```
+pub fn undo(journal: PathBuf) -> Result<Vec<PathBuf>, Error> {
+    journal::undo(&journal)
 }
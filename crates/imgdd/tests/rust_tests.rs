@@ -1,7 +1,10 @@
 #[cfg(test)]
 mod tests {
     use image::imageops::FilterType;
+    use image::ImageFormat;
     use imgdd::*;
+    use imgddcore::dedupe::{KeepPolicy, LinkKind};
+    use imgddcore::normalize::{AlphaPolicy, NormalizePipeline, NormalizeStep, ResizeMode};
     use std::fs::File;
     use std::io::Write;
     use std::path::PathBuf;
@@ -28,15 +31,138 @@ mod tests {
     #[test]
     fn test_select_algo() {
         assert_eq!(select_algo(Some("dhash")), "dhash");
+        assert_eq!(select_algo(Some("verticalDHash")), "verticaldhash");
+        assert_eq!(select_algo(Some("doubleDHash")), "doubledhash");
         assert_eq!(select_algo(Some("ahash")), "ahash");
         assert_eq!(select_algo(Some("mhash")), "mhash");
         assert_eq!(select_algo(Some("phash")), "phash");
         assert_eq!(select_algo(Some("whash")), "whash");
+        assert_eq!(select_algo(Some("colorHash")), "colorhash");
+        assert_eq!(select_algo(Some("PDQ")), "pdq");
+        assert_eq!(select_algo(Some("blockHash")), "blockhash");
+        assert_eq!(select_algo(Some("marrHildreth")), "marrhildreth");
+        assert_eq!(select_algo(Some("blockMean")), "blockmean");
+        assert_eq!(select_algo(Some("blockMeanOverlap")), "blockmeanoverlap");
 
         let result = std::panic::catch_unwind(|| select_algo(Some("unsupported")));
         assert!(result.is_err(), "Expected panic for unsupported algorithm");
     }
 
+    #[test]
+    fn test_select_pipeline() {
+        let plain = NormalizePipeline::new(FilterType::Triangle);
+        assert_eq!(
+            select_pipeline(Some("triangle"), Some(false), None, None, None),
+            plain
+        );
+        assert_eq!(
+            select_pipeline(Some("triangle"), None, None, None, None),
+            plain
+        );
+        assert_eq!(
+            select_pipeline(Some("nearest"), Some(true), None, None, None).steps[0],
+            NormalizeStep::Orient
+        );
+        assert_eq!(
+            select_pipeline(Some("triangle"), Some(true), Some("black"), None, None),
+            plain.clone().oriented().with_alpha(AlphaPolicy::Black)
+        );
+        assert_eq!(
+            select_pipeline(Some("triangle"), Some(false), None, Some(12), None),
+            plain.clone().with_trim(Some(12))
+        );
+        assert_eq!(
+            select_pipeline(Some("triangle"), Some(false), None, None, Some("pad")),
+            plain.with_resize_mode(ResizeMode::Pad)
+        );
+    }
+
+    #[test]
+    fn test_select_resize_mode() {
+        assert_eq!(select_resize_mode(None), ResizeMode::Stretch);
+        assert_eq!(select_resize_mode(Some("Stretch")), ResizeMode::Stretch);
+        assert_eq!(
+            select_resize_mode(Some("center_crop")),
+            ResizeMode::CenterCrop
+        );
+        assert_eq!(select_resize_mode(Some("pad")), ResizeMode::Pad);
+
+        let result = std::panic::catch_unwind(|| select_resize_mode(Some("unsupported")));
+        assert!(
+            result.is_err(),
+            "Expected panic for unsupported resize mode"
+        );
+    }
+
+    #[test]
+    fn test_select_alpha_policy() {
+        assert_eq!(select_alpha_policy(None), AlphaPolicy::Ignore);
+        assert_eq!(select_alpha_policy(Some("White")), AlphaPolicy::White);
+        assert_eq!(select_alpha_policy(Some("black")), AlphaPolicy::Black);
+        assert_eq!(
+            select_alpha_policy(Some("#80FF0a")),
+            AlphaPolicy::Color([128, 255, 10])
+        );
+        assert_eq!(
+            select_alpha_policy(Some("808080")),
+            AlphaPolicy::Color([128, 128, 128])
+        );
+
+        for unsupported in ["unsupported", "#12345", "#12345g", "#ééé"] {
+            let result = std::panic::catch_unwind(|| select_alpha_policy(Some(unsupported)));
+            assert!(result.is_err(), "Expected panic for {}", unsupported);
+        }
+    }
+
+    #[test]
+    fn test_select_keep_policy() {
+        assert_eq!(select_keep_policy(None, None), KeepPolicy::First);
+        assert_eq!(
+            select_keep_policy(Some("largest_resolution"), None),
+            KeepPolicy::LargestResolution
+        );
+        assert_eq!(
+            select_keep_policy(Some("largest_size"), None),
+            KeepPolicy::LargestFileSize
+        );
+        assert_eq!(select_keep_policy(Some("oldest"), None), KeepPolicy::Oldest);
+        assert_eq!(select_keep_policy(Some("newest"), None), KeepPolicy::Newest);
+        assert_eq!(
+            select_keep_policy(Some("format"), Some(&["png", "jpeg"])),
+            KeepPolicy::PreferredFormat(vec![ImageFormat::Png, ImageFormat::Jpeg])
+        );
+        assert_eq!(
+            select_keep_policy(Some("path"), Some(&["originals"])),
+            KeepPolicy::PathPriority(vec![PathBuf::from("originals")])
+        );
+
+        let result = std::panic::catch_unwind(|| select_keep_policy(Some("unsupported"), None));
+        assert!(
+            result.is_err(),
+            "Expected panic for unsupported keep policy"
+        );
+
+        let result =
+            std::panic::catch_unwind(|| select_keep_policy(Some("format"), Some(&["xyz"])));
+        assert!(
+            result.is_err(),
+            "Expected panic for unsupported image format"
+        );
+    }
+
+    #[test]
+    fn test_options_default() {
+        let options = HashOptions::default();
+        assert_eq!(
+            (options.hash_size, options.orient, options.sort),
+            (8, false, false)
+        );
+
+        let options = DupesOptions::default();
+        assert_eq!((options.max_distance, options.hash_size), (0, 8));
+        assert!(!options.remove && options.trim.is_none());
+    }
+
     #[test]
     fn test_hash_with_valid_inputs() {
         let temp_dir = tempdir().unwrap();
@@ -48,9 +174,11 @@ mod tests {
 
         let result = hash(
             temp_dir.path().to_path_buf(),
-            Some("nearest"),
-            Some("dhash"),
-            Some(false),
+            &HashOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                ..Default::default()
+            },
         );
         assert!(result.is_ok(), "Hash function failed: {:?}", result.err());
     }
@@ -60,9 +188,11 @@ mod tests {
         let invalid_path = PathBuf::from("/non/existent/path");
         let result = hash(
             invalid_path.clone(),
-            Some("nearest"),
-            Some("dhash"),
-            Some(false),
+            &HashOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                ..Default::default()
+            },
         );
         assert!(
             result.is_err(),
@@ -74,7 +204,15 @@ mod tests {
     #[test]
     fn test_hash_with_sorting() {
         let img_dir = PathBuf::from("../../imgs/test/apple_pie");
-        let result = hash(img_dir, Some("nearest"), Some("dhash"), Some(true));
+        let result = hash(
+            img_dir,
+            &HashOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                sort: true,
+                ..Default::default()
+            },
+        );
 
         assert!(result.is_ok(), "Hash function failed: {:?}", result.err());
 
@@ -92,6 +230,75 @@ mod tests {
         assert!(sorted, "Hashes are not sorted: {:?}", hash_paths);
     }
 
+    #[test]
+    fn test_hash_with_hash_size() {
+        let img_dir = PathBuf::from("../../imgs/test/apple_pie");
+        let result = hash(
+            img_dir,
+            &HashOptions {
+                filter: Some("nearest"),
+                algo: Some("phash"),
+                hash_size: 16,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Hash function failed: {:?}", result.err());
+
+        // A 16x16 grid produces 256-bit hashes
+        for (hash, _) in result.unwrap() {
+            assert_eq!(hash.len(), 256);
+            assert_eq!(hash.to_hex().len(), 64);
+        }
+    }
+
+    #[test]
+    fn test_hash_trimmed_and_dupes_with_trim() {
+        let temp_dir = tempdir().unwrap();
+        let content = image::RgbImage::from_fn(40, 30, |x, y| {
+            image::Rgb([60 + (x * 4) as u8, 60 + (y * 5) as u8, 90])
+        });
+        let letterboxed = image::RgbImage::from_fn(40, 46, |x, y| match y {
+            8..38 => *content.get_pixel(x, y - 8),
+            _ => image::Rgb([3, 3, 3]),
+        });
+        content.save(temp_dir.path().join("content.png")).unwrap();
+        letterboxed
+            .save(temp_dir.path().join("letterboxed.png"))
+            .unwrap();
+
+        let result = hash_trimmed(
+            temp_dir.path().to_path_buf(),
+            10,
+            &HashOptions {
+                filter: Some("triangle"),
+                algo: Some("ahash"),
+                sort: true,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Hash function failed: {:?}", result.err());
+        let mut hashes = result.unwrap();
+        hashes.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(hashes[0].0, hashes[1].0);
+        assert!(hashes[0].2.is_empty());
+        assert_eq!((hashes[1].2.top, hashes[1].2.bottom), (8, 8));
+
+        let dupes_with_trim = |trim| {
+            dupes(
+                temp_dir.path().to_path_buf(),
+                &DupesOptions {
+                    filter: Some("triangle"),
+                    algo: Some("ahash"),
+                    trim,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        assert!(dupes_with_trim(None).is_empty());
+        assert_eq!(dupes_with_trim(Some(10)).len(), 1);
+    }
+
     #[test]
     fn test_dupes_with_valid_inputs() {
         let temp_dir = tempdir().unwrap();
@@ -107,9 +314,11 @@ mod tests {
 
         let result = dupes(
             temp_dir.path().to_path_buf(),
-            Some("nearest"),
-            Some("dhash"),
-            false,
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                ..Default::default()
+            },
         );
         assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
 
@@ -124,11 +333,252 @@ mod tests {
     #[test]
     fn test_dupes_with_invalid_path() {
         let invalid_path = PathBuf::from("/non/existent/path");
-        let result = dupes(invalid_path.clone(), Some("nearest"), Some("dhash"), false);
+        let result = dupes(
+            invalid_path.clone(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                ..Default::default()
+            },
+        );
         assert!(
             result.is_err(),
             "Expected error for invalid path: {:?}",
             invalid_path
         );
     }
+
+    #[test]
+    fn test_dupes_with_max_distance() {
+        let img_dir = PathBuf::from("../../imgs/test/apple_pie");
+        let result = dupes(
+            img_dir,
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                max_distance: 64,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
+
+        // Every hash is within 64 bits of every other, so all images form a single group
+        let duplicates = result.unwrap();
+        assert_eq!(duplicates.len(), 1, "Expected a single group");
+        assert_eq!(duplicates[0].len(), 10);
+    }
+
+    #[test]
+    fn test_dupes_group_members() {
+        let temp_dir = tempdir().unwrap();
+        let source = PathBuf::from("../../imgs/test/apple_pie/99556.jpg");
+        std::fs::copy(&source, temp_dir.path().join("b.jpg")).unwrap();
+        std::fs::copy(&source, temp_dir.path().join("a.jpg")).unwrap();
+
+        let result = dupes(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
+
+        // Members are unique, sorted by path and carry their metadata
+        let duplicates = result.unwrap();
+        assert_eq!(duplicates.len(), 1, "Expected a single group");
+        assert_eq!(
+            duplicates[0].paths(),
+            vec![temp_dir.path().join("a.jpg"), temp_dir.path().join("b.jpg")]
+        );
+        let size = std::fs::metadata(&source).unwrap().len();
+        let dimensions = image::image_dimensions(&source).ok();
+        for member in &duplicates[0].members {
+            assert_eq!(member.hash, duplicates[0].hash);
+            assert_eq!(member.file_size, size);
+            assert_eq!(member.dimensions, dimensions);
+        }
+    }
+
+    #[test]
+    fn test_dupes_with_quarantine() {
+        let temp_dir = tempdir().unwrap();
+        let quarantine_dir = tempdir().unwrap();
+        let originals = temp_dir.path().join("originals");
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&originals).unwrap();
+        std::fs::create_dir(&nested).unwrap();
+
+        let source = PathBuf::from("../../imgs/test/apple_pie/99556.jpg");
+        std::fs::copy(&source, originals.join("a.jpg")).unwrap();
+        std::fs::copy(&source, nested.join("b.jpg")).unwrap();
+
+        let originals_str = originals.to_str().unwrap();
+        let result = dupes(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                remove: true,
+                keep: Some("path"),
+                priority: Some(&[originals_str]),
+                quarantine: Some(quarantine_dir.path().to_path_buf()),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
+
+        // Original is kept, the duplicate is moved with its relative path
+        assert!(originals.join("a.jpg").exists());
+        assert!(!nested.join("b.jpg").exists());
+        assert!(quarantine_dir.path().join("nested").join("b.jpg").exists());
+    }
+
+    #[test]
+    fn test_dupes_journal_and_undo() {
+        let temp_dir = tempdir().unwrap();
+        let quarantine_dir = tempdir().unwrap();
+        let journal_path = quarantine_dir.path().join("journal.jsonl");
+
+        let source = PathBuf::from("../../imgs/test/apple_pie/99556.jpg");
+        std::fs::copy(&source, temp_dir.path().join("a.jpg")).unwrap();
+        std::fs::copy(&source, temp_dir.path().join("b.jpg")).unwrap();
+
+        let result = dupes(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                remove: true,
+                quarantine: Some(quarantine_dir.path().join("files")),
+                journal: Some(journal_path.clone()),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
+
+        // Exactly one duplicate was moved and journaled
+        let journal = std::fs::read_to_string(&journal_path).unwrap();
+        assert_eq!(journal.lines().count(), 1);
+        assert!(journal.contains("\"algo\":\"dhash\""));
+
+        let restored = undo(journal_path).expect("Undo function failed");
+        assert_eq!(restored.len(), 1);
+        assert!(temp_dir.path().join("a.jpg").exists());
+        assert!(temp_dir.path().join("b.jpg").exists());
+    }
+
+    #[test]
+    fn test_dupes_journal_without_remove() {
+        let temp_dir = tempdir().unwrap();
+        let journal_dir = tempdir().unwrap();
+        let journal_path = journal_dir.path().join("journal.jsonl");
+
+        let source = PathBuf::from("../../imgs/test/apple_pie/99556.jpg");
+        std::fs::copy(&source, temp_dir.path().join("a.jpg")).unwrap();
+        std::fs::copy(&source, temp_dir.path().join("b.jpg")).unwrap();
+
+        let result = dupes(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                journal: Some(journal_path.clone()),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
+        assert_eq!(result.unwrap().len(), 1);
+
+        // Reporting duplicates does not create the journal
+        assert!(!journal_path.exists());
+    }
+
+    #[test]
+    fn test_select_link_kind() {
+        assert_eq!(select_link_kind(None), None);
+        assert_eq!(select_link_kind(Some("hard")), Some(LinkKind::Hard));
+        assert_eq!(select_link_kind(Some("Symbolic")), Some(LinkKind::Symbolic));
+        assert_eq!(select_link_kind(Some("reflink")), Some(LinkKind::Reflink));
+
+        let result = std::panic::catch_unwind(|| select_link_kind(Some("unsupported")));
+        assert!(result.is_err(), "Expected panic for unsupported link kind");
+    }
+
+    #[test]
+    fn test_dupes_with_link() {
+        let temp_dir = tempdir().unwrap();
+        let source = PathBuf::from("../../imgs/test/apple_pie/99556.jpg");
+        std::fs::copy(&source, temp_dir.path().join("a.jpg")).unwrap();
+        std::fs::copy(&source, temp_dir.path().join("b.jpg")).unwrap();
+
+        let result = dupes(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                remove: true,
+                link: Some("hard"),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dupes function failed: {:?}", result.err());
+
+        // Both paths keep existing
+        assert!(temp_dir.path().join("a.jpg").exists());
+        assert!(temp_dir.path().join("b.jpg").exists());
+
+        let result = dupes(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                remove: true,
+                quarantine: Some(temp_dir.path().join("quarantine")),
+                link: Some("hard"),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err(), "Expected error for quarantine and link");
+    }
+
+    #[test]
+    fn test_undo_with_invalid_journal() {
+        let result = undo(PathBuf::from("/non/existent/journal.jsonl"));
+        assert!(result.is_err(), "Expected error for missing journal");
+    }
+
+    #[test]
+    fn test_dupes_dry_run() {
+        let temp_dir = tempdir().unwrap();
+        let source = PathBuf::from("../../imgs/test/apple_pie/99556.jpg");
+        std::fs::copy(&source, temp_dir.path().join("a.jpg")).unwrap();
+        std::fs::copy(&source, temp_dir.path().join("b.jpg")).unwrap();
+
+        let result = dupes_dry_run(
+            temp_dir.path().to_path_buf(),
+            &DupesOptions {
+                filter: Some("nearest"),
+                algo: Some("dhash"),
+                keep: Some("path"),
+                priority: Some(&[temp_dir.path().join("b.jpg").to_str().unwrap()]),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok(), "Dry run failed: {:?}", result.err());
+
+        let plan = result.unwrap();
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].keep, temp_dir.path().join("b.jpg"));
+        assert_eq!(plan[0].remove, vec![temp_dir.path().join("a.jpg")]);
+        assert_eq!(plan[0].reason, "path priority");
+        assert_eq!(
+            plan[0].bytes_reclaimed,
+            std::fs::metadata(&source).unwrap().len()
+        );
+
+        // Nothing is removed during a dry run
+        assert!(temp_dir.path().join("a.jpg").exists());
+    }
 }
@@ -1,7 +1,11 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use imgddcore::dedupe::{collect_hashes, find_duplicates, open_image, sort_hashes};
-use imgddcore::hashing::ImageHash;
+use imgddcore::dedupe::{
+    collect_hashes, find_duplicates, multi_index_pairs, open_image, sort_hashes, DuplicateAction,
+    KeepPolicy,
+};
+use imgddcore::hashing::{BlockMeanMode, ColorMomentHash, ImageHash, RadialVarianceHash};
+use imgddcore::index::BKTree;
 use imgddcore::normalize::proc as normalize;
 use std::path::PathBuf;
 
@@ -47,6 +51,7 @@ fn benchmark_collect_hashes(c: &mut Criterion) {
                 black_box(&dir_path),
                 black_box(image::imageops::FilterType::Triangle),
                 black_box("dhash"),
+                black_box(8),
             )
             .expect("Failed to collect hashes");
         });
@@ -55,8 +60,9 @@ fn benchmark_collect_hashes(c: &mut Criterion) {
 
 fn benchmark_sort_hashes(c: &mut Criterion) {
     let dir_path = PathBuf::from("../../imgs/test");
-    let mut hash_paths = collect_hashes(&dir_path, image::imageops::FilterType::Triangle, "dhash")
-        .expect("Failed to collect hashes");
+    let mut hash_paths =
+        collect_hashes(&dir_path, image::imageops::FilterType::Triangle, "dhash", 8)
+            .expect("Failed to collect hashes");
 
     c.bench_function("sort_hashes", |b| {
         b.iter(|| {
@@ -67,14 +73,68 @@ fn benchmark_sort_hashes(c: &mut Criterion) {
 
 fn benchmark_find_duplicates(c: &mut Criterion) {
     let dir_path = PathBuf::from("../../imgs/test");
-    let mut hash_paths = collect_hashes(&dir_path, image::imageops::FilterType::Triangle, "dhash")
-        .expect("Failed to collect hashes");
+    let mut hash_paths =
+        collect_hashes(&dir_path, image::imageops::FilterType::Triangle, "dhash", 8)
+            .expect("Failed to collect hashes");
     sort_hashes(&mut hash_paths);
 
     c.bench_function("find_duplicates", |b| {
         b.iter(|| {
-            let _ =
-                find_duplicates(black_box(&hash_paths), false).expect("Failed to find duplicates");
+            let _ = find_duplicates(
+                black_box(&hash_paths),
+                0,
+                &DuplicateAction::Report,
+                &KeepPolicy::First,
+                None,
+            )
+            .expect("Failed to find duplicates");
+        });
+    });
+}
+
+fn benchmark_multi_index_pairs(c: &mut Criterion) {
+    let dir_path = PathBuf::from("../../imgs/test");
+    let hash_paths = collect_hashes(&dir_path, image::imageops::FilterType::Triangle, "dhash", 8)
+        .expect("Failed to collect hashes");
+
+    c.bench_function("multi_index_pairs", |b| {
+        b.iter(|| {
+            black_box(multi_index_pairs(black_box(&hash_paths), black_box(8)));
+        });
+    });
+}
+
+fn benchmark_multi_index_pairs_large(c: &mut Criterion) {
+    // A million pseudo-random 64-bit hashes, every tenth with a near-duplicate 3 bits away
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut hash_paths = Vec::with_capacity(1_100_000);
+    for i in 0..1_000_000u64 {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        hash_paths.push((ImageHash::from(state), PathBuf::new()));
+        if i % 10 == 0 {
+            hash_paths.push((ImageHash::from(state ^ (0b111 << (i % 60))), PathBuf::new()));
+        }
+    }
+
+    c.bench_function("multi_index_pairs_1m", |b| {
+        b.iter(|| {
+            black_box(multi_index_pairs(black_box(&hash_paths), black_box(8)));
+        });
+    });
+}
+
+fn benchmark_bktree_find(c: &mut Criterion) {
+    let dir_path = PathBuf::from("../../imgs/test");
+    let hash_paths = collect_hashes(&dir_path, image::imageops::FilterType::Triangle, "dhash", 8)
+        .expect("Failed to collect hashes");
+    let tree = BKTree::from_hashes(&hash_paths);
+    let query = hash_paths[0].0.clone();
+
+    c.bench_function("bktree_find", |b| {
+        b.iter(|| {
+            black_box(tree.find(black_box(&query), black_box(8)));
         });
     });
 }
@@ -160,6 +220,98 @@ fn benchmark_whash(c: &mut Criterion) {
     });
 }
 
+fn benchmark_pdq(c: &mut Criterion) {
+    let img_path = PathBuf::from("../../imgs/test/single/file000898199107.jpg");
+
+    // PDQ filters and downsamples the full image itself
+    let image = open_image(&img_path).expect("Failed to open image");
+
+    c.bench_function("pdq", |b| {
+        b.iter(|| {
+            // Compute PDQ for the full image
+            ImageHash::pdq(black_box(&image)).expect("Failed to compute pdq");
+        });
+    });
+}
+
+fn benchmark_blockhash(c: &mut Criterion) {
+    let img_path = PathBuf::from("../../imgs/test/single/file000898199107.jpg");
+
+    // Blockhash averages blocks of the full image itself
+    let image = open_image(&img_path).expect("Failed to open image");
+
+    c.bench_function("blockhash", |b| {
+        b.iter(|| {
+            // Compute blockhash for the full image
+            ImageHash::blockhash(black_box(&image), black_box(16))
+                .expect("Failed to compute blockhash");
+        });
+    });
+}
+
+fn benchmark_marr_hildreth(c: &mut Criterion) {
+    let img_path = PathBuf::from("../../imgs/test/single/file000898199107.jpg");
+
+    // Unwrap the image and normalize it outside the benchmark iteration
+    let image = open_image(&img_path).expect("Failed to open image");
+    let normalized_image = normalize(&image, image::imageops::FilterType::Triangle, 512, 512)
+        .expect("Failed to normalize image");
+
+    c.bench_function("marr_hildreth", |b| {
+        b.iter(|| {
+            // Compute mhHash for the normalized image
+            ImageHash::marr_hildreth(black_box(&normalized_image))
+                .expect("Failed to compute marr_hildreth");
+        });
+    });
+}
+
+fn benchmark_radial_variance(c: &mut Criterion) {
+    let img_path = PathBuf::from("../../imgs/test/single/file000898199107.jpg");
+
+    // Radial variance projects the full image itself
+    let image = open_image(&img_path).expect("Failed to open image");
+
+    c.bench_function("radial_variance", |b| {
+        b.iter(|| {
+            // Compute rvHash for the full image
+            RadialVarianceHash::compute(black_box(&image))
+                .expect("Failed to compute radial_variance");
+        });
+    });
+}
+
+fn benchmark_color_moment(c: &mut Criterion) {
+    let img_path = PathBuf::from("../../imgs/test/single/file000898199107.jpg");
+
+    // Color moments resize and convert the full color image itself
+    let image = open_image(&img_path).expect("Failed to open image");
+
+    c.bench_function("color_moment", |b| {
+        b.iter(|| {
+            // Compute cmHash for the full image
+            ColorMomentHash::compute(black_box(&image)).expect("Failed to compute color_moment");
+        });
+    });
+}
+
+fn benchmark_block_mean(c: &mut Criterion) {
+    let img_path = PathBuf::from("../../imgs/test/single/file000898199107.jpg");
+
+    // Unwrap the image and normalize it outside the benchmark iteration
+    let image = open_image(&img_path).expect("Failed to open image");
+    let normalized_image = normalize(&image, image::imageops::FilterType::Triangle, 256, 256)
+        .expect("Failed to normalize image");
+
+    c.bench_function("block_mean", |b| {
+        b.iter(|| {
+            // Compute bmHash for the normalized image
+            ImageHash::block_mean(black_box(&normalized_image), BlockMeanMode::Disjoint)
+                .expect("Failed to compute block_mean");
+        });
+    });
+}
+
 criterion_group! {
     name = group1;
     config = Criterion::default().sample_size(40);
@@ -179,7 +331,21 @@ criterion_group!(
     benchmark_dhash,
     benchmark_phash,
     benchmark_whash,
-    benchmark_find_duplicates
+    benchmark_pdq,
+    benchmark_blockhash,
+    benchmark_marr_hildreth,
+    benchmark_radial_variance,
+    benchmark_color_moment,
+    benchmark_block_mean,
+    benchmark_find_duplicates,
+    benchmark_multi_index_pairs,
+    benchmark_bktree_find
 );
 
-criterion_main!(group1, group2, group3);
+criterion_group! {
+    name = group4;
+    config = Criterion::default().sample_size(10);
+    targets = benchmark_multi_index_pairs_large
+}
+
+criterion_main!(group1, group2, group3, group4);
@@ -0,0 +1,120 @@
+use crate::dedupe::multi_index_pairs;
+use crate::hashing::ImageHash;
+use std::path::PathBuf;
+
+/// A connected component of duplicate images.
+///
+/// Members are sorted by path and clusters are numbered by their first member,
+/// so the same input always produces the same cluster IDs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateCluster {
+    /// Stable identifier of the cluster.
+    pub id: usize,
+    /// File paths belonging to the cluster, sorted by path.
+    pub members: Vec<PathBuf>,
+    /// The member chosen to represent the cluster (the first member).
+    pub representative: PathBuf,
+}
+
+/// Disjoint-set forest with path halving and union by size.
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return;
+        }
+        if self.size[root_a] < self.size[root_b] {
+            std::mem::swap(&mut root_a, &mut root_b);
+        }
+        self.parent[root_b] = root_a;
+        self.size[root_a] += self.size[root_b];
+    }
+}
+
+/// Groups pairwise matches into connected components.
+///
+/// If A matches B and B matches C, all three end up in the same cluster even when A and C do not match.
+///
+/// Works with any hash type and distance, e.g. the Hamming distances of `multi_index_pairs`
+/// or the `ColorMomentHash` distances of `similar_pairs`.
+///
+/// # Arguments
+///
+/// * `hash_paths` - A slice of hash-path tuples.
+/// * `pairs` - Pairs of indices into `hash_paths` that are considered duplicates, with their distance.
+///
+/// # Returns
+///
+/// * A vector of `DuplicateCluster`s with at least two members, ordered by cluster ID.
+pub fn cluster_pairs<H, D>(
+    hash_paths: &[(H, PathBuf)],
+    pairs: &[(usize, usize, D)],
+) -> Vec<DuplicateCluster> {
+    let mut components = UnionFind::new(hash_paths.len());
+    for &(i, j, _) in pairs {
+        components.union(i, j);
+    }
+
+    let mut groups: Vec<Vec<PathBuf>> = vec![Vec::new(); hash_paths.len()];
+    for (i, (_, path)) in hash_paths.iter().enumerate() {
+        let root = components.find(i);
+        groups[root].push(path.clone());
+    }
+
+    let mut members: Vec<Vec<PathBuf>> = groups
+        .into_iter()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort();
+            group
+        })
+        .collect();
+    members.sort();
+
+    members
+        .into_iter()
+        .enumerate()
+        .map(|(id, members)| DuplicateCluster {
+            id,
+            representative: members[0].clone(),
+            members,
+        })
+        .collect()
+}
+
+/// Clusters images whose hashes are transitively within a Hamming distance threshold.
+///
+/// # Arguments
+///
+/// * `hash_paths` - A slice of hash-path tuples.
+/// * `max_distance` - The maximum Hamming distance for two hashes to be linked.
+///
+/// # Returns
+///
+/// * A vector of `DuplicateCluster`s with at least two members, ordered by cluster ID.
+pub fn cluster_duplicates(
+    hash_paths: &[(ImageHash, PathBuf)],
+    max_distance: u32,
+) -> Vec<DuplicateCluster> {
+    cluster_pairs(hash_paths, &multi_index_pairs(hash_paths, max_distance))
+}
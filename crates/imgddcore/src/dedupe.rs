@@ -1,33 +1,277 @@
-use crate::hashing::ImageHash;
-use crate::normalize;
+use crate::cluster::cluster_pairs;
+use crate::dihedral;
+use crate::hashing::{BlockMeanMode, ImageHash};
+use crate::journal::{Journal, JournalAction};
+use crate::normalize::{NormalizePipeline, NormalizeStep, Trim};
 use anyhow::Error;
 use anyhow::{anyhow, Result};
 use image::imageops::FilterType;
-use image::{DynamicImage, ImageReader};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use walkdir::WalkDir;
 
+/// Bits per bin of `colorhash`, the default of the Python imagehash library.
+const COLORHASH_BINBITS: u32 = 3;
+
 /// Collects hashes for all image files in a directory recursively.
 ///
 /// # Arguments
 ///
 /// * `path` - The directory containing images to process.
 /// * `filter` - The resize filter to use.
-///              Options: `Nearest`, `Triangle`, `CatmullRom`, `Gaussian`, `Lanczos3`.
+///   Options: `Nearest`, `Triangle`, `CatmullRom`, `Gaussian`, `Lanczos3`.
 /// * `algo` - The hashing algorithm to use.
-///              Options: `dhash`, `ahash`, `mhash`, `phash`, `whash`.
+///   Options: `dhash`, `verticaldhash`, `doubledhash`, `ahash`, `mhash`, `phash`, `whash`, `colorhash`,
+///   `pdq`, `blockhash`, `marrhildreth`, `blockmean`, `blockmeanoverlap`.
+/// * `hash_size` - The side length of the hash grid, e.g. `8` for 64-bit hashes and `16` for 256-bit hashes.
+///   `whash` requires a power of two and `blockhash` an even size.
+///   `doubledhash` concatenates both dHash directions, so its hashes are twice as long.
+///   `colorhash` always produces 42-bit hashes, `pdq` and `blockmean` 256-bit hashes,
+///   `marrhildreth` 576-bit hashes and `blockmeanoverlap` 961-bit hashes.
 ///
 /// # Returns
 ///
 /// * A vector of tuples containing the hash value and the corresponding file path.
+///
+/// # Errors
+///
+/// Returns an error if `hash_size` is not supported by `algo`.
 pub fn collect_hashes(
-    path: &PathBuf,
+    path: &Path,
     filter: FilterType,
     algo: &str,
-) -> Result<Vec<(u64, PathBuf)>, Error> {
+    hash_size: u32,
+) -> Result<Vec<(ImageHash, PathBuf)>, Error> {
+    collect_hashes_with(path, algo, hash_size, &NormalizePipeline::new(filter))
+}
+
+/// Collects hashes for all image files in a directory recursively, preprocessing every image
+/// with a custom pipeline.
+///
+/// # Arguments
+///
+/// * `path` - The directory containing images to process.
+/// * `algo` - The hashing algorithm to use, see `collect_hashes`.
+/// * `hash_size` - The side length of the hash grid, see `collect_hashes`.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`.
+///
+/// # Returns
+///
+/// * A vector of tuples containing the hash value and the corresponding file path.
+///
+/// # Errors
+///
+/// Returns an error if `hash_size` is not supported by `algo`.
+pub fn collect_hashes_with(
+    path: &Path,
+    algo: &str,
+    hash_size: u32,
+    pipeline: &NormalizePipeline,
+) -> Result<Vec<(ImageHash, PathBuf)>, Error> {
+    check_hash_size(algo, hash_size)?;
+
+    // Only read the orientation when a step uses it
+    let read_orientation = pipeline.steps.contains(&NormalizeStep::Orient);
+    Ok(collect_with(
+        path,
+        read_orientation,
+        |image, orientation| hash_image_with(image, orientation, algo, hash_size, pipeline),
+    ))
+}
+
+/// Collects hashes for all image files in a directory recursively, cropping uniform borders
+/// such as letterboxing before each image is hashed.
+///
+/// The borders are detected by a `TrimBorder` step with `tolerance`, which replaces any such
+/// step of `pipeline`, so they are measured after the image is turned upright and its alpha is
+/// flattened. See `NormalizePipeline::with_trim`.
+///
+/// # Arguments
+///
+/// * `path` - The directory containing images to process.
+/// * `algo` - The hashing algorithm to use, see `collect_hashes`.
+/// * `hash_size` - The side length of the hash grid, see `collect_hashes`.
+/// * `tolerance` - The largest channel difference still counted as border.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`.
+///
+/// # Returns
+///
+/// * A vector of tuples containing the hash value, the corresponding file path
+///   and the number of pixels trimmed from each edge.
+///
+/// # Errors
+///
+/// Returns an error if `hash_size` is not supported by `algo`.
+pub fn collect_trimmed_hashes(
+    path: &Path,
+    algo: &str,
+    hash_size: u32,
+    tolerance: u8,
+    pipeline: &NormalizePipeline,
+) -> Result<Vec<(ImageHash, PathBuf, Trim)>, Error> {
+    check_hash_size(algo, hash_size)?;
+
+    let pipeline = pipeline.clone().with_trim(Some(tolerance));
+    let read_orientation = pipeline.steps.contains(&NormalizeStep::Orient);
+    let hashes = collect_with(path, read_orientation, |image, orientation| {
+        hash_image_trimmed(image, orientation, algo, hash_size, &pipeline)
+    });
+
+    Ok(hashes
+        .into_iter()
+        .map(|((hash, trim), path)| (hash, path, trim))
+        .collect())
+}
+
+/// Collects PDQ hashes along with their quality scores for all image files in a directory recursively.
+///
+/// `collect_hashes` with `pdq` keeps only the hashes. The quality score counts strong gradients,
+/// and images scoring below 50 carry too little detail to match reliably, so callers can drop
+/// them before searching for duplicates.
+///
+/// # Arguments
+///
+/// * `path` - The directory containing images to process.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`. PDQ skips its `Resize` and `Convert` steps.
+///
+/// # Returns
+///
+/// * A vector of tuples containing the hash value, the corresponding file path
+///   and the quality score from 0 to 100.
+pub fn collect_pdq_hashes(
+    path: &Path,
+    pipeline: &NormalizePipeline,
+) -> Vec<(ImageHash, PathBuf, u32)> {
+    let read_orientation = pipeline.steps.contains(&NormalizeStep::Orient);
+    let hashes = collect_with(path, read_orientation, |image, orientation| {
+        ImageHash::pdq(&*pipeline.run(image, orientation, None)?)
+    });
+
+    hashes
+        .into_iter()
+        .map(|((hash, quality), path)| (hash, path, quality))
+        .collect()
+}
+
+/// Collects canonical hashes for all image files in a directory recursively.
+///
+/// Every image is hashed in all 8 flipped and rotated orientations and the smallest hash is kept,
+/// so mirrored and 90° rotated copies of an image end up with identical hashes.
+///
+/// # Arguments
+///
+/// * `path` - The directory containing images to process.
+/// * `filter` - The resize filter to use.
+/// * `algo` - The hashing algorithm to use, see `collect_hashes`.
+/// * `hash_size` - The side length of the hash grid, see `collect_hashes`.
+///
+/// # Returns
+///
+/// * A vector of tuples containing the canonical hash and the corresponding file path.
+///
+/// # Errors
+///
+/// Returns an error if `hash_size` is not supported by `algo`.
+pub fn collect_canonical_hashes(
+    path: &Path,
+    filter: FilterType,
+    algo: &str,
+    hash_size: u32,
+) -> Result<Vec<(ImageHash, PathBuf)>, Error> {
+    collect_canonical_hashes_with(path, algo, hash_size, &NormalizePipeline::new(filter))
+}
+
+/// Collects canonical hashes for all image files in a directory recursively, preprocessing every
+/// image with a custom pipeline.
+///
+/// The steps in front of the first `Resize` or `Convert` step run before the image is flipped and
+/// rotated, see `dihedral::dihedral_hash_with`.
+///
+/// # Arguments
+///
+/// * `path` - The directory containing images to process.
+/// * `algo` - The hashing algorithm to use, see `collect_hashes`.
+/// * `hash_size` - The side length of the hash grid, see `collect_hashes`.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`.
+///
+/// # Returns
+///
+/// * A vector of tuples containing the canonical hash and the corresponding file path.
+///
+/// # Errors
+///
+/// Returns an error if `hash_size` is not supported by `algo`.
+pub fn collect_canonical_hashes_with(
+    path: &Path,
+    algo: &str,
+    hash_size: u32,
+    pipeline: &NormalizePipeline,
+) -> Result<Vec<(ImageHash, PathBuf)>, Error> {
+    check_hash_size(algo, hash_size)?;
+
+    let read_orientation = pipeline.steps.contains(&NormalizeStep::Orient);
+    Ok(collect_with(
+        path,
+        read_orientation,
+        |image, orientation| {
+            dihedral::canonical_hash_with(image, orientation, algo, hash_size, pipeline)
+        },
+    ))
+}
+
+/// Checks that `hash_size` is supported by `algo`.
+fn check_hash_size(algo: &str, hash_size: u32) -> Result<()> {
+    if hash_size < 2
+        || (algo == "whash" && !hash_size.is_power_of_two())
+        || (algo == "blockhash" && !hash_size.is_multiple_of(2))
+    {
+        return Err(anyhow!(
+            "Unsupported hash size {} for algorithm {}",
+            hash_size,
+            algo
+        ));
+    }
+
+    Ok(())
+}
+
+/// Collects a hash of any type for all image files in a directory recursively.
+///
+/// This is the building block of `collect_hashes` for hashes that are not bit strings,
+/// such as `ColorMomentHash` and `RadialVarianceHash`.
+///
+/// # Arguments
+///
+/// * `path` - The directory containing images to process.
+/// * `hasher` - Computes the hash of a decoded image.
+///
+/// # Returns
+///
+/// * A vector of tuples containing the hash and the corresponding file path.
+///   Files that cannot be opened or hashed are skipped.
+pub fn collect_features<H, F>(path: &Path, hasher: F) -> Vec<(H, PathBuf)>
+where
+    H: Send,
+    F: Fn(&DynamicImage) -> Result<H> + Sync,
+{
+    collect_with(path, false, |image, _| hasher(image))
+}
+
+/// Hashes every image file in a directory recursively, optionally reading its orientation.
+fn collect_with<H, F>(path: &Path, read_orientation: bool, hasher: F) -> Vec<(H, PathBuf)>
+where
+    H: Send,
+    F: Fn(&DynamicImage, Orientation) -> Result<H> + Sync,
+{
     let files: Vec<PathBuf> = WalkDir::new(path)
         .into_iter()
         .filter_map(|entry| entry.ok())
@@ -35,43 +279,133 @@ pub fn collect_hashes(
         .map(|entry| entry.path().to_path_buf())
         .collect();
 
-    let hash_paths: Vec<(u64, PathBuf)> = files
+    files
         .par_iter()
-        .filter_map(|file_path| match open_image(file_path) {
-            Ok(image) => {
-                let hash = match algo {
-                    "dhash" => {
-                        let normalized = normalize::proc(&image, filter, 9, 8).ok()?;
-                        ImageHash::dhash(&normalized).ok()?.get_hash()
-                    }
-                    "ahash" => {
-                        let normalized = normalize::proc(&image, filter, 8, 8).ok()?;
-                        ImageHash::ahash(&normalized).ok()?.get_hash()
-                    }
-                    "mhash" => {
-                        let normalized = normalize::proc(&image, filter, 8, 8).ok()?;
-                        ImageHash::mhash(&normalized).ok()?.get_hash()
-                    }
-                    "phash" => {
-                        let normalized = normalize::proc(&image, filter, 32, 32).ok()?;
-                        ImageHash::phash(&normalized).ok()?.get_hash()
-                    }
-                    "whash" => {
-                        let normalized = normalize::proc(&image, filter, 8, 8).ok()?;
-                        ImageHash::whash(&normalized).ok()?.get_hash()
-                    }
-                    _ => panic!("Unsupported hashing algorithm: {}", algo),
-                };
-                Some((hash, file_path.clone()))
-            }
-            Err(e) => {
-                eprintln!("Failed to open image {}: {}", file_path.display(), e);
-                None
-            }
-        })
-        .collect();
+        .filter_map(
+            |file_path| match decode_image(file_path, read_orientation) {
+                Ok((image, orientation)) => {
+                    let hash = hasher(&image, orientation).ok()?;
+                    Some((hash, file_path.clone()))
+                }
+                Err(e) => {
+                    eprintln!("Failed to open image {}: {}", file_path.display(), e);
+                    None
+                }
+            },
+        )
+        .collect()
+}
+
+/// Normalizes and hashes a single image.
+///
+/// # Arguments
+///
+/// * `image` - The decoded image.
+/// * `filter` - The resize filter to use.
+/// * `algo` - The hashing algorithm to use.
+///   Options: `dhash`, `verticaldhash`, `doubledhash`, `ahash`, `mhash`, `phash`, `whash`, `colorhash`,
+///   `pdq`, `blockhash`, `marrhildreth`, `blockmean`, `blockmeanoverlap`.
+/// * `hash_size` - The side length of the hash grid. Ignored by `colorhash`, `pdq`, `marrhildreth`
+///   and the `blockmean` variants.
+///
+/// # Returns
+///
+/// * The `ImageHash` of the image.
+///
+/// # Errors
+///
+/// Returns an error if the image cannot be normalized or hashed.
+pub fn hash_image(
+    image: &DynamicImage,
+    filter: FilterType,
+    algo: &str,
+    hash_size: u32,
+) -> Result<ImageHash> {
+    hash_image_with(
+        image,
+        Orientation::NoTransforms,
+        algo,
+        hash_size,
+        &NormalizePipeline::new(filter),
+    )
+}
+
+/// Preprocesses a single image with a custom pipeline and hashes it.
+///
+/// # Arguments
+///
+/// * `image` - The decoded image.
+/// * `orientation` - The orientation recorded in the image metadata, used by `NormalizeStep::Orient`.
+/// * `algo` - The hashing algorithm to use, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid, see `hash_image`.
+/// * `pipeline` - The preprocessing steps. Algorithms that hash the full image skip its
+///   `Resize` and `Convert` steps.
+///
+/// # Returns
+///
+/// * The `ImageHash` of the image.
+///
+/// # Errors
+///
+/// Returns an error if the image cannot be normalized or hashed.
+pub fn hash_image_with(
+    image: &DynamicImage,
+    orientation: Orientation,
+    algo: &str,
+    hash_size: u32,
+    pipeline: &NormalizePipeline,
+) -> Result<ImageHash> {
+    hash_image_trimmed(image, orientation, algo, hash_size, pipeline).map(|(hash, _trim)| hash)
+}
+
+/// Hashes an image like `hash_image_with`, also returning the border removed by the
+/// `TrimBorder` steps of `pipeline`.
+fn hash_image_trimmed(
+    image: &DynamicImage,
+    orientation: Orientation,
+    algo: &str,
+    hash_size: u32,
+    pipeline: &NormalizePipeline,
+) -> Result<(ImageHash, Trim)> {
+    // Every run of the pipeline trims the same border, so the last one seen is reported
+    let trim = Cell::new(Trim::default());
+    let run = |size| {
+        let (current, trimmed) = pipeline.run_trimmed(image, orientation, size)?;
+        trim.set(trimmed);
+        Ok::<_, Error>(current)
+    };
+    let normalize = |width, height| run(Some((width, height))).map(Cow::into_owned);
+    let full_image = || run(None);
 
-    Ok(hash_paths)
+    let hash = match algo {
+        "dhash" => ImageHash::dhash(&normalize(hash_size + 1, hash_size)?),
+        "verticaldhash" => ImageHash::dhash_vertical(&normalize(hash_size, hash_size + 1)?),
+        "doubledhash" => {
+            let horizontal = ImageHash::dhash(&normalize(hash_size + 1, hash_size)?)?;
+            let vertical = ImageHash::dhash_vertical(&normalize(hash_size, hash_size + 1)?)?;
+            Ok(ImageHash::from_bits(
+                horizontal.bits().chain(vertical.bits()),
+            ))
+        }
+        "ahash" => ImageHash::ahash(&normalize(hash_size, hash_size)?),
+        "mhash" => ImageHash::mhash(&normalize(hash_size, hash_size)?),
+        "phash" => ImageHash::phash(&normalize(hash_size * 4, hash_size * 4)?),
+        "whash" => ImageHash::whash(&normalize(hash_size, hash_size)?),
+        // Works on the full color image, since the `Convert` step discards color
+        "colorhash" => ImageHash::colorhash(&*full_image()?, COLORHASH_BINBITS),
+        // PDQ applies its own filtering and downsampling to the full image.
+        // `collect_pdq_hashes` keeps the quality score dropped here
+        "pdq" => ImageHash::pdq(&*full_image()?).map(|(hash, _quality)| hash),
+        // Blockhash averages blocks of the full image instead of resizing it
+        "blockhash" => ImageHash::blockhash(&*full_image()?, hash_size),
+        "marrhildreth" => ImageHash::marr_hildreth(&normalize(512, 512)?),
+        "blockmean" => ImageHash::block_mean(&normalize(256, 256)?, BlockMeanMode::Disjoint),
+        "blockmeanoverlap" => {
+            ImageHash::block_mean(&normalize(256, 256)?, BlockMeanMode::Overlapping)
+        }
+        _ => panic!("Unsupported hashing algorithm: {}", algo),
+    }?;
+    Ok((hash, trim.get()))
 }
 
 /// Sorts a vector of hashes by hash value.
@@ -80,8 +414,8 @@ pub fn collect_hashes(
 ///
 /// * `hash_paths` - A mutable reference to a vector of hash-path tuples.
 #[inline]
-pub fn sort_hashes(hash_paths: &mut Vec<(u64, PathBuf)>) {
-    hash_paths.sort_by_key(|(hash, _)| *hash);
+pub fn sort_hashes(hash_paths: &mut [(ImageHash, PathBuf)]) {
+    hash_paths.sort_by(|(hash1, _), (hash2, _)| hash1.cmp(hash2));
 }
 
 /// Opens an image file and decodes it.
@@ -105,46 +439,855 @@ pub fn open_image(file_path: &PathBuf) -> Result<DynamicImage> {
         .map_err(|e| anyhow!("Error decoding image {}: {}", file_path.display(), e))
 }
 
-/// Identifies duplicate images based on hash values.
+/// Opens an image file and decodes it, optionally turning it upright.
+///
+/// Cameras often store photos in sensor orientation and record the rotation in an EXIF
+/// `Orientation` tag instead. Applying it makes such a photo hash like an upright copy of itself.
+///
+/// # Arguments
+///
+/// * `file_path` - The path to the image file.
+/// * `apply_orientation` - Whether to rotate and flip the image according to its metadata.
+///
+/// # Returns
+///
+/// * A `DynamicImage` if the file is successfully opened and decoded.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or decoded.
+pub fn open_image_with(file_path: &Path, apply_orientation: bool) -> Result<DynamicImage> {
+    let (mut image, orientation) = decode_image(file_path, apply_orientation)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Opens an image file and decodes it along with the orientation recorded in its metadata.
+///
+/// Images without orientation metadata, or decoded with `read_orientation` unset,
+/// report `Orientation::NoTransforms`.
+fn decode_image(file_path: &Path, read_orientation: bool) -> Result<(DynamicImage, Orientation)> {
+    let reader = ImageReader::open(file_path)
+        .map_err(|e| anyhow!("Error opening image {}: {}", file_path.display(), e))?;
+    if !read_orientation {
+        let image = reader
+            .decode()
+            .map_err(|e| anyhow!("Error decoding image {}: {}", file_path.display(), e))?;
+        return Ok((image, Orientation::NoTransforms));
+    }
+
+    let mut decoder = reader
+        .into_decoder()
+        .map_err(|e| anyhow!("Error decoding image {}: {}", file_path.display(), e))?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let image = DynamicImage::from_decoder(decoder)
+        .map_err(|e| anyhow!("Error decoding image {}: {}", file_path.display(), e))?;
+
+    Ok((image, orientation))
+}
+
+/// Computes the Hamming distance between two hash values.
+///
+/// # Arguments
+///
+/// * `hash1` - The first hash value.
+/// * `hash2` - The second hash value.
+///
+/// # Returns
+///
+/// * The number of bits that differ between the two hashes.
+#[inline]
+pub fn hamming_distance(hash1: &ImageHash, hash2: &ImageHash) -> u32 {
+    hash1.hamming_distance(hash2)
+}
+
+/// Splits a hash of `len` bits into `count` contiguous substrings of near-equal width.
+///
+/// # Returns
+///
+/// * A vector of `(start, width)` pairs, one per substring.
+fn hash_bands(len: usize, count: usize) -> Vec<(usize, usize)> {
+    let mut bands = Vec::with_capacity(count);
+    let mut start = 0;
+    for band in 0..count {
+        let width = len / count + usize::from(band < len % count);
+        bands.push((start, width));
+        start += width;
+    }
+    bands
+}
+
+/// Enumerates every left-aligned mask of at most `radius` set bits within the top `width` bits.
+fn flip_masks(width: usize, radius: usize) -> Vec<u64> {
+    let mut masks = vec![0u64];
+    let mut frontier = vec![(0u64, 0usize)];
+    for _ in 0..radius.min(width) {
+        let mut next = Vec::new();
+        for &(mask, lowest) in &frontier {
+            // Only set bits after the last one, so each mask is produced once
+            for bit in lowest..width {
+                next.push((mask | (1u64 << (63 - bit)), bit + 1));
+            }
+        }
+        masks.extend(next.iter().map(|&(mask, _)| mask));
+        frontier = next;
+    }
+    masks
+}
+
+/// Estimates the work per hash of a multi-index search with `count` substrings.
+///
+/// Every substring probes all buckets within `max_distance / count` bits, and every probe
+/// verifies the hashes expected in a bucket of `n` hashes spread over its `2^width` values.
+fn probe_cost(len: usize, count: usize, max_distance: usize, n: usize) -> f64 {
+    let width = len.div_ceil(count);
+    let radius = max_distance / count;
+    let mut masks = 0.0;
+    let mut combinations = 1.0;
+    for k in 0..=radius.min(width) {
+        masks += combinations;
+        combinations *= (width - k) as f64 / (k + 1) as f64;
+    }
+    count as f64 * masks * (1.0 + n as f64 / 2f64.powi(width as i32))
+}
+
+/// Finds every pair of hashes within a Hamming distance threshold using multi-index hashing.
+///
+/// Each hash is split into `m` substrings. By the pigeonhole principle, two hashes within
+/// `max_distance` bits differ by at most `max_distance / m` bits on at least one substring, so
+/// every hash only probes the buckets within that radius of its own substrings and verifies the
+/// hashes found there. `m` is chosen to minimize the expected work, which puts substrings at
+/// about `log2(n)` bits so that most buckets hold a single hash.
+///
+/// Every hash probes `sum(C(w, k) for k <= max_distance / m)` buckets per substring of `w` bits.
+/// This stays small while `max_distance` is a small fraction of the hash length, e.g. 254 buckets
+/// per substring for 64-bit hashes at `n = 10^7` and `max_distance = 8`, and the search is linear
+/// in `n` with that constant. As `max_distance` approaches half the hash length, most pairs end up
+/// verified and the search degrades towards the quadratic scan of `similar_pairs`.
 ///
 /// # Arguments
 ///
 /// * `hash_paths` - A slice of hash-path tuples.
-/// * `remove` - A boolean indicating whether to delete duplicate files.
+/// * `max_distance` - The maximum Hamming distance of a reported pair.
 ///
 /// # Returns
 ///
-/// * A hashmap mapping hash values to lists of duplicate file paths.
+/// * A sorted vector of `(i, j, distance)` tuples, where `i < j` index into `hash_paths`.
+pub fn multi_index_pairs(
+    hash_paths: &[(ImageHash, PathBuf)],
+    max_distance: u32,
+) -> Vec<(usize, usize, u32)> {
+    let len = hash_paths
+        .iter()
+        .map(|(hash, _)| hash.len())
+        .max()
+        .unwrap_or(0);
+
+    // Every pair of hashes is within `len` bits
+    if max_distance as usize >= len {
+        return (0..hash_paths.len())
+            .flat_map(|i| ((i + 1)..hash_paths.len()).map(move |j| (i, j)))
+            .map(|(i, j)| (i, j, hamming_distance(&hash_paths[i].0, &hash_paths[j].0)))
+            .filter(|&(_, _, distance)| distance <= max_distance)
+            .collect();
+    }
+
+    // Substrings must fit a word, and more than `max_distance + 1` of them gain nothing
+    let fewest = len.div_ceil(64);
+    let count = (fewest..=len.min(max_distance as usize + 1).max(fewest))
+        .min_by(|&a, &b| {
+            let cost = |count| probe_cost(len, count, max_distance as usize, hash_paths.len());
+            cost(a).total_cmp(&cost(b))
+        })
+        .unwrap_or(fewest);
+    let radius = max_distance as usize / count;
+    let bands = hash_bands(len, count);
+
+    let words: Vec<Vec<u64>> = hash_paths
+        .iter()
+        .map(|(hash, _)| {
+            bands
+                .iter()
+                .map(|&(start, width)| hash.band_word(start, width))
+                .collect()
+        })
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (band, &(_, width)) in bands.iter().enumerate() {
+        let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (i, hash_words) in words.iter().enumerate() {
+            buckets.entry(hash_words[band]).or_default().push(i);
+        }
+
+        let masks = flip_masks(width, radius);
+        pairs.par_extend((0..hash_paths.len()).into_par_iter().flat_map_iter(|i| {
+            let mut found = Vec::new();
+            for mask in &masks {
+                let Some(members) = buckets.get(&(words[i][band] ^ mask)) else {
+                    continue;
+                };
+                for &j in members.iter().filter(|&&j| j > i) {
+                    // Only report a pair from the first substring it is found through
+                    if (0..band)
+                        .any(|b| (words[i][b] ^ words[j][b]).count_ones() as usize <= radius)
+                    {
+                        continue;
+                    }
+
+                    let distance = hamming_distance(&hash_paths[i].0, &hash_paths[j].0);
+                    if distance <= max_distance {
+                        found.push((i, j, distance));
+                    }
+                }
+            }
+            found
+        }));
+    }
+
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Finds every pair of hashes within a threshold of a custom distance function.
+///
+/// Unlike `multi_index_pairs`, this compares every pair of hashes, so it works with any hash type
+/// and metric, e.g. `ColorMomentHash::distance` or one minus `RadialVarianceHash::cross_correlation`.
+///
+/// # Arguments
+///
+/// * `hash_paths` - A slice of hash-path tuples.
+/// * `max_distance` - The maximum distance of a reported pair.
+/// * `distance` - Computes the distance between two hashes.
+///
+/// # Returns
+///
+/// * A vector of `(i, j, distance)` tuples sorted by `i` then `j`, where `i < j` index into `hash_paths`.
+pub fn similar_pairs<H, D>(
+    hash_paths: &[(H, PathBuf)],
+    max_distance: f64,
+    distance: D,
+) -> Vec<(usize, usize, f64)>
+where
+    H: Sync,
+    D: Fn(&H, &H) -> f64 + Sync,
+{
+    (0..hash_paths.len())
+        .into_par_iter()
+        .flat_map_iter(|i| {
+            let distance = &distance;
+            ((i + 1)..hash_paths.len())
+                .map(move |j| (i, j, distance(&hash_paths[i].0, &hash_paths[j].0)))
+                .filter(|&(_, _, d)| d <= max_distance)
+        })
+        .collect()
+}
+
+/// Policy deciding which file of a duplicate group survives removal.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum KeepPolicy {
+    /// Keep the first path of the group, which is sorted by path.
+    #[default]
+    First,
+    /// Keep the image with the most pixels.
+    LargestResolution,
+    /// Keep the largest file on disk.
+    LargestFileSize,
+    /// Keep the file with the oldest modification time.
+    Oldest,
+    /// Keep the file with the newest modification time.
+    Newest,
+    /// Keep the file whose format appears first in the list, e.g. PNG over JPEG.
+    PreferredFormat(Vec<ImageFormat>),
+    /// Keep the file located under the directory that appears first in the list.
+    PathPriority(Vec<PathBuf>),
+}
+
+/// Selects the file of a duplicate group that should be kept.
+///
+/// Ties, and files whose metadata cannot be read, fall back to the earliest path in the group.
+///
+/// # Arguments
+///
+/// * `paths` - The file paths of a duplicate group.
+/// * `policy` - The `KeepPolicy` to apply.
+///
+/// # Returns
+///
+/// * The index of the surviving path, or `None` if `paths` is empty.
+pub fn select_survivor(paths: &[PathBuf], policy: &KeepPolicy) -> Option<usize> {
+    let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let rank = |position: Option<usize>, len: usize| position.unwrap_or(len);
+
+    // `min_by_key` returns the first minimum, so ties keep the earliest path
+    let indices = 0..paths.len();
+    match policy {
+        KeepPolicy::First => indices.min(),
+        KeepPolicy::LargestResolution => indices.min_by_key(|&i| {
+            let pixels = image::image_dimensions(&paths[i])
+                .map(|(width, height)| width as u64 * height as u64)
+                .unwrap_or(0);
+            Reverse(pixels)
+        }),
+        KeepPolicy::LargestFileSize => {
+            indices.min_by_key(|&i| Reverse(fs::metadata(&paths[i]).map(|m| m.len()).unwrap_or(0)))
+        }
+        KeepPolicy::Oldest => indices.min_by_key(|&i| {
+            let time: Option<SystemTime> = modified(&paths[i]);
+            (time.is_none(), time)
+        }),
+        KeepPolicy::Newest => indices.min_by_key(|&i| {
+            let time: Option<SystemTime> = modified(&paths[i]);
+            (time.is_none(), Reverse(time))
+        }),
+        KeepPolicy::PreferredFormat(formats) => indices.min_by_key(|&i| {
+            let format = ImageFormat::from_path(&paths[i]).ok();
+            rank(
+                formats.iter().position(|f| Some(*f) == format),
+                formats.len(),
+            )
+        }),
+        KeepPolicy::PathPriority(directories) => indices.min_by_key(|&i| {
+            rank(
+                directories.iter().position(|dir| paths[i].starts_with(dir)),
+                directories.len(),
+            )
+        }),
+    }
+}
+
+/// The planned outcome for one group of duplicates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedAction {
+    /// The file that is kept.
+    pub keep: PathBuf,
+    /// The files that are removed, in group order.
+    pub remove: Vec<PathBuf>,
+    /// Why `keep` was chosen over the removed files.
+    pub reason: String,
+    /// The total size in bytes of the removed files.
+    pub bytes_reclaimed: u64,
+}
+
+/// Describes why a `KeepPolicy` selected the kept file.
+fn keep_reason(policy: &KeepPolicy) -> &'static str {
+    match policy {
+        KeepPolicy::First => "first path in group",
+        KeepPolicy::LargestResolution => "largest resolution",
+        KeepPolicy::LargestFileSize => "largest file size",
+        KeepPolicy::Oldest => "oldest modification time",
+        KeepPolicy::Newest => "newest modification time",
+        KeepPolicy::PreferredFormat(_) => "preferred format",
+        KeepPolicy::PathPriority(_) => "path priority",
+    }
+}
+
+/// A file belonging to a `DuplicateGroup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateMember {
+    /// The file path.
+    pub path: PathBuf,
+    /// The hash of the file.
+    pub hash: ImageHash,
+    /// The size of the file in bytes, or `0` if its metadata cannot be read.
+    pub file_size: u64,
+    /// The width and height of the image, or `None` if its header cannot be read.
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl DuplicateMember {
+    /// Creates a member, reading its file size and image dimensions from disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file path.
+    /// * `hash` - The hash of the file.
+    pub fn new(path: PathBuf, hash: ImageHash) -> Self {
+        let file_size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let dimensions = image::image_dimensions(&path).ok();
+
+        Self {
+            path,
+            hash,
+            file_size,
+            dimensions,
+        }
+    }
+}
+
+/// A group of duplicate images.
+///
+/// Members are unique and sorted by path, so the same input always produces the same group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    /// The hash the group was matched on. For near-duplicates, the hash of the first path found.
+    pub hash: ImageHash,
+    /// The ID of the `DuplicateCluster` the group belongs to, see `cluster_pairs`.
+    /// Near-duplicate groups that are transitively linked share a cluster ID.
+    pub cluster_id: usize,
+    /// The files of the group, sorted by path.
+    pub members: Vec<DuplicateMember>,
+}
+
+impl DuplicateGroup {
+    /// Creates a group from hash-path tuples, dropping repeated paths and sorting by path.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash the group was matched on.
+    /// * `cluster_id` - The ID of the cluster the group belongs to.
+    /// * `members` - The hash-path tuples of the group.
+    pub fn new(hash: ImageHash, cluster_id: usize, members: Vec<(ImageHash, PathBuf)>) -> Self {
+        let mut members = members;
+        members.sort_by(|a, b| a.1.cmp(&b.1));
+        members.dedup_by(|a, b| a.1 == b.1);
+
+        Self {
+            hash,
+            cluster_id,
+            members: members
+                .into_iter()
+                .map(|(hash, path)| DuplicateMember::new(path, hash))
+                .collect(),
+        }
+    }
+
+    /// Returns the file paths of the group, sorted by path.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.members
+            .iter()
+            .map(|member| member.path.clone())
+            .collect()
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if the group has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// Plans the outcome of a single duplicate group.
+fn plan_group(group: &DuplicateGroup, keep: &KeepPolicy) -> Option<PlannedAction> {
+    let paths = group.paths();
+    let survivor = select_survivor(&paths, keep)?;
+
+    let (remove, bytes_reclaimed) = group
+        .members
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != survivor)
+        .fold((Vec::new(), 0), |(mut remove, bytes), (_, member)| {
+            remove.push(member.path.clone());
+            (remove, bytes + member.file_size)
+        });
+
+    Some(PlannedAction {
+        keep: paths[survivor].clone(),
+        remove,
+        reason: keep_reason(keep).to_string(),
+        bytes_reclaimed,
+    })
+}
+
+/// Builds an action plan for duplicate groups without touching the filesystem.
+///
+/// # Arguments
+///
+/// * `duplicates` - The duplicate groups returned by `find_duplicates`.
+/// * `keep` - The `KeepPolicy` selecting which file of each group is kept.
+///
+/// # Returns
+///
+/// * A vector of `PlannedAction`s, one per group, sorted by kept path.
+pub fn plan_duplicates(duplicates: &[DuplicateGroup], keep: &KeepPolicy) -> Vec<PlannedAction> {
+    let mut plan: Vec<PlannedAction> = duplicates
+        .iter()
+        .filter_map(|group| plan_group(group, keep))
+        .collect();
+
+    plan.sort_by(|a, b| a.keep.cmp(&b.keep));
+    plan
+}
+
+/// Action applied to every duplicate that is not kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DuplicateAction {
+    /// Leave files untouched and only report duplicates.
+    #[default]
+    Report,
+    /// Delete duplicates permanently.
+    Remove,
+    /// Move duplicates into `dir`, preserving their path relative to the scanned `root`.
+    Quarantine { root: PathBuf, dir: PathBuf },
+    /// Replace duplicates with a link to the kept file, so every original path keeps existing.
+    Link(LinkKind),
+}
+
+/// The kind of link that replaces a duplicate with `DuplicateAction::Link`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// A hard link sharing the kept file's data. Both files must be on the same filesystem.
+    Hard,
+    /// A symbolic link pointing at the absolute path of the kept file.
+    Symbolic,
+    /// A copy-on-write clone of the kept file. Requires filesystem support (Btrfs, XFS, APFS, ReFS).
+    Reflink,
+}
+
+/// Replaces a file with a link to another file.
+///
+/// The link is created next to `path` under a temporary name and then renamed over it,
+/// so `path` is left untouched if the link cannot be created. The temporary name carries the
+/// process id and a counter, skipping names that already exist, such as leftovers of an
+/// interrupted run.
+///
+/// # Arguments
+///
+/// * `path` - The file to replace.
+/// * `target` - The file the link points to.
+/// * `kind` - The kind of link to create.
 ///
 /// # Errors
 ///
-/// Returns an error if a file fails to be removed when `remove` is set to `true`.
+/// Returns an error if the link cannot be created, e.g. when hard linking across filesystems
+/// or reflinking on a filesystem without copy-on-write support.
+pub fn link_file(path: &Path, target: &Path, kind: LinkKind) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid file path {}", path.display()))?;
+
+    let mut attempt = 0;
+    let temporary = loop {
+        let mut name = file_name.to_os_string();
+        name.push(format!(".imgdd-link-{}-{}", std::process::id(), attempt));
+        let temporary = path.with_file_name(name);
+
+        let linked = match kind {
+            LinkKind::Hard => fs::hard_link(target, &temporary),
+            LinkKind::Symbolic => {
+                fs::canonicalize(target).and_then(|target| symlink(&target, &temporary))
+            }
+            LinkKind::Reflink => reflink_copy::reflink(target, &temporary),
+        };
+        match linked {
+            Ok(()) => break temporary,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => {
+                return Err(anyhow!(
+                    "Error linking file {} to {}: {}",
+                    path.display(),
+                    target.display(),
+                    e
+                ))
+            }
+        }
+    };
+
+    fs::rename(&temporary, path).map_err(|e| {
+        let _ = fs::remove_file(&temporary);
+        anyhow!("Error replacing file {}: {}", path.display(), e)
+    })
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// Checks that a quarantine directory lies outside of the scanned root directory.
+///
+/// Files quarantined inside `root` would be hashed again on the next scan and moved once more.
+///
+/// # Errors
+///
+/// Returns an error if `dir` is `root` or one of its subdirectories.
+pub fn check_quarantine_dir(root: &Path, dir: &Path) -> Result<()> {
+    let (root, dir) = (resolve_path(root), resolve_path(dir));
+    if dir.starts_with(&root) {
+        return Err(anyhow!(
+            "Quarantine directory {} is inside the scanned directory {}",
+            dir.display(),
+            root.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves symbolic links in a path that may not exist yet through its closest existing ancestor.
+fn resolve_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    for ancestor in absolute.ancestors() {
+        if let (Ok(resolved), Ok(rest)) = (ancestor.canonicalize(), absolute.strip_prefix(ancestor))
+        {
+            return resolved.join(rest);
+        }
+    }
+    absolute
+}
+
+/// Moves a file into a quarantine directory, preserving its path relative to `root`.
+///
+/// `root/a/b/c.jpg` is moved to `dir/a/b/c.jpg`. If that file already exists, a numeric suffix is
+/// added (`c_1.jpg`, `c_2.jpg`, ...). Moves across filesystems fall back to copy and delete.
+///
+/// # Arguments
+///
+/// * `path` - The file to quarantine.
+/// * `root` - The scanned root directory the relative path is computed from.
+/// * `dir` - The quarantine directory.
+///
+/// # Returns
+///
+/// * The path the file was moved to.
+///
+/// # Errors
+///
+/// Returns an error if `dir` is inside `root`, see `check_quarantine_dir`, or if the destination
+/// cannot be created or the file cannot be moved.
+pub fn quarantine_file(path: &Path, root: &Path, dir: &Path) -> Result<PathBuf> {
+    let destination = quarantine_destination(path, root, dir)?;
+    move_file(path, &destination)?;
+    Ok(destination)
+}
+
+/// Chooses the path `quarantine_file` moves a file to and creates its parent directory.
+///
+/// # Arguments
+///
+/// * `path` - The file to quarantine.
+/// * `root` - The scanned root directory the relative path is computed from.
+/// * `dir` - The quarantine directory.
+///
+/// # Returns
+///
+/// * A path inside `dir` that does not exist yet.
+///
+/// # Errors
+///
+/// Returns an error if `dir` is inside `root`, see `check_quarantine_dir`, or if the parent
+/// directory of the destination cannot be created.
+pub fn quarantine_destination(path: &Path, root: &Path, dir: &Path) -> Result<PathBuf> {
+    check_quarantine_dir(root, dir)?;
+
+    // Files outside of `root` keep only their file name
+    let relative = match path.strip_prefix(root) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => PathBuf::from(
+            path.file_name()
+                .ok_or_else(|| anyhow!("Invalid file path {}", path.display()))?,
+        ),
+    };
+
+    let mut destination = dir.join(&relative);
+    let stem = destination.file_stem().unwrap_or_default().to_os_string();
+    let extension = destination.extension().map(|ext| ext.to_os_string());
+    let mut suffix = 1;
+    while destination.exists() {
+        let mut name = stem.clone();
+        name.push(format!("_{}", suffix));
+        if let Some(ext) = &extension {
+            name.push(".");
+            name.push(ext);
+        }
+        destination.set_file_name(name);
+        suffix += 1;
+    }
+
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| anyhow!("Error creating directory {}: {}", parent.display(), e))?;
+    }
+
+    Ok(destination)
+}
+
+/// Moves a file, falling back to copy and delete when it crosses filesystems.
+///
+/// Any other rename error is returned as is. If the original cannot be deleted after copying,
+/// the copy is removed again, so the file is never left in both places.
+pub(crate) fn move_file(from: &Path, to: &Path) -> Result<()> {
+    let error = |e: std::io::Error| {
+        anyhow!(
+            "Error moving file {} to {}: {}",
+            from.display(),
+            to.display(),
+            e
+        )
+    };
+    match fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {}
+        Err(e) => return Err(error(e)),
+    }
+
+    if let Err(e) = fs::copy(from, to) {
+        let _ = fs::remove_file(to);
+        return Err(error(e));
+    }
+    if let Err(e) = fs::remove_file(from) {
+        let _ = fs::remove_file(to);
+        return Err(anyhow!("Error removing file {}: {}", from.display(), e));
+    }
+    Ok(())
+}
+
+/// Identifies duplicate images based on hash values.
+///
+/// # Arguments
+///
+/// * `hash_paths` - A slice of hash-path tuples, sorted with `sort_hashes`.
+/// * `max_distance` - The maximum Hamming distance for two hashes to be considered duplicates.
+///   A value of `0` only matches identical hashes. Larger values are searched with `multi_index_pairs`.
+/// * `action` - The `DuplicateAction` applied to every duplicate that is not kept.
+/// * `keep` - The `KeepPolicy` selecting which file of each group is kept.
+/// * `journal` - An optional `Journal` recording every remove, move or link. Each entry is written
+///   before its operation is applied and reverted if the operation fails.
+///
+/// # Returns
+///
+/// * A vector of `DuplicateGroup`s with at least two members, sorted by their first path.
+///   For near-duplicates, each group is matched on the hash of its first path found, and groups
+///   that are transitively linked share a cluster ID.
+///
+/// # Errors
+///
+/// Returns an error if the journal cannot be written or the quarantine directory is inside the
+/// scanned root, see `check_quarantine_dir`.
+/// Files that fail to be removed, moved or linked are reported and skipped.
 pub fn find_duplicates(
-    hash_paths: &[(u64, PathBuf)],
-    remove: bool,
-) -> Result<HashMap<u64, Vec<PathBuf>>, Error> {
-    let mut duplicates_map: HashMap<u64, Vec<PathBuf>> = HashMap::new();
-
-    for window in hash_paths.windows(2) {
-        if let [(hash1, path1), (hash2, path2)] = window {
-            if hash1 == hash2 {
-                duplicates_map
-                    .entry(*hash1)
-                    .or_insert_with(Vec::new)
-                    .extend(vec![path1.clone(), path2.clone()]);
+    hash_paths: &[(ImageHash, PathBuf)],
+    max_distance: u32,
+    action: &DuplicateAction,
+    keep: &KeepPolicy,
+    mut journal: Option<&mut Journal>,
+) -> Result<Vec<DuplicateGroup>, Error> {
+    if let DuplicateAction::Quarantine { root, dir } = action {
+        check_quarantine_dir(root, dir)?;
+    }
+
+    let mut candidates: Vec<(ImageHash, Vec<(ImageHash, PathBuf)>)> = Vec::new();
+    let mut pairs: Vec<(usize, usize, u32)> = Vec::new();
+
+    if max_distance == 0 {
+        let mut start = 0;
+        for run in hash_paths.chunk_by(|(hash1, _), (hash2, _)| hash1 == hash2) {
+            if run.len() > 1 {
+                candidates.push((run[0].0.clone(), run.to_vec()));
+                pairs.extend((start + 1..start + run.len()).map(|j| (start, j, 0)));
+            }
+            start += run.len();
+        }
+    } else {
+        // Group every remaining path within `max_distance` of the first ungrouped path
+        pairs = multi_index_pairs(hash_paths, max_distance);
+        let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); hash_paths.len()];
+        for &(i, j, _) in &pairs {
+            neighbours[i].push(j);
+        }
+
+        let mut grouped = vec![false; hash_paths.len()];
+        for (i, anchor) in hash_paths.iter().enumerate() {
+            if grouped[i] {
+                continue;
+            }
+
+            let mut group = vec![anchor.clone()];
+            for &j in &neighbours[i] {
+                if !grouped[j] {
+                    grouped[j] = true;
+                    group.push(hash_paths[j].clone());
+                }
+            }
+
+            if group.len() > 1 {
+                candidates.push((anchor.0.clone(), group));
             }
         }
     }
 
-    if remove {
-        for paths in duplicates_map.values() {
-            for path in paths.iter().skip(1) {
-                if let Err(e) = fs::remove_file(path) {
-                    eprintln!("Failed to remove file {}: {}", path.display(), e);
+    let clusters = cluster_pairs(hash_paths, &pairs);
+    let cluster_ids: HashMap<&PathBuf, usize> = clusters
+        .iter()
+        .flat_map(|cluster| cluster.members.iter().map(move |path| (path, cluster.id)))
+        .collect();
+
+    let mut groups: Vec<DuplicateGroup> = candidates
+        .into_par_iter()
+        .map(|(hash, members)| {
+            let cluster_id = cluster_ids[&members[0].1];
+            DuplicateGroup::new(hash, cluster_id, members)
+        })
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort_by(|a, b| a.members[0].path.cmp(&b.members[0].path));
+
+    if *action != DuplicateAction::Report {
+        for group in &groups {
+            let Some(planned) = plan_group(group, keep) else {
+                continue;
+            };
+
+            for member in group.members.iter().filter(|m| m.path != planned.keep) {
+                let path = &member.path;
+                let (journal_action, destination) = match action {
+                    DuplicateAction::Report => continue,
+                    DuplicateAction::Remove => (JournalAction::Remove, None),
+                    DuplicateAction::Quarantine { root, dir } => {
+                        match quarantine_destination(path, root, dir) {
+                            Ok(destination) => (JournalAction::Move, Some(destination)),
+                            Err(e) => {
+                                eprintln!("Failed to quarantine file {}: {}", path.display(), e);
+                                continue;
+                            }
+                        }
+                    }
+                    DuplicateAction::Link(_) => (JournalAction::Link, None),
+                };
+
+                // Journal the operation before applying it, so the journal covers every change
+                if let Some(journal) = journal.as_deref_mut() {
+                    journal.record(
+                        journal_action,
+                        path,
+                        destination.as_deref(),
+                        &planned.keep,
+                        &member.hash,
+                    )?;
+                }
+
+                let outcome = match (action, &destination) {
+                    (DuplicateAction::Quarantine { .. }, Some(destination)) => {
+                        move_file(path, destination).map_err(|e| {
+                            anyhow!("Failed to quarantine file {}: {}", path.display(), e)
+                        })
+                    }
+                    (DuplicateAction::Link(kind), _) => link_file(path, &planned.keep, *kind)
+                        .map_err(|e| anyhow!("Failed to link file {}: {}", path.display(), e)),
+                    _ => fs::remove_file(path)
+                        .map_err(|e| anyhow!("Failed to remove file {}: {}", path.display(), e)),
+                };
+
+                if let Err(e) = outcome {
+                    eprintln!("{}", e);
+                    if let Some(journal) = journal.as_deref_mut() {
+                        if let Err(e) = journal.revert() {
+                            eprintln!("{}", e);
+                        }
+                    }
                 }
             }
         }
     }
 
-    Ok(duplicates_map)
+    Ok(groups)
 }
@@ -0,0 +1,292 @@
+use crate::dedupe::hash_image_with;
+use crate::hashing::ImageHash;
+use crate::normalize::NormalizePipeline;
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::DynamicImage;
+
+/// One of the 8 symmetries of a rectangle: the rotations by multiples of 90° and the mirror images.
+///
+/// Every transform is a horizontal flip or not, followed by a number of clockwise quarter turns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    /// The image itself.
+    Identity,
+    /// Rotation by 90° clockwise.
+    Rotate90,
+    /// Rotation by 180°.
+    Rotate180,
+    /// Rotation by 270° clockwise.
+    Rotate270,
+    /// Mirror image across the vertical axis.
+    FlipHorizontal,
+    /// Mirror image across the anti-diagonal.
+    Transverse,
+    /// Mirror image across the horizontal axis.
+    FlipVertical,
+    /// Mirror image across the main diagonal.
+    Transpose,
+}
+
+impl Transform {
+    /// Every transform in declaration order, by whether it flips and then by its quarter turns.
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::Transverse,
+        Transform::FlipVertical,
+        Transform::Transpose,
+    ];
+
+    /// Returns the clockwise quarter turns and whether the image is flipped horizontally first.
+    fn parts(self) -> (u8, bool) {
+        let index = self as usize;
+        ((index % 4) as u8, index >= 4)
+    }
+
+    /// Builds a transform from its clockwise quarter turns and horizontal flip.
+    fn from_parts(quarter_turns: u8, flip: bool) -> Self {
+        Self::ALL[quarter_turns as usize % 4 + if flip { 4 } else { 0 }]
+    }
+
+    /// Applies the transform to an image.
+    pub fn apply(self, image: &DynamicImage) -> DynamicImage {
+        let (quarter_turns, flip) = self.parts();
+        let flipped = if flip { image.fliph() } else { image.clone() };
+        match quarter_turns {
+            1 => flipped.rotate90(),
+            2 => flipped.rotate180(),
+            3 => flipped.rotate270(),
+            _ => flipped,
+        }
+    }
+
+    /// Returns the transform that undoes this one.
+    pub fn inverse(self) -> Self {
+        let (quarter_turns, flip) = self.parts();
+        if flip {
+            // Every mirror image undoes itself
+            self
+        } else {
+            Self::from_parts((4 - quarter_turns) % 4, false)
+        }
+    }
+
+    /// Returns the transform that applies this one and then `next`.
+    pub fn then(self, next: Self) -> Self {
+        let (turns1, flip1) = self.parts();
+        let (turns2, flip2) = next.parts();
+
+        // Flipping reverses the direction of the turns made before it
+        let turns1 = if flip2 { (4 - turns1) % 4 } else { turns1 };
+        Self::from_parts((turns1 + turns2) % 4, flip1 ^ flip2)
+    }
+}
+
+/// The hashes of all 8 dihedral transforms of an image, as produced by `dihedral_hash`.
+///
+/// Flipped and rotated copies of an image share the same `canonical` hash, and `find_transform`
+/// tells which transform turns one image into the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DihedralHash {
+    hashes: Vec<ImageHash>,
+}
+
+impl DihedralHash {
+    /// Returns the hash of the image after applying `transform`.
+    pub fn hash(&self, transform: Transform) -> &ImageHash {
+        &self.hashes[transform as usize]
+    }
+
+    /// Returns the smallest hash of all transforms, along with the transform that produced it.
+    ///
+    /// Ties are resolved in the order of `Transform::ALL`, so symmetric images keep `Identity`.
+    pub fn canonical(&self) -> (Transform, &ImageHash) {
+        Transform::ALL
+            .iter()
+            .map(|&transform| (transform, self.hash(transform)))
+            .min_by(|a, b| a.1.cmp(b.1))
+            .unwrap_or((Transform::Identity, &self.hashes[0]))
+    }
+
+    /// Finds the transform that makes this image look most like `other`.
+    ///
+    /// # Arguments
+    /// * `other` - The dihedral hash of the other image.
+    ///
+    /// # Returns
+    /// * The transform to apply to this image and the Hamming distance of the result to `other`.
+    ///   Ties are resolved in the order of `Transform::ALL`.
+    pub fn find_transform(&self, other: &Self) -> (Transform, u32) {
+        let target = other.hash(Transform::Identity);
+        Transform::ALL
+            .iter()
+            .map(|&transform| (transform, self.hash(transform).hamming_distance(target)))
+            .min_by_key(|&(_, distance)| distance)
+            .unwrap_or((Transform::Identity, u32::MAX))
+    }
+
+    /// Checks whether some transform of this image is within `max_distance` of `other`.
+    ///
+    /// # Returns
+    /// * The transform relating the two images, or `None` if they do not match.
+    pub fn matches(&self, other: &Self, max_distance: u32) -> Option<Transform> {
+        let (transform, distance) = self.find_transform(other);
+        (distance <= max_distance).then_some(transform)
+    }
+}
+
+/// Hashes all 8 dihedral transforms of an image.
+///
+/// Algorithms that hash a square grayscale image (`ahash`, `mhash`, `phash` and `whash`) normalize
+/// the image once and transform the small result, since a square stays square under every transform.
+/// Resizing a flipped or rotated copy may round a few pixels differently, so its hashes can differ
+/// from the matching transform's by a bit or two. Other algorithms transform the decoded image
+/// before it is normalized, so every transform goes through the same resize as a plain `hash_image` call.
+///
+/// # Arguments
+/// * `image` - The decoded image.
+/// * `filter` - The resize filter to use.
+/// * `algo` - The hashing algorithm to use, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid.
+///
+/// # Returns
+/// * A `DihedralHash` with one hash per transform.
+///
+/// # Errors
+/// Returns an error if a transform of the image cannot be hashed.
+pub fn dihedral_hash(
+    image: &DynamicImage,
+    filter: FilterType,
+    algo: &str,
+    hash_size: u32,
+) -> Result<DihedralHash> {
+    dihedral_hash_with(
+        image,
+        Orientation::NoTransforms,
+        algo,
+        hash_size,
+        &NormalizePipeline::new(filter),
+    )
+}
+
+/// Hashes all 8 dihedral transforms of an image like `dihedral_hash`, preprocessing it with a
+/// custom pipeline.
+///
+/// The steps in front of the first `Resize` or `Convert` step, such as `Orient` or `TrimBorder`,
+/// run once on the full image before it is transformed. The remaining steps run on every transform.
+///
+/// # Arguments
+/// * `image` - The decoded image.
+/// * `orientation` - The orientation recorded in the image metadata, used by `NormalizeStep::Orient`.
+/// * `algo` - The hashing algorithm to use, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`.
+///
+/// # Returns
+/// * A `DihedralHash` with one hash per transform.
+///
+/// # Errors
+/// Returns an error if the image cannot be normalized or a transform of it cannot be hashed.
+pub fn dihedral_hash_with(
+    image: &DynamicImage,
+    orientation: Orientation,
+    algo: &str,
+    hash_size: u32,
+    pipeline: &NormalizePipeline,
+) -> Result<DihedralHash> {
+    let (full, rest) = pipeline.split_at_resize();
+    let prepared = full.run(image, orientation, None)?;
+
+    let hashes = match square_hasher(algo, hash_size) {
+        Some((side, hasher)) => {
+            let normalized =
+                rest.apply(&prepared, Orientation::NoTransforms, Some((side, side)))?;
+            Transform::ALL
+                .iter()
+                .map(|transform| hasher(&transform.apply(&normalized)))
+                .collect::<Result<Vec<ImageHash>>>()?
+        }
+        None => Transform::ALL
+            .iter()
+            .map(|transform| {
+                hash_image_with(
+                    &transform.apply(&prepared),
+                    Orientation::NoTransforms,
+                    algo,
+                    hash_size,
+                    &rest,
+                )
+            })
+            .collect::<Result<Vec<ImageHash>>>()?,
+    };
+
+    Ok(DihedralHash { hashes })
+}
+
+/// A hash function applied to an image that is already normalized.
+type Hasher = fn(&DynamicImage) -> Result<ImageHash>;
+
+/// Returns the side of the square an algorithm normalizes images to, along with the hash function
+/// applied to it, or `None` if the algorithm hashes a non-square or full size image.
+fn square_hasher(algo: &str, hash_size: u32) -> Option<(u32, Hasher)> {
+    match algo {
+        "ahash" => Some((hash_size, ImageHash::ahash)),
+        "mhash" => Some((hash_size, ImageHash::mhash)),
+        "phash" => Some((hash_size * 4, ImageHash::phash)),
+        "whash" => Some((hash_size, ImageHash::whash)),
+        _ => None,
+    }
+}
+
+/// Computes the canonical hash of an image, the smallest hash of all its dihedral transforms.
+///
+/// Flipped and rotated copies of an image have the same canonical hash, so exact duplicate
+/// searches find them like any other duplicate. Slightly altered copies, and rarely the rounding
+/// differences described in `dihedral_hash`, may settle on different transforms, so near-duplicates
+/// are better compared with `DihedralHash::matches`.
+///
+/// # Arguments
+/// * `image` - The decoded image.
+/// * `filter` - The resize filter to use.
+/// * `algo` - The hashing algorithm to use, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid.
+///
+/// # Errors
+/// Returns an error if a transform of the image cannot be hashed.
+pub fn canonical_hash(
+    image: &DynamicImage,
+    filter: FilterType,
+    algo: &str,
+    hash_size: u32,
+) -> Result<ImageHash> {
+    let hash = dihedral_hash(image, filter, algo, hash_size)?;
+    Ok(hash.canonical().1.clone())
+}
+
+/// Computes the canonical hash of an image like `canonical_hash`, preprocessing it with a custom
+/// pipeline, see `dihedral_hash_with`.
+///
+/// # Arguments
+/// * `image` - The decoded image.
+/// * `orientation` - The orientation recorded in the image metadata, used by `NormalizeStep::Orient`.
+/// * `algo` - The hashing algorithm to use, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`.
+///
+/// # Errors
+/// Returns an error if the image cannot be normalized or a transform of it cannot be hashed.
+pub fn canonical_hash_with(
+    image: &DynamicImage,
+    orientation: Orientation,
+    algo: &str,
+    hash_size: u32,
+    pipeline: &NormalizePipeline,
+) -> Result<ImageHash> {
+    let hash = dihedral_hash_with(image, orientation, algo, hash_size, pipeline)?;
+    Ok(hash.canonical().1.clone())
+}
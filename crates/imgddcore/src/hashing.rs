@@ -1,19 +1,204 @@
-use anyhow::Result;
+use crate::normalize;
+use anyhow::{anyhow, Result};
 use image::{DynamicImage, GenericImageView};
+use std::fmt;
 
 use dwt::wavelet::Haar;
 use dwt::{Operation, Transform};
 use rustdct::DctPlanner;
 
-/// A structure representing the hash of an image as u64.
+/// A structure representing the hash of an image as a variable-length bit vector.
 ///
 /// The `ImageHash` structure is used to store and compare the hash of an image for deduplication purposes.
-#[derive(Eq, PartialEq, Hash, Clone)]
+/// Bits are packed most significant first into 64-bit words, so a 64-bit hash is a single `u64`.
+/// The hash size of each algorithm follows the dimensions of the normalized image it is given.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, PartialOrd, Ord)]
 pub struct ImageHash {
-    hash: u64,
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl From<u64> for ImageHash {
+    fn from(hash: u64) -> Self {
+        Self {
+            words: vec![hash],
+            len: 64,
+        }
+    }
+}
+
+impl fmt::Display for ImageHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
 }
 
 impl ImageHash {
+    /// Builds a hash from a sequence of bits, most significant first.
+    ///
+    /// # Arguments
+    /// * `bits` - The bits of the hash.
+    ///
+    /// # Returns
+    /// * An `ImageHash` holding every bit of `bits`.
+    pub fn from_bits<I: IntoIterator<Item = bool>>(bits: I) -> Self {
+        let mut words = Vec::new();
+        let mut len = 0;
+        for bit in bits {
+            if len % 64 == 0 {
+                words.push(0);
+            }
+            if bit {
+                words[len / 64] |= 1 << (63 - len % 64);
+            }
+            len += 1;
+        }
+
+        Self { words, len }
+    }
+
+    /// Parses a hash from a hex string, as produced by `to_hex`.
+    ///
+    /// # Arguments
+    /// * `hex` - The hex string. Every character holds four bits.
+    /// * `len` - The number of bits in the hash. `to_hex` pads hashes whose size is not a
+    ///   multiple of four with leading zero bits, which are dropped again.
+    ///
+    /// # Errors
+    /// Returns an error if `hex` contains a character that is not a hex digit, or if its
+    /// length or padding does not match `len`.
+    pub fn from_hex(hex: &str, len: usize) -> Result<Self> {
+        let nibbles = hex
+            .chars()
+            .map(|c| {
+                c.to_digit(16)
+                    .ok_or_else(|| anyhow!("Invalid hex digit {:?} in hash {}", c, hex))
+            })
+            .collect::<Result<Vec<u32>>>()?;
+        if nibbles.len() != len.div_ceil(4) {
+            return Err(anyhow!(
+                "Hash {} has {} hex digits, expected {} for {} bits",
+                hex,
+                nibbles.len(),
+                len.div_ceil(4),
+                len
+            ));
+        }
+
+        let padding = nibbles.len() * 4 - len;
+        let mut bits = nibbles
+            .into_iter()
+            .flat_map(|nibble| (0..4).rev().map(move |i| nibble >> i & 1 == 1));
+        if bits.by_ref().take(padding).any(|bit| bit) {
+            return Err(anyhow!("Hash {} has bits set in its padding", hex));
+        }
+
+        Ok(Self::from_bits(bits))
+    }
+
+    /// Returns the number of bits in the hash.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the hash holds no bits.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bit at `index`, counting from the most significant bit.
+    ///
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn bit(&self, index: usize) -> bool {
+        assert!(index < self.len, "Bit index {} out of range", index);
+        self.words[index / 64] >> (63 - index % 64) & 1 == 1
+    }
+
+    /// Returns an iterator over the bits of the hash, most significant first.
+    pub fn bits(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.bit(i))
+    }
+
+    /// Returns the bits `start..start + width` as a new hash.
+    pub(crate) fn band(&self, start: usize, width: usize) -> Self {
+        let len = (start + width).min(self.len).saturating_sub(start);
+        let mut words = vec![0u64; len.div_ceil(64)];
+        for (k, word) in words.iter_mut().enumerate() {
+            let (index, offset) = ((start + k * 64) / 64, (start + k * 64) % 64);
+            *word = self.words[index] << offset;
+            if offset > 0 && index + 1 < self.words.len() {
+                *word |= self.words[index + 1] >> (64 - offset);
+            }
+
+            // Clear bits past the end of the band
+            let remaining = len - k * 64;
+            if remaining < 64 {
+                *word &= !(u64::MAX >> remaining);
+            }
+        }
+
+        Self { words, len }
+    }
+
+    /// Returns the bits `start..start + width` left-aligned in a single word.
+    ///
+    /// Bits past the end of the hash are zero. `width` must not exceed 64.
+    pub(crate) fn band_word(&self, start: usize, width: usize) -> u64 {
+        debug_assert!(width <= 64, "Band of {} bits does not fit a word", width);
+        self.band(start, width).words.first().copied().unwrap_or(0)
+    }
+
+    /// Computes the Hamming distance to another hash.
+    ///
+    /// Hashes of different sizes are compared over their common prefix, and every extra bit
+    /// of the longer hash counts as a difference.
+    ///
+    /// # Arguments
+    /// * `other` - The hash to compare against.
+    ///
+    /// # Returns
+    /// * The number of bits that differ between the two hashes.
+    #[inline]
+    pub fn hamming_distance(&self, other: &Self) -> u32 {
+        let common = self.len.min(other.len);
+        let (full, rest) = (common / 64, common % 64);
+
+        let mut distance: u32 = self.words[..full]
+            .iter()
+            .zip(&other.words[..full])
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+        if rest > 0 {
+            let mask = u64::MAX << (64 - rest);
+            distance += ((self.words[full] ^ other.words[full]) & mask).count_ones();
+        }
+
+        distance + self.len.abs_diff(other.len) as u32
+    }
+
+    /// Encodes the hash as a hex string with four bits per character.
+    ///
+    /// Hashes whose size is not a multiple of four are padded with leading zero bits.
+    pub fn to_hex(&self) -> String {
+        let padding = (4 - self.len % 4) % 4;
+        let bits: Vec<bool> = std::iter::repeat_n(false, padding)
+            .chain(self.bits())
+            .collect();
+
+        bits.chunks(4)
+            .map(|nibble| {
+                let value = nibble
+                    .iter()
+                    .fold(0, |acc, &bit| (acc << 1) | u32::from(bit));
+                char::from_digit(value, 16).unwrap_or('0')
+            })
+            .collect()
+    }
+
     /// Computes the average hash (aHash) of a given image.
     ///
     /// # Arguments
@@ -28,27 +213,20 @@ impl ImageHash {
     /// - Based on average brightness, making it suitable for detecting overall image similarity.
     #[inline]
     pub fn ahash(image: &DynamicImage) -> Result<Self> {
-        let mut sum = 0u64;
-        let mut pixels = [0u8; 64];
-
-        // Collect pixel values and compute sum
-        for (i, (_, _, pixel)) in image.pixels().enumerate().take(64) {
-            pixels[i] = pixel[0]; // Grayscale value
-            sum += pixels[i] as u64;
+        // Collect grayscale pixel values, one bit per pixel
+        let pixels: Vec<u8> = image.pixels().map(|(_, _, pixel)| pixel[0]).collect();
+        if pixels.is_empty() {
+            return Err(anyhow!("Cannot compute aHash of an empty image"));
         }
 
         // Collect average pixel value
-        let avg = sum / 64;
+        let sum: u64 = pixels.iter().map(|&pixel| pixel as u64).sum();
+        let avg = sum / pixels.len() as u64;
 
         // Compute hash and store bits in the correct order
-        let mut hash = 0u64;
-        for (i, &pixel) in pixels.iter().enumerate() {
-            if pixel as u64 > avg {
-                hash |= 1 << (63 - i); // reverse order
-            }
-        }
-
-        Ok(Self { hash })
+        Ok(Self::from_bits(
+            pixels.iter().map(|&pixel| pixel as u64 > avg),
+        ))
     }
 
     /// Computes the median hash (mHash) of a given image.
@@ -65,30 +243,24 @@ impl ImageHash {
     /// - Suitable for images with varying brightness or exposure levels.
     #[inline]
     pub fn mhash(image: &DynamicImage) -> Result<Self> {
-        let mut pixels = [0u8; 64];
-
-        // Collect 64 pixel values
-        for (i, pixel) in image.pixels().map(|p| p.2[0]).take(64).enumerate() {
-            pixels[i] = pixel;
+        // Collect pixel values, one bit per pixel
+        let pixels: Vec<u8> = image.pixels().map(|p| p.2[0]).collect();
+        if pixels.len() < 2 {
+            return Err(anyhow!("Cannot compute mHash of fewer than two pixels"));
         }
 
         // Copy pixels so we don't modify the original array
-        let mut pixels_copy = pixels;
+        let mut pixels_copy = pixels.clone();
 
         // Find median O(n)
-        let mid = 32;
+        let mid = pixels.len() / 2;
         let (low, median, _high) = pixels_copy.select_nth_unstable(mid);
         let median = (*median as u64 + low[mid - 1] as u64) / 2; // Compute true median
 
         // Compute hash
-        let mut hash = 0u64;
-        for (i, &pixel) in pixels.iter().enumerate() {
-            if pixel as u64 > median {
-                hash |= 1 << (63 - i); // reverse order
-            }
-        }
-
-        Ok(Self { hash })
+        Ok(Self::from_bits(
+            pixels.iter().map(|&pixel| pixel as u64 > median),
+        ))
     }
 
     /// Computes the difference hash (dHash) of a given image.
@@ -105,18 +277,57 @@ impl ImageHash {
     /// - Resistant to small transformations like cropping or rotation.
     #[inline]
     pub fn dhash(image: &DynamicImage) -> Result<Self> {
-        let mut hash = 0u64;
+        let (width, height) = image.dimensions();
+        if width < 2 {
+            return Err(anyhow!(
+                "Cannot compute dHash of an image narrower than 2 pixels"
+            ));
+        }
 
-        for y in 0..8 {
+        // Compare every pixel with its right neighbour, one row at a time
+        let mut bits = Vec::with_capacity(((width - 1) * height) as usize);
+        for y in 0..height {
             let mut current = image.get_pixel(0, y)[0];
-            for x in 1..9 {
+            for x in 1..width {
                 let next = image.get_pixel(x, y)[0];
-                hash = (hash << 1) | (next > current) as u64;
+                bits.push(next > current);
                 current = next;
             }
         }
 
-        Ok(Self { hash })
+        Ok(Self::from_bits(bits))
+    }
+
+    /// Computes the vertical difference hash (vertical dHash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to a `DynamicImage` for which the hash is to be calculated.
+    ///
+    /// # Returns
+    /// * An `ImageHash` instance containing the computed vertical dHash value.
+    ///
+    /// # Details
+    /// **Vertical dHash (Vertical Difference Hash):**
+    /// - Encodes relative changes between vertically adjacent pixels, e.g. on an 8x9 image.
+    /// - Catches horizontal banding that the horizontal dHash cannot see.
+    #[inline]
+    pub fn dhash_vertical(image: &DynamicImage) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        if height < 2 {
+            return Err(anyhow!(
+                "Cannot compute vertical dHash of an image shorter than 2 pixels"
+            ));
+        }
+
+        // Compare every pixel with its bottom neighbour, one row at a time
+        let mut bits = Vec::with_capacity((width * (height - 1)) as usize);
+        for y in 1..height {
+            for x in 0..width {
+                bits.push(image.get_pixel(x, y)[0] > image.get_pixel(x, y - 1)[0]);
+            }
+        }
+
+        Ok(Self::from_bits(bits))
     }
 
     /// Computes the perceptual hash (pHash) of a given image.
@@ -133,41 +344,48 @@ impl ImageHash {
     /// - Focuses on low-frequency components, which are less affected by resizing or compression.
     #[inline]
     pub fn phash(image: &DynamicImage) -> Result<Self> {
-        const IMG_SIZE: usize = 32;
-        const HASH_SIZE: usize = 8;
+        // The normalized image is 4 times the hash size in each dimension
+        let img_size = image.width() as usize;
+        let hash_size = img_size / 4;
+        if image.height() as usize != img_size || hash_size < 2 {
+            return Err(anyhow!(
+                "pHash requires a square image of at least 8x8 pixels, got {}x{}",
+                image.width(),
+                image.height()
+            ));
+        }
 
-        // Collect pixel values from normalized 32x32 grayscale image
+        // Collect pixel values from normalized grayscale image
         let mut pixels: Vec<f32> = image.pixels().map(|p| p.2[0] as f32).collect();
 
         // Plan DCT once for both rows and columns
         let mut planner = DctPlanner::new();
-        let dct = planner.plan_dct2(IMG_SIZE);
+        let dct = planner.plan_dct2(img_size);
 
         // Apply DCT row-wise in-place
-        for row in pixels.chunks_exact_mut(IMG_SIZE) {
+        for row in pixels.chunks_exact_mut(img_size) {
             dct.process_dct2(row);
         }
 
         // Apply DCT column-wise in-place
-        for col in 0..IMG_SIZE {
-            let mut col_values: [f32; IMG_SIZE] = [0.0; IMG_SIZE];
-
-            for row in 0..IMG_SIZE {
-                col_values[row] = pixels[row * IMG_SIZE + col];
+        let mut col_values = vec![0f32; img_size];
+        for col in 0..img_size {
+            for row in 0..img_size {
+                col_values[row] = pixels[row * img_size + col];
             }
 
             dct.process_dct2(&mut col_values);
 
-            for row in 0..IMG_SIZE {
-                pixels[row * IMG_SIZE + col] = col_values[row];
+            for row in 0..img_size {
+                pixels[row * img_size + col] = col_values[row];
             }
         }
 
-        // Extract top-left 8x8 DCT coefficients (low frequencies)
-        let mut dct_lowfreq = [0f32; HASH_SIZE * HASH_SIZE];
-        for y in 0..HASH_SIZE {
-            for x in 0..HASH_SIZE {
-                dct_lowfreq[y * HASH_SIZE + x] = pixels[y * IMG_SIZE + x];
+        // Extract top-left DCT coefficients (low frequencies)
+        let mut dct_lowfreq = vec![0f32; hash_size * hash_size];
+        for y in 0..hash_size {
+            for x in 0..hash_size {
+                dct_lowfreq[y * hash_size + x] = pixels[y * img_size + x];
             }
         }
 
@@ -178,14 +396,116 @@ impl ImageHash {
         let median = ac_coeffs[mid];
 
         // Generate hash
-        let mut hash = 0u64;
-        for (i, &val) in dct_lowfreq.iter().enumerate() {
-            if val > median {
-                hash |= 1 << (63 - i);
+        Ok(Self::from_bits(dct_lowfreq.iter().map(|&val| val > median)))
+    }
+
+    /// Computes the PDQ hash and quality score of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to the full size `DynamicImage` for which the hash is to be calculated.
+    ///
+    /// # Returns
+    /// * An `ImageHash` instance containing the 256-bit PDQ hash, and the quality score from 0 to 100.
+    ///
+    /// # Details
+    /// **PDQ:**
+    /// - Blurs the luminance with a Jarosz tent filter and samples it down to 64x64.
+    /// - Keeps the 16x16 lowest frequencies of the DCT, skipping the DC row and column, split at their median.
+    /// - The quality score counts strong gradients; images scoring below 50 carry too little detail to match reliably.
+    /// - Bit-for-bit compatible with Facebook's reference implementation, including its hex encoding.
+    #[inline]
+    pub fn pdq(image: &DynamicImage) -> Result<(Self, u32)> {
+        const SIZE: usize = 64;
+        const DCT_SIZE: usize = 16;
+        const JAROSZ_PASSES: usize = 2;
+
+        let rgb = image.to_rgb8();
+        let (width, height) = (rgb.width() as usize, rgb.height() as usize);
+        if width == 0 || height == 0 {
+            return Err(anyhow!("Cannot compute PDQ of an empty image"));
+        }
+
+        // Luminance on the 0..=255 scale
+        let mut luma: Vec<f32> = rgb
+            .pixels()
+            .map(|p| {
+                let [r, g, b] = p.0.map(f32::from);
+                r * 0.299 + g * 0.587 + b * 0.114
+            })
+            .collect();
+
+        // Two passes of box filters half an output pixel wide approximate a tent filter
+        let row_window = width.div_ceil(2 * SIZE);
+        let col_window = height.div_ceil(2 * SIZE);
+        let mut buffer = vec![0f32; luma.len()];
+        for _ in 0..JAROSZ_PASSES {
+            for row in 0..height {
+                box_filter(&luma, &mut buffer, row * width, width, 1, row_window);
+            }
+            for col in 0..width {
+                box_filter(&buffer, &mut luma, col, height, width, col_window);
             }
         }
 
-        Ok(Self { hash })
+        // Sample the centers of a 64x64 grid
+        let mut pixels = [[0f32; SIZE]; SIZE];
+        for (i, row) in pixels.iter_mut().enumerate() {
+            let y = (2 * i + 1) * height / (2 * SIZE);
+            for (j, pixel) in row.iter_mut().enumerate() {
+                *pixel = luma[y * width + (2 * j + 1) * width / (2 * SIZE)];
+            }
+        }
+
+        // Quality: the sum of quantized gradients between neighbouring pixels
+        let mut gradient_sum = 0;
+        for i in 0..SIZE {
+            for j in 0..SIZE {
+                if i + 1 < SIZE {
+                    gradient_sum +=
+                        (((pixels[i][j] - pixels[i + 1][j]) * 100.0 / 255.0) as i32).abs();
+                }
+                if j + 1 < SIZE {
+                    gradient_sum +=
+                        (((pixels[i][j] - pixels[i][j + 1]) * 100.0 / 255.0) as i32).abs();
+                }
+            }
+        }
+        let quality = (gradient_sum / 90).min(100) as u32;
+
+        // Partial DCT: only the 16 lowest frequencies above DC in each direction
+        // The reference takes a double precision cosine of a single precision angle
+        let scale = (2.0 / SIZE as f32).sqrt();
+        let mut dct = [[0f32; SIZE]; DCT_SIZE];
+        for (i, row) in dct.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                let angle =
+                    std::f32::consts::PI / 2.0 / SIZE as f32 * (i + 1) as f32 * (2 * j + 1) as f32;
+                *value = scale * (angle as f64).cos() as f32;
+            }
+        }
+
+        let mut intermediate = [[0f32; SIZE]; DCT_SIZE];
+        for i in 0..DCT_SIZE {
+            for j in 0..SIZE {
+                intermediate[i][j] = (0..SIZE).fold(0.0, |sum, k| sum + dct[i][k] * pixels[k][j]);
+            }
+        }
+        let mut coefficients = Vec::with_capacity(DCT_SIZE * DCT_SIZE);
+        for row in &intermediate {
+            for dct_row in &dct {
+                coefficients.push((0..SIZE).fold(0.0, |sum, k| sum + row[k] * dct_row[k]));
+            }
+        }
+
+        // Lower median, as the reference Torben median
+        let mut sorted = coefficients.clone();
+        let mid = (sorted.len() - 1) / 2;
+        sorted.select_nth_unstable_by(mid, |a, b| a.partial_cmp(b).unwrap());
+        let median = sorted[mid];
+
+        // The reference hex encoding starts with the last coefficient
+        let hash = Self::from_bits(coefficients.iter().rev().map(|&value| value > median));
+        Ok((hash, quality))
     }
 
     /// Computes the wavelet hash (wHash) of a given image.
@@ -202,21 +522,29 @@ impl ImageHash {
     /// - Robust against scaling, rotation, and noise.
     #[inline]
     pub fn whash(image: &DynamicImage) -> Result<Self> {
-        const HASH_SIZE: u32 = 8;
-        let ll_max_level: usize = 3;
+        // The normalized image is hash size x hash size, which must be a power of two.
+        let hash_size = image.width();
+        if image.height() != hash_size || hash_size < 2 || !hash_size.is_power_of_two() {
+            return Err(anyhow!(
+                "wHash requires a square image with a power of two size, got {}x{}",
+                image.width(),
+                image.height()
+            ));
+        }
+        let ll_max_level = hash_size.trailing_zeros() as usize;
 
         // Allocate flat vector of normalized pixels (row–major order).
-        let total_pixels = (HASH_SIZE * HASH_SIZE) as usize;
+        let total_pixels = (hash_size * hash_size) as usize;
         let mut pixels = Vec::with_capacity(total_pixels);
-        for y in 0..HASH_SIZE {
-            for x in 0..HASH_SIZE {
+        for y in 0..hash_size {
+            for x in 0..hash_size {
                 let pixel = image.get_pixel(x, y);
                 pixels.push(pixel[0] as f32 / 255.0);
             }
         }
 
         // ---------- Remove low-level frequency (DC) component ---------- //
-        // Perform a full forward Haar transform - 8×8 image (3 levels).
+        // Perform a full forward Haar transform - log2(hash size) levels, e.g. 3 for an 8×8 image.
         pixels.transform(Operation::Forward, &Haar::new(), ll_max_level);
 
         // Zero out the DC coefficient.
@@ -226,7 +554,7 @@ impl ImageHash {
         pixels.transform(Operation::Inverse, &Haar::new(), ll_max_level);
 
         // ---------- Compute median O(n) ---------- //
-        let mid: usize = 32;
+        let mid: usize = total_pixels / 2;
         // Clone flat pixel vector.
         let mut flat = pixels.clone();
         // Quicksort vector.
@@ -235,23 +563,930 @@ impl ImageHash {
         let median = (flat[mid - 1] + flat[mid]) / 2.0;
 
         // Generate hash.
-        let mut hash = 0u64;
-        for (i, &val) in pixels.iter().enumerate() {
-            if val > median {
-                hash |= 1 << (63 - i);
+        Ok(Self::from_bits(pixels.iter().map(|&val| val > median)))
+    }
+
+    /// Computes the block mean value hash (blockhash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to the full size `DynamicImage` for which the hash is to be calculated.
+    /// * `bits` - The side length of the block grid, e.g. `16` for 256-bit hashes. Must be even.
+    ///
+    /// # Returns
+    /// * An `ImageHash` instance containing `bits * bits` bits.
+    ///
+    /// # Details
+    /// **Blockhash:**
+    /// - Sums the brightness of every block of the full image, splitting pixels that straddle two blocks by area.
+    /// - Compares each block to the median of its quarter of the image, so no pre-resize is needed.
+    /// - Matches the precise method of blockhash.io's reference implementations, including transparent pixels counting as white.
+    #[inline]
+    pub fn blockhash(image: &DynamicImage, bits: u32) -> Result<Self> {
+        let rgba = image.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        if width == 0 || height == 0 || bits < 2 || !bits.is_multiple_of(2) {
+            return Err(anyhow!(
+                "Cannot compute blockhash with {} bits of a {}x{} image",
+                bits,
+                width,
+                height
+            ));
+        }
+
+        let blocks_per_side = bits as usize;
+        let block_width = width as f64 / bits as f64;
+        let block_height = height as f64 / bits as f64;
+
+        // Each pixel falls into at most two blocks per axis, weighted by the covered area
+        let split = |position: u32, size: u32, block_size: f64| {
+            if size.is_multiple_of(bits) {
+                let block = floor_div(position as f64, block_size) as usize;
+                return (block, block, 1.0, 0.0);
+            }
+
+            let end = (position + 1) as f64 % block_size;
+            let fraction = end.fract();
+            let first = floor_div(position as f64, block_size) as usize;
+            let second = if end.trunc() > 0.0 || position + 1 == size {
+                first
+            } else {
+                (-floor_div(-(position as f64), block_size)) as usize
+            };
+            (
+                first.min(blocks_per_side - 1),
+                second.min(blocks_per_side - 1),
+                1.0 - fraction,
+                fraction,
+            )
+        };
+
+        let mut blocks = vec![0f64; blocks_per_side * blocks_per_side];
+        for y in 0..height {
+            let (top, bottom, weight_top, weight_bottom) = split(y, height, block_height);
+            for x in 0..width {
+                let (left, right, weight_left, weight_right) = split(x, width, block_width);
+                let [r, g, b, a] = rgba.get_pixel(x, y).0;
+                let value = if a == 0 {
+                    765.0
+                } else {
+                    (r as u32 + g as u32 + b as u32) as f64
+                };
+
+                blocks[top * blocks_per_side + left] += value * weight_top * weight_left;
+                blocks[top * blocks_per_side + right] += value * weight_top * weight_right;
+                blocks[bottom * blocks_per_side + left] += value * weight_bottom * weight_left;
+                blocks[bottom * blocks_per_side + right] += value * weight_bottom * weight_right;
+            }
+        }
+
+        // Compare each block with the median of its horizontal band
+        let half_block_value = block_width * block_height * 256.0 * 3.0 / 2.0;
+        let band_size = blocks.len() / 4;
+        let mut hash_bits = Vec::with_capacity(blocks.len());
+        for band in blocks.chunks(band_size) {
+            let mut sorted = band.to_vec();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let median = if band_size.is_multiple_of(2) {
+                (sorted[band_size / 2 - 1] + sorted[band_size / 2]) / 2.0
+            } else {
+                sorted[band_size / 2]
+            };
+
+            // Blocks equal to the median follow its half of the value range, so flat images are not all zeros
+            hash_bits.extend(band.iter().map(|&value| {
+                value > median || ((value - median).abs() < 1.0 && median > half_block_value)
+            }));
+        }
+
+        Ok(Self::from_bits(hash_bits))
+    }
+
+    /// Computes the Marr-Hildreth hash (mhHash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to a 512x512 grayscale `DynamicImage` for which the hash is to be calculated.
+    ///
+    /// # Returns
+    /// * An `ImageHash` instance containing the 576-bit (72-byte) mhHash value.
+    ///
+    /// # Details
+    /// **mhHash (Marr-Hildreth Hash):**
+    /// - Blurs with a 7x7 Gaussian kernel, equalizes the histogram and filters the image with a
+    ///   Marr-Hildreth (Laplacian of Gaussian) kernel.
+    /// - Sums the edge response in a 31x31 grid of blocks and compares overlapping 3x3 windows of blocks to their mean.
+    /// - Adapted from the algorithm of OpenCV's `img_hash::MarrHildrethHash`, with its default alpha of 2
+    ///   and level of 1. Its output has not been verified against OpenCV.
+    #[inline]
+    pub fn marr_hildreth(image: &DynamicImage) -> Result<Self> {
+        const SIZE: usize = 512;
+        const BLOCKS: usize = 31;
+        const BLOCK_SIZE: usize = 16;
+        const ALPHA: f32 = 2.0;
+        const LEVEL: f32 = 1.0;
+
+        if image.width() as usize != SIZE || image.height() as usize != SIZE {
+            return Err(anyhow!(
+                "mhHash requires a {}x{} image, got {}x{}",
+                SIZE,
+                SIZE,
+                image.width(),
+                image.height()
+            ));
+        }
+        let blurred = opencv_gaussian_blur(&image.to_luma8(), &opencv_gaussian_kernel(7, 0.0));
+        let equalized = normalize::equalize(&DynamicImage::ImageLuma8(blurred))?.to_luma8();
+        let pixels: Vec<f32> = equalized.pixels().map(|p| p[0] as f32).collect();
+
+        // OpenCV's kernel grows with exp(a / 2) instead of the textbook exp(-a / 2), and so does this one
+        let sigma = (4.0 * ALPHA.powf(LEVEL)) as usize;
+        let ratio = ALPHA.powf(-LEVEL);
+        let side = 2 * sigma + 1;
+        let mut kernel = vec![0f32; side * side];
+        for row in 0..side {
+            let y = ratio * (row as f32 - sigma as f32);
+            for col in 0..side {
+                let x = ratio * (col as f32 - sigma as f32);
+                let a = x * x + y * y;
+                kernel[row * side + col] = (2.0 - a) * (a / 2.0).exp();
+            }
+        }
+
+        // Pad by reflecting the border without repeating the edge pixel
+        let padded_side = SIZE + 2 * sigma;
+        let reflect = |position: usize| -> usize {
+            let position = position as isize - sigma as isize;
+            let last = SIZE as isize - 1;
+            position.abs().min(2 * last - position.abs()) as usize
+        };
+        let mut padded = vec![0f32; padded_side * padded_side];
+        for (y, padded_row) in padded.chunks_exact_mut(padded_side).enumerate() {
+            let row = &pixels[reflect(y) * SIZE..(reflect(y) + 1) * SIZE];
+            for (x, value) in padded_row.iter_mut().enumerate() {
+                *value = row[reflect(x)];
+            }
+        }
+
+        // Correlate with the kernel over the area covered by blocks
+        let covered = BLOCKS * BLOCK_SIZE;
+        let mut filtered = vec![0f32; covered * covered];
+        for (y, filtered_row) in filtered.chunks_exact_mut(covered).enumerate() {
+            for (ky, kernel_row) in kernel.chunks_exact(side).enumerate() {
+                let padded_row = &padded[(y + ky) * padded_side..(y + ky + 1) * padded_side];
+                for (kx, &weight) in kernel_row.iter().enumerate() {
+                    for (sum, &value) in filtered_row.iter_mut().zip(&padded_row[kx..]) {
+                        *sum += weight * value;
+                    }
+                }
+            }
+        }
+
+        // Sum the response of every block, transposed like OpenCV
+        let mut blocks = [[0f32; BLOCKS]; BLOCKS];
+        for (row, block_row) in blocks.iter_mut().enumerate() {
+            for (col, block) in block_row.iter_mut().enumerate() {
+                let mut sum = 0f64;
+                for y in col * BLOCK_SIZE..(col + 1) * BLOCK_SIZE {
+                    for x in row * BLOCK_SIZE..(row + 1) * BLOCK_SIZE {
+                        sum += filtered[y * covered + x] as f64;
+                    }
+                }
+                *block = sum as f32;
+            }
+        }
+
+        // Compare every block of 3x3 windows, 4 blocks apart, with the window mean
+        let mut bits = Vec::with_capacity(576);
+        for row in (0..BLOCKS - 2).step_by(4) {
+            for col in (0..BLOCKS - 2).step_by(4) {
+                let window = blocks[row..row + 3].iter().flat_map(|r| &r[col..col + 3]);
+                let mean = (window.clone().map(|&b| b as f64).sum::<f64>() / 9.0) as f32;
+                bits.extend(window.map(|&block| block > mean));
+            }
+        }
+
+        Ok(Self::from_bits(bits))
+    }
+
+    /// Computes the block mean hash (bmHash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to a 256x256 grayscale `DynamicImage` for which the hash is to be calculated.
+    /// * `mode` - Whether the 16x16 blocks are disjoint or overlap by half a block.
+    ///
+    /// # Returns
+    /// * An `ImageHash` instance containing 256 bits for `BlockMeanMode::Disjoint`
+    ///   and 961 bits for `BlockMeanMode::Overlapping`.
+    ///
+    /// # Details
+    /// **bmHash (Block Mean Hash):**
+    /// - Averages 16x16 pixel blocks and sets a bit for every block at or above the median of all blocks.
+    /// - Nearly as cheap as aHash, but less sensitive to a few extreme pixels.
+    /// - Adapted from the algorithm of OpenCV's `img_hash::BlockMeanHash`, where the modes are
+    ///   `BLOCK_MEAN_HASH_MODE_0` and `_1`. Its output has not been verified against OpenCV.
+    #[inline]
+    pub fn block_mean(image: &DynamicImage, mode: BlockMeanMode) -> Result<Self> {
+        const SIZE: u32 = 256;
+        const BLOCK_SIZE: u32 = 16;
+
+        if image.width() != SIZE || image.height() != SIZE {
+            return Err(anyhow!(
+                "bmHash requires a {}x{} image, got {}x{}",
+                SIZE,
+                SIZE,
+                image.width(),
+                image.height()
+            ));
+        }
+        let gray = image.to_luma8();
+
+        // Every block has the same size, so comparing sums is the same as comparing means
+        let step = match mode {
+            BlockMeanMode::Disjoint => BLOCK_SIZE,
+            BlockMeanMode::Overlapping => BLOCK_SIZE / 2,
+        };
+        let mut sums = Vec::new();
+        for row in (0..=SIZE - BLOCK_SIZE).step_by(step as usize) {
+            for col in (0..=SIZE - BLOCK_SIZE).step_by(step as usize) {
+                let sum: u32 = (row..row + BLOCK_SIZE)
+                    .flat_map(|y| (col..col + BLOCK_SIZE).map(move |x| (x, y)))
+                    .map(|(x, y)| gray.get_pixel(x, y)[0] as u32)
+                    .sum();
+                sums.push(sum);
             }
         }
 
-        Ok(Self { hash })
+        // OpenCV takes the upper median of an even number of blocks
+        let mut sorted = sums.clone();
+        let mid = sorted.len() / 2;
+        let (_, &mut median, _) = sorted.select_nth_unstable(mid);
+
+        Ok(Self::from_bits(sums.iter().map(|&sum| sum >= median)))
+    }
+
+    /// Computes the color hash (colorHash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to the full color `DynamicImage` for which the hash is to be calculated.
+    /// * `binbits` - The number of bits used to encode the fraction of pixels in each bin.
+    ///
+    /// # Returns
+    /// * An `ImageHash` instance containing `14 * binbits` bits.
+    ///
+    /// # Details
+    /// **colorHash (Color Hash):**
+    /// - Bins pixels into black, gray, and 6 faint and 6 bright hue ranges in HSV space.
+    /// - Encodes the fraction of pixels in each bin, so recolored images no longer look identical.
+    /// - Matches the colorhash of the Python imagehash library, including PIL's HSV conversion.
+    #[inline]
+    pub fn colorhash(image: &DynamicImage, binbits: u32) -> Result<Self> {
+        const HUE_BINS: usize = 6;
+
+        let rgb = image.to_rgb8();
+        if rgb.is_empty() || binbits == 0 || binbits > 16 {
+            return Err(anyhow!(
+                "Cannot compute colorHash with {} bin bits of a {}x{} image",
+                binbits,
+                rgb.width(),
+                rgb.height()
+            ));
+        }
+
+        let (mut black, mut gray, mut colors) = (0u64, 0u64, 0u64);
+        let mut faint = [0u64; HUE_BINS];
+        let mut bright = [0u64; HUE_BINS];
+        for pixel in rgb.pixels() {
+            let [r, g, b] = pixel.0;
+
+            // PIL "L" conversion
+            let intensity = (r as u32 * 19595 + g as u32 * 38470 + b as u32 * 7471 + 0x8000) >> 16;
+            let (hue, saturation) = pil_hue_saturation(r, g, b);
+            let saturation = saturation as u32;
+
+            if intensity < 256 / 8 {
+                black += 1;
+            } else if saturation < 256 / 3 {
+                gray += 1;
+            } else {
+                colors += 1;
+
+                // Hue bins split 0..=255 into 6 equal ranges, the last one closed
+                let bin = (hue as usize * HUE_BINS / 255).min(HUE_BINS - 1);
+                if saturation < 256 * 2 / 3 {
+                    faint[bin] += 1;
+                } else if saturation > 256 * 2 / 3 {
+                    bright[bin] += 1;
+                }
+            }
+        }
+
+        // Discretize the fraction of pixels in each bin, in the same order of operations as imagehash
+        let max_value = 1u64 << binbits;
+        let quantize = |value: f64| (value as u64).min(max_value - 1);
+        let total = rgb.pixels().len() as f64;
+        let colors = colors.max(1) as f64;
+        let values = [black, gray]
+            .into_iter()
+            .map(|count| quantize(count as f64 / total * max_value as f64))
+            .chain(
+                faint
+                    .iter()
+                    .chain(&bright)
+                    .map(|&count| quantize(count as f64 * max_value as f64 / colors)),
+            );
+
+        // imagehash marks bit `i` when the value has any bit set at or above `binbits - i - 1`
+        Ok(Self::from_bits(values.flat_map(|value| {
+            (0..binbits)
+                .map(move |i| !(value >> (binbits - i - 1)).is_multiple_of(1 << (binbits - i)))
+        })))
     }
 
     /// Retrieves the computed hash value.
     ///
     /// # Returns
     ///
-    /// * Hash value as a `u64`.
+    /// * The leading 64 bits of the hash as a `u64`, which is the whole hash for 8x8 hashes.
+    ///   Shorter hashes are padded with trailing zero bits.
     #[inline]
     pub fn get_hash(&self) -> u64 {
-        self.hash
+        self.words.first().copied().unwrap_or(0)
+    }
+}
+
+/// Divides and rounds down exactly like Python's float `//` operator.
+fn floor_div(a: f64, b: f64) -> f64 {
+    let modulo = a % b;
+    let mut div = (a - modulo) / b;
+    if modulo != 0.0 && (b < 0.0) != (modulo < 0.0) {
+        div -= 1.0;
+    }
+
+    let floor = div.floor();
+    if div - floor > 0.5 {
+        floor + 1.0
+    } else {
+        floor
+    }
+}
+
+/// Converts an RGB pixel to hue and saturation exactly like PIL's `HSV` mode.
+fn pil_hue_saturation(r: u8, g: u8, b: u8) -> (u8, u8) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if max == min {
+        return (0, 0);
+    }
+
+    // PIL mixes float variables with double constants, mirrored here to match its rounding
+    let chroma = (max - min) as f32;
+    let saturation = chroma / max as f32;
+    let rc = (max - r) as f32 / chroma;
+    let gc = (max - g) as f32 / chroma;
+    let bc = (max - b) as f32 / chroma;
+    let hue = if r == max {
+        bc - gc
+    } else if g == max {
+        (2.0 + rc as f64 - bc as f64) as f32
+    } else {
+        (4.0 + gc as f64 - rc as f64) as f32
+    };
+    let hue = ((hue as f64 / 6.0 + 1.0) % 1.0) as f32;
+
+    (
+        (hue as f64 * 255.0).clamp(0.0, 255.0) as u8,
+        (saturation as f64 * 255.0).clamp(0.0, 255.0) as u8,
+    )
+}
+
+/// Block layout of `ImageHash::block_mean`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlockMeanMode {
+    /// A 16x16 grid of disjoint blocks, producing 256-bit hashes.
+    #[default]
+    Disjoint,
+    /// A 31x31 grid of blocks that overlap their neighbours by half, producing 961-bit hashes.
+    Overlapping,
+}
+
+/// The radial variance hash of an image, a vector of quantized coefficients rather than a bit string.
+///
+/// Radial variance hashes are compared with `cross_correlation` instead of the Hamming distance.
+#[derive(Debug, Eq, PartialEq, Hash, Clone)]
+pub struct RadialVarianceHash {
+    coefficients: Vec<u8>,
+}
+
+impl fmt::Display for RadialVarianceHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl RadialVarianceHash {
+    /// Number of projection lines through the image center, one per degree.
+    const ANGLES: usize = 180;
+    /// Number of DCT coefficients kept in the hash.
+    const COEFFICIENTS: usize = 40;
+
+    /// Computes the radial variance hash (rvHash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to the full size `DynamicImage` for which the hash is to be calculated.
+    ///
+    /// # Returns
+    /// * A `RadialVarianceHash` of 40 coefficients.
+    ///
+    /// # Details
+    /// **rvHash (Radial Variance Hash):**
+    /// - Samples 180 lines through the center of the blurred image, a discrete Radon transform.
+    /// - Keeps the lowest 40 DCT coefficients of the pixel variance along each line.
+    /// - Robust against noise, blurring and brightness changes.
+    /// - Adapted from the algorithm of OpenCV's `img_hash::RadialVarianceHash`, with its default sigma of 1.
+    ///   Its output has not been verified against OpenCV.
+    pub fn compute(image: &DynamicImage) -> Result<Self> {
+        let gray = image.to_luma8();
+        if gray.is_empty() {
+            return Err(anyhow!("Cannot compute rvHash of an empty image"));
+        }
+        // OpenCV sizes the kernel of an 8-bit image to 6 sigma + 1 taps
+        let blurred = opencv_gaussian_blur(&gray, &opencv_gaussian_kernel(7, 1.0));
+        let (width, height) = (blurred.width() as isize, blurred.height() as isize);
+        let pixel = |row: isize, col: isize| blurred.get_pixel(col as u32, row as u32)[0];
+
+        // Round half away from zero, then floor, as pHash does
+        let round = |value: f64| (value + if value >= 0.0 { 0.5 } else { -0.5 }).floor() as isize;
+        let x_offset = round(width as f64 / 2.0);
+        let y_offset = round(height as f64 / 2.0);
+
+        // Sample every line, mirroring the steep angles off the shallow ones
+        let n = Self::ANGLES;
+        let length = width.max(height) as usize;
+        let mut projections = vec![vec![0u8; length]; n];
+        let mut counts = vec![0usize; n];
+        for k in 0..=n / 4 {
+            let slope = (k as f64 * std::f64::consts::PI / n as f64).tan();
+            for x in 0..length as isize {
+                let y = round(slope * (x - x_offset) as f64);
+                if y + y_offset >= 0 && y + y_offset < height && x < width {
+                    projections[k][x as usize] = pixel(y + y_offset, x);
+                    counts[k] += 1;
+                }
+                if y + x_offset >= 0 && y + x_offset < width && k != n / 4 && x < height {
+                    projections[n / 2 - k][x as usize] = pixel(x, y + x_offset);
+                    counts[n / 2 - k] += 1;
+                }
+            }
+        }
+        for (j, k) in (3 * n / 4..n).enumerate() {
+            let mirrored = k - 2 * j;
+            let slope = (k as f64 * std::f64::consts::PI / n as f64).tan();
+            for x in 0..length as isize {
+                let y = round(slope * (x - x_offset) as f64);
+                if y + y_offset >= 0 && y + y_offset < height && x < width {
+                    projections[k][x as usize] = pixel(y + y_offset, x);
+                    counts[k] += 1;
+                }
+                if y_offset - y >= 0
+                    && y_offset - y < width
+                    && 2 * y_offset - x >= 0
+                    && 2 * y_offset - x < height
+                    && k != 3 * n / 4
+                {
+                    projections[mirrored][x as usize] = pixel(2 * y_offset - x, y_offset - y);
+                    counts[mirrored] += 1;
+                }
+            }
+        }
+
+        // Variance along each line, standardized over all lines
+        let mut features: Vec<f64> = projections
+            .iter()
+            .zip(&counts)
+            .map(|(line, &count)| {
+                // Avoids dividing by zero for lines that miss the image, as OpenCV does
+                let count = count as f64 + 0.00001;
+                let sum: f64 = line.iter().map(|&v| v as f64).sum();
+                let sum_squared: f64 = line.iter().map(|&v| v as f64 * v as f64).sum();
+                sum_squared / count - sum * sum / (count * count)
+            })
+            .collect();
+        let sum: f64 = features.iter().sum();
+        let sum_squared: f64 = features.iter().map(|f| f * f).sum();
+        let mean = sum / n as f64;
+        let deviation = (sum_squared / n as f64 - sum * sum / (n * n) as f64).sqrt();
+        for feature in &mut features {
+            *feature = (*feature - mean) / deviation;
+        }
+
+        // Lowest DCT coefficients of the features, scaled to the full byte range and truncated like OpenCV
+        let values: Vec<f64> = (0..Self::COEFFICIENTS)
+            .map(|k| {
+                let sum: f64 = features
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| {
+                        f * (std::f64::consts::PI * (2 * i + 1) as f64 * k as f64 / (2 * n) as f64)
+                            .cos()
+                    })
+                    .sum();
+                let scale = if k == 0 {
+                    1.0
+                } else {
+                    std::f64::consts::SQRT_2
+                };
+                sum * scale / (n as f64).sqrt()
+            })
+            .collect();
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        let min = values.iter().cloned().fold(0.0, f64::min);
+        let coefficients = values
+            .iter()
+            .map(|v| (255.0 * (v - min) / (max - min)).clamp(0.0, 255.0) as u8)
+            .collect();
+
+        Ok(Self { coefficients })
+    }
+
+    /// Builds a hash from its coefficients, as produced by `coefficients`.
+    pub fn from_coefficients(coefficients: Vec<u8>) -> Self {
+        Self { coefficients }
+    }
+
+    /// Returns the quantized DCT coefficients of the hash.
+    pub fn coefficients(&self) -> &[u8] {
+        &self.coefficients
+    }
+
+    /// Encodes the coefficients as a hex string with two characters per coefficient.
+    pub fn to_hex(&self) -> String {
+        self.coefficients
+            .iter()
+            .map(|c| format!("{:02x}", c))
+            .collect()
+    }
+
+    /// Computes the peak cross-correlation with another hash.
+    ///
+    /// The coefficients are correlated at every cyclic shift and the best match is kept.
+    ///
+    /// # Arguments
+    /// * `other` - The hash to compare against.
+    ///
+    /// # Returns
+    /// * The peak Pearson correlation from 0 to 1, where 1 means identical. Hashes of
+    ///   different lengths or without any variation have a correlation of 0.
+    pub fn cross_correlation(&self, other: &Self) -> f64 {
+        let n = self.coefficients.len();
+        if n == 0 || n != other.coefficients.len() {
+            return 0.0;
+        }
+
+        let mean = |c: &[u8]| c.iter().map(|&v| v as f64).sum::<f64>() / n as f64;
+        let (mean_x, mean_y) = (mean(&self.coefficients), mean(&other.coefficients));
+
+        let mut peak = 0.0;
+        for shift in 0..n {
+            let (mut numerator, mut denominator_x, mut denominator_y) = (0.0, 0.0, 0.0);
+            for i in 0..n {
+                let x = self.coefficients[i] as f64 - mean_x;
+                let y = other.coefficients[(n + i - shift) % n] as f64 - mean_y;
+                numerator += x * y;
+                denominator_x += x * x;
+                denominator_y += y * y;
+            }
+
+            let correlation = numerator / (denominator_x * denominator_y).sqrt();
+            if correlation > peak {
+                peak = correlation;
+            }
+        }
+
+        peak
+    }
+}
+
+/// The color moment hash of an image, a vector of Hu moments rather than a bit string.
+///
+/// Color moment hashes are compared with the Euclidean `distance` instead of the Hamming distance.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ColorMomentHash {
+    moments: Vec<f64>,
+}
+
+impl fmt::Display for ColorMomentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let moments: Vec<String> = self.moments.iter().map(f64::to_string).collect();
+        f.write_str(&moments.join(","))
+    }
+}
+
+impl ColorMomentHash {
+    /// Side length of the square the image is resized to.
+    const SIZE: u32 = 512;
+
+    /// Computes the color moment hash (cmHash) of a given image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to the full size `DynamicImage` for which the hash is to be calculated.
+    ///
+    /// # Returns
+    /// * A `ColorMomentHash` of 42 moments.
+    ///
+    /// # Details
+    /// **cmHash (Color Moment Hash):**
+    /// - Resizes the image to 512x512 and smooths it with a 3x3 Gaussian kernel.
+    /// - Computes the 7 Hu moments of every HSV and YCrCb channel, in that order.
+    /// - Hu moments do not change when the image is rotated, so it matches copies rotated by any angle.
+    /// - Adapted from the algorithm of OpenCV's `img_hash::ColorMomentHash`, but resizes with Catmull-Rom
+    ///   instead of OpenCV's bicubic kernel. Its output has not been verified against OpenCV.
+    pub fn compute(image: &DynamicImage) -> Result<Self> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(anyhow!("Cannot compute cmHash of an empty image"));
+        }
+        let resized = image
+            .resize_exact(
+                Self::SIZE,
+                Self::SIZE,
+                image::imageops::FilterType::CatmullRom,
+            )
+            .to_rgb8();
+        let pixels: Vec<[u8; 3]> = resized.pixels().map(|p| p.0).collect();
+        let blurred = gaussian_blur_3x3(&pixels, Self::SIZE as usize);
+
+        let (hsv, ycrcb): (Vec<[u8; 3]>, Vec<[u8; 3]>) = blurred
+            .iter()
+            .map(|&[r, g, b]| (opencv_hsv(r, g, b), opencv_ycrcb(r, g, b)))
+            .unzip();
+
+        let mut moments = Vec::with_capacity(42);
+        for converted in [&hsv, &ycrcb] {
+            for channel in 0..3 {
+                let values: Vec<f64> = converted.iter().map(|p| p[channel] as f64).collect();
+                moments.extend(hu_moments(&values, Self::SIZE as usize));
+            }
+        }
+
+        Ok(Self { moments })
+    }
+
+    /// Builds a hash from its moments, as produced by `moments`.
+    pub fn from_moments(moments: Vec<f64>) -> Self {
+        Self { moments }
+    }
+
+    /// Returns the Hu moments of the HSV channels followed by those of the YCrCb channels.
+    pub fn moments(&self) -> &[f64] {
+        &self.moments
+    }
+
+    /// Computes the Euclidean (L2) distance to another hash.
+    ///
+    /// # Arguments
+    /// * `other` - The hash to compare against.
+    ///
+    /// # Returns
+    /// * The distance between the two moment vectors, where 0 means identical.
+    ///   Hashes of different lengths are infinitely far apart.
+    pub fn distance(&self, other: &Self) -> f64 {
+        if self.moments.len() != other.moments.len() {
+            return f64::INFINITY;
+        }
+
+        self.moments
+            .iter()
+            .zip(&other.moments)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+/// Builds the separable Gaussian kernel of `cv::getGaussianKernel`.
+///
+/// Without a positive `sigma`, the 7-tap kernel is OpenCV's fixed table and other sizes derive sigma from the size.
+fn opencv_gaussian_kernel(size: usize, sigma: f64) -> Vec<f64> {
+    if sigma <= 0.0 && size == 7 {
+        return [1.0, 3.5, 7.0, 9.0, 7.0, 3.5, 1.0]
+            .iter()
+            .map(|w| w / 32.0)
+            .collect();
+    }
+    let sigma = if sigma > 0.0 {
+        sigma
+    } else {
+        ((size as f64 - 1.0) * 0.5 - 1.0) * 0.3 + 0.8
+    };
+
+    let radius = (size / 2) as f64;
+    let weights: Vec<f64> = (0..size)
+        .map(|i| {
+            let x = i as f64 - radius;
+            (-(x * x) / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f64 = weights.iter().sum();
+    weights.iter().map(|w| w / sum).collect()
+}
+
+/// Blurs a grayscale image with a separable kernel, reflecting the border like OpenCV.
+///
+/// Sums are kept in floating point and rounded once, where OpenCV rounds the kernel to fixed point.
+fn opencv_gaussian_blur(image: &image::GrayImage, kernel: &[f64]) -> image::GrayImage {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let radius = (kernel.len() / 2) as isize;
+    let reflect = |position: isize, length: usize| -> usize {
+        let last = length as isize - 1;
+        let mut position = position;
+        while last > 0 && (position < 0 || position > last) {
+            position = if position < 0 {
+                -position
+            } else {
+                2 * last - position
+            };
+        }
+        position.clamp(0, last) as usize
+    };
+
+    let pixels = image.as_raw();
+    let mut horizontal = vec![0f64; width * height];
+    for (y, row) in horizontal.chunks_exact_mut(width).enumerate() {
+        for (x, sum) in row.iter_mut().enumerate() {
+            for (k, weight) in kernel.iter().enumerate() {
+                let col = reflect(x as isize + k as isize - radius, width);
+                *sum += weight * pixels[y * width + col] as f64;
+            }
+        }
+    }
+
+    image::GrayImage::from_fn(width as u32, height as u32, |x, y| {
+        let mut sum = 0f64;
+        for (k, weight) in kernel.iter().enumerate() {
+            let row = reflect(y as isize + k as isize - radius, height);
+            sum += weight * horizontal[row * width + x as usize];
+        }
+        image::Luma([(sum + 0.5).floor().clamp(0.0, 255.0) as u8])
+    })
+}
+
+/// Smooths a square RGB image with the 3x3 kernel `[1, 2, 1] / 16`, reflecting the border like OpenCV.
+fn gaussian_blur_3x3(pixels: &[[u8; 3]], side: usize) -> Vec<[u8; 3]> {
+    let reflect = |position: isize| -> usize {
+        let last = side as isize - 1;
+        position.abs().min(2 * last - position.abs()) as usize
+    };
+    let weights = [1u32, 2, 1];
+
+    let mut blurred = vec![[0u8; 3]; pixels.len()];
+    for y in 0..side {
+        for x in 0..side {
+            let mut sums = [0u32; 3];
+            for (dy, wy) in weights.iter().enumerate() {
+                let row = reflect(y as isize + dy as isize - 1);
+                for (dx, wx) in weights.iter().enumerate() {
+                    let pixel = pixels[row * side + reflect(x as isize + dx as isize - 1)];
+                    for (sum, &value) in sums.iter_mut().zip(&pixel) {
+                        *sum += wy * wx * value as u32;
+                    }
+                }
+            }
+            blurred[y * side + x] = sums.map(|sum| ((sum + 8) >> 4) as u8);
+        }
+    }
+    blurred
+}
+
+/// Converts an RGB pixel to 8-bit HSV with hue in `0..180`, using OpenCV's fixed-point arithmetic.
+fn opencv_hsv(r: u8, g: u8, b: u8) -> [u8; 3] {
+    const SHIFT: u32 = 12;
+    let divide = |numerator: f64, denominator: i32| -> i32 {
+        if denominator == 0 {
+            0
+        } else {
+            (numerator / denominator as f64).round_ties_even() as i32
+        }
+    };
+
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    let value = r.max(g).max(b);
+    let diff = value - r.min(g).min(b);
+
+    let saturation = (diff * divide((255 << SHIFT) as f64, value) + (1 << (SHIFT - 1))) >> SHIFT;
+    let hue = if value == r {
+        g - b
+    } else if value == g {
+        b - r + 2 * diff
+    } else {
+        r - g + 4 * diff
+    };
+    let mut hue = (hue * divide((180 << SHIFT) as f64, 6 * diff) + (1 << (SHIFT - 1))) >> SHIFT;
+    if hue < 0 {
+        hue += 180;
+    }
+
+    [hue as u8, saturation as u8, value as u8]
+}
+
+/// Converts an RGB pixel to 8-bit YCrCb, using OpenCV's fixed-point arithmetic.
+fn opencv_ycrcb(r: u8, g: u8, b: u8) -> [u8; 3] {
+    const SHIFT: i32 = 14;
+    const HALF: i32 = 1 << (SHIFT - 1);
+    const DELTA: i32 = 128 << SHIFT;
+
+    let (r, g, b) = (r as i32, g as i32, b as i32);
+    let y = (r * 4899 + g * 9617 + b * 1868 + HALF) >> SHIFT;
+    let cr = ((r - y) * 11682 + DELTA + HALF) >> SHIFT;
+    let cb = ((b - y) * 9241 + DELTA + HALF) >> SHIFT;
+
+    [
+        y.clamp(0, 255) as u8,
+        cr.clamp(0, 255) as u8,
+        cb.clamp(0, 255) as u8,
+    ]
+}
+
+/// Computes the 7 Hu moment invariants of a square single-channel image.
+fn hu_moments(values: &[f64], side: usize) -> [f64; 7] {
+    let coordinates = |i: usize| ((i % side) as f64, (i / side) as f64);
+
+    let (mut m00, mut m10, mut m01) = (0.0, 0.0, 0.0);
+    for (i, &value) in values.iter().enumerate() {
+        let (x, y) = coordinates(i);
+        m00 += value;
+        m10 += value * x;
+        m01 += value * y;
+    }
+    // A black channel has no moments, as in OpenCV
+    if m00 == 0.0 {
+        return [0.0; 7];
+    }
+
+    // Central moments around the centroid
+    let (cx, cy) = (m10 / m00, m01 / m00);
+    let mut mu = [[0.0f64; 4]; 4];
+    for (i, &value) in values.iter().enumerate() {
+        let (x, y) = coordinates(i);
+        let (dx, dy) = (x - cx, y - cy);
+        let (dx2, dy2) = (dx * dx, dy * dy);
+        mu[2][0] += value * dx2;
+        mu[1][1] += value * dx * dy;
+        mu[0][2] += value * dy2;
+        mu[3][0] += value * dx2 * dx;
+        mu[2][1] += value * dx2 * dy;
+        mu[1][2] += value * dx * dy2;
+        mu[0][3] += value * dy2 * dy;
+    }
+
+    // Normalized central moments
+    let s2 = 1.0 / (m00 * m00);
+    let s3 = s2 / m00.sqrt();
+    let (n20, n11, n02) = (mu[2][0] * s2, mu[1][1] * s2, mu[0][2] * s2);
+    let (n30, n21, n12, n03) = (mu[3][0] * s3, mu[2][1] * s3, mu[1][2] * s3, mu[0][3] * s3);
+
+    let (t0, t1) = (n30 + n12, n21 + n03);
+    let (q0, q1) = (t0 * t0, t1 * t1);
+    let n4 = 4.0 * n11;
+    let (s, d) = (n20 + n02, n20 - n02);
+    let (a, b) = (n30 - 3.0 * n12, 3.0 * n21 - n03);
+    let (u, v) = (t0 * (q0 - 3.0 * q1), t1 * (3.0 * q0 - q1));
+
+    [
+        s,
+        d * d + n4 * n11,
+        a * a + b * b,
+        q0 + q1,
+        a * u + b * v,
+        d * (q0 - q1) + n4 * t0 * t1,
+        b * u - a * v,
+    ]
+}
+
+/// Applies a box filter of `window` elements along one row or column of a row-major buffer.
+///
+/// The window shrinks at both ends instead of padding, matching PDQ's reference implementation.
+fn box_filter(
+    input: &[f32],
+    output: &mut [f32],
+    start: usize,
+    length: usize,
+    stride: usize,
+    window: usize,
+) {
+    let half = (window + 2) / 2;
+    let at = |i: usize| start + i * stride;
+
+    let mut sum = 0f32;
+    let mut count = 0f32;
+    for i in 0..length + half - 1 {
+        if i < length {
+            sum += input[at(i)];
+            count += 1.0;
+        }
+        if i >= window {
+            sum -= input[at(i - window)];
+            count -= 1.0;
+        }
+        if i + 1 >= half {
+            output[at(i + 1 - half)] = sum / count;
+        }
     }
 }
@@ -0,0 +1,193 @@
+use crate::dedupe::hamming_distance;
+use crate::hashing::ImageHash;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+
+/// A single entry of the `BKTree`.
+struct Node {
+    hash: ImageHash,
+    path: PathBuf,
+    children: Vec<(u32, usize)>,
+}
+
+/// A BK-tree (Burkhard-Keller tree) indexing hashes by Hamming distance.
+///
+/// The `BKTree` answers "which known images are within distance k of this one?" without scanning
+/// every hash. Nodes are stored in a flat arena and each child is keyed by its distance to the parent.
+#[derive(Default)]
+pub struct BKTree {
+    nodes: Vec<Node>,
+}
+
+impl BKTree {
+    /// Creates an empty `BKTree`.
+    #[inline]
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    /// Builds a `BKTree` from the output of `collect_hashes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_paths` - A slice of hash-path tuples.
+    ///
+    /// # Returns
+    ///
+    /// * A `BKTree` containing every hash-path tuple.
+    pub fn from_hashes(hash_paths: &[(ImageHash, PathBuf)]) -> Self {
+        let mut tree = Self::new();
+        for (hash, path) in hash_paths {
+            tree.insert(hash.clone(), path.clone());
+        }
+        tree
+    }
+
+    /// Inserts a hash and its file path into the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The hash value to index.
+    /// * `path` - The file path the hash belongs to.
+    pub fn insert(&mut self, hash: ImageHash, path: PathBuf) {
+        let new_index = self.nodes.len();
+        if new_index == 0 {
+            self.nodes.push(Node {
+                hash,
+                path,
+                children: Vec::new(),
+            });
+            return;
+        }
+
+        // Walk down the tree until a free edge for this distance is found
+        let mut current = 0;
+        loop {
+            let distance = hamming_distance(&self.nodes[current].hash, &hash);
+            let next = self.nodes[current]
+                .children
+                .iter()
+                .find(|(edge, _)| *edge == distance)
+                .map(|(_, child)| *child);
+
+            match next {
+                Some(child) => current = child,
+                None => {
+                    self.nodes[current].children.push((distance, new_index));
+                    self.nodes.push(Node {
+                        hash,
+                        path,
+                        children: Vec::new(),
+                    });
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Finds every indexed hash within `max_distance` of the query hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The query hash value.
+    /// * `max_distance` - The maximum Hamming distance of a match.
+    ///
+    /// # Returns
+    ///
+    /// * A vector of `(distance, hash, path)` tuples, sorted by distance then path.
+    pub fn find(&self, hash: &ImageHash, max_distance: u32) -> Vec<(u32, &ImageHash, &PathBuf)> {
+        let mut matches = Vec::new();
+        if self.nodes.is_empty() {
+            return matches;
+        }
+
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let distance = hamming_distance(&node.hash, hash);
+            if distance <= max_distance {
+                matches.push((distance, &node.hash, &node.path));
+            }
+
+            // Triangle inequality: only edges in [d - r, d + r] can hold matches
+            let low = distance.saturating_sub(max_distance);
+            let high = distance.saturating_add(max_distance);
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(edge, _)| (low..=high).contains(edge))
+                    .map(|(_, child)| *child),
+            );
+        }
+
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.2.cmp(b.2)));
+        matches
+    }
+
+    /// Finds the `k` indexed hashes closest to the query hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - The query hash value.
+    /// * `k` - The number of neighbours to return.
+    ///
+    /// # Returns
+    ///
+    /// * A vector of at most `k` `(distance, hash, path)` tuples, sorted by distance then path.
+    pub fn nearest(&self, hash: &ImageHash, k: usize) -> Vec<(u32, &ImageHash, &PathBuf)> {
+        if self.nodes.is_empty() || k == 0 {
+            return Vec::new();
+        }
+
+        // Max-heap of the best `k` candidates seen so far, ties broken by path
+        let mut best: BinaryHeap<(u32, &PathBuf, usize)> = BinaryHeap::with_capacity(k + 1);
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let distance = hamming_distance(&node.hash, hash);
+
+            if best.len() < k {
+                best.push((distance, &node.path, index));
+            } else if best
+                .peek()
+                .is_some_and(|(worst, path, _)| (distance, &node.path) < (*worst, *path))
+            {
+                best.pop();
+                best.push((distance, &node.path, index));
+            }
+
+            // Prune using the current worst distance once `k` candidates are known
+            let radius = match best.peek() {
+                Some((worst, _, _)) if best.len() == k => *worst,
+                _ => u32::MAX,
+            };
+            let low = distance.saturating_sub(radius);
+            let high = distance.saturating_add(radius);
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(edge, _)| (low..=high).contains(edge))
+                    .map(|(_, child)| *child),
+            );
+        }
+
+        let mut neighbours: Vec<(u32, &ImageHash, &PathBuf)> = best
+            .into_iter()
+            .map(|(distance, path, index)| (distance, &self.nodes[index].hash, path))
+            .collect();
+        neighbours.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.2.cmp(b.2)));
+        neighbours
+    }
+
+    /// Returns the number of indexed hashes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if the tree holds no hashes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
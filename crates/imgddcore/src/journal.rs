@@ -0,0 +1,216 @@
+use crate::dedupe::move_file;
+use crate::hashing::ImageHash;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The operation recorded by a `JournalEntry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalAction {
+    /// The duplicate was deleted.
+    Remove,
+    /// The duplicate was moved to `destination`.
+    Move,
+    /// The duplicate was replaced with a link to `survivor`.
+    Link,
+}
+
+/// A single line of the dedupe journal.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// The operation applied to the duplicate.
+    pub action: JournalAction,
+    /// The original path of the duplicate.
+    pub original: PathBuf,
+    /// Where the duplicate was moved to, if it was moved.
+    pub destination: Option<PathBuf>,
+    /// The path of the file that was kept.
+    pub survivor: PathBuf,
+    /// The hash of the duplicate as a hex string.
+    pub hash: String,
+    /// The hashing algorithm that produced `hash`.
+    pub algo: String,
+    /// Seconds since the Unix epoch when the operation was applied.
+    pub timestamp: u64,
+}
+
+/// An append-only JSON lines journal of destructive dedupe operations.
+///
+/// Every remove, move or link applied by `find_duplicates` is written as one `JournalEntry` per line,
+/// so the operations can be audited and quarantined files can be restored with `undo`.
+pub struct Journal {
+    file: File,
+    path: PathBuf,
+    algo: String,
+    last_start: Option<u64>,
+}
+
+impl Journal {
+    /// Opens a journal for appending, creating the file if it does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the journal file.
+    /// * `algo` - The hashing algorithm recorded with every entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal file cannot be opened.
+    pub fn open(path: &Path, algo: &str) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow!("Error opening journal {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            algo: algo.to_string(),
+            last_start: None,
+        })
+    }
+
+    /// Appends an entry to the journal.
+    ///
+    /// # Arguments
+    ///
+    /// * `action` - The operation applied to the duplicate.
+    /// * `original` - The original path of the duplicate.
+    /// * `destination` - Where the duplicate was moved to, if it was moved.
+    /// * `survivor` - The path of the file that was kept.
+    /// * `hash` - The hash of the duplicate.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry cannot be written.
+    pub fn record(
+        &mut self,
+        action: JournalAction,
+        original: &Path,
+        destination: Option<&Path>,
+        survivor: &Path,
+        hash: &ImageHash,
+    ) -> Result<()> {
+        let entry = JournalEntry {
+            action,
+            original: original.to_path_buf(),
+            destination: destination.map(Path::to_path_buf),
+            survivor: survivor.to_path_buf(),
+            hash: hash.to_hex(),
+            algo: self.algo.clone(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        let start = self.file.metadata()?.len();
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        self.last_start = Some(start);
+        Ok(())
+    }
+
+    /// Removes the entry written by the last call to `record`.
+    ///
+    /// Used when the recorded operation could not be applied. Does nothing if no entry is pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the journal file cannot be truncated.
+    pub fn revert(&mut self) -> Result<()> {
+        if let Some(start) = self.last_start.take() {
+            self.file
+                .set_len(start)
+                .map_err(|e| anyhow!("Error reverting journal {}: {}", self.path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads every entry of a journal file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the journal file.
+///
+/// # Returns
+///
+/// * The journal entries in the order they were written.
+///
+/// # Errors
+///
+/// Returns an error if the journal cannot be read or a line is not a valid entry.
+pub fn read_journal(path: &Path) -> Result<Vec<JournalEntry>> {
+    let file =
+        File::open(path).map_err(|e| anyhow!("Error opening journal {}: {}", path.display(), e))?;
+
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            anyhow!(
+                "Error parsing journal {} line {}: {}",
+                path.display(),
+                number + 1,
+                e
+            )
+        })?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Restores quarantined files recorded in a journal.
+///
+/// Moves are undone in reverse order. Removed and linked files cannot be restored and are skipped,
+/// as are files whose original path is occupied again.
+///
+/// # Arguments
+///
+/// * `path` - The path of the journal file.
+///
+/// # Returns
+///
+/// * The original paths of the restored files.
+///
+/// # Errors
+///
+/// Returns an error if the journal cannot be read.
+pub fn undo(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut restored = Vec::new();
+
+    for entry in read_journal(path)?.iter().rev() {
+        let Some(destination) = &entry.destination else {
+            continue;
+        };
+        if !destination.exists() || entry.original.exists() {
+            continue;
+        }
+
+        if let Some(parent) = entry.original.parent() {
+            if let Err(e) = fs::create_dir_all(parent) {
+                eprintln!("Failed to create directory {}: {}", parent.display(), e);
+                continue;
+            }
+        }
+
+        match move_file(destination, &entry.original) {
+            Ok(()) => restored.push(entry.original.clone()),
+            Err(e) => eprintln!("Failed to restore file {}: {}", entry.original.display(), e),
+        }
+    }
+
+    Ok(restored)
+}
@@ -1,4 +1,9 @@
+pub mod cluster;
 pub mod dedupe;
+pub mod dihedral;
 pub mod hashing;
+pub mod index;
+pub mod journal;
 pub mod normalize;
+pub mod segment;
 pub mod validate;
@@ -1,5 +1,282 @@
 use anyhow::Result;
-use image::{imageops::FilterType, DynamicImage};
+use image::imageops::{self, FilterType};
+use image::metadata::Orientation;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+use std::borrow::Cow;
+
+/// The color space produced by `NormalizeStep::Convert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Grayscale, the input of every hash that compares brightness. Transparency is kept.
+    Luma,
+    /// 8-bit RGB without transparency.
+    Rgb,
+}
+
+/// How transparent pixels are treated before an image is hashed.
+///
+/// Converting an image to grayscale drops its alpha channel, so the colors hidden under
+/// transparent pixels would end up in the hash. Compositing onto a solid background hashes
+/// the image the way it is displayed instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaPolicy {
+    /// Keeps the color channels as they are and drops the alpha channel.
+    #[default]
+    Ignore,
+    /// Composites the image onto a white background.
+    White,
+    /// Composites the image onto a black background.
+    Black,
+    /// Composites the image onto a solid RGB background color.
+    Color([u8; 3]),
+}
+
+impl AlphaPolicy {
+    /// Returns the background color transparent pixels are composited onto, or `None` for `Ignore`.
+    pub fn background(self) -> Option<[u8; 3]> {
+        match self {
+            AlphaPolicy::Ignore => None,
+            AlphaPolicy::White => Some([255, 255, 255]),
+            AlphaPolicy::Black => Some([0, 0, 0]),
+            AlphaPolicy::Color(color) => Some(color),
+        }
+    }
+}
+
+/// How an image is fitted to the input size of a hashing algorithm when the aspect ratios differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeMode {
+    /// Scales each axis independently, distorting the image. A wide banner then hashes like a
+    /// squeezed square thumbnail of itself.
+    #[default]
+    Stretch,
+    /// Crops the largest centered region with the target aspect ratio, then scales it.
+    /// Matches thumbnails cropped from the middle of a wider image.
+    CenterCrop,
+    /// Scales the whole image to fit inside the target and pads the rest with black,
+    /// or with transparent pixels for images with alpha. Keeps the full content undistorted.
+    Pad,
+}
+
+/// One preprocessing step of a `NormalizePipeline`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NormalizeStep {
+    /// Rotates and flips the image upright according to the orientation recorded in its metadata.
+    Orient,
+    /// Composites transparent pixels onto a solid RGB background color.
+    FlattenAlpha([u8; 3]),
+    /// Crops uniform borders, such as letterboxing, within `tolerance` of the top left pixel.
+    TrimBorder { tolerance: u8 },
+    /// Applies a Gaussian blur with the given standard deviation.
+    Blur(f32),
+    /// Equalizes the histogram, converting the image to grayscale. See `equalize`.
+    Equalize,
+    /// Resizes the image to the input size of the hashing algorithm. See `ResizeMode`.
+    Resize(FilterType, ResizeMode),
+    /// Converts the image to another color space.
+    Convert(ColorSpace),
+}
+
+/// An ordered list of preprocessing steps applied to every image before it is hashed.
+///
+/// Algorithms that hash a fixed-size image, such as `dhash` or `phash`, run every step.
+/// If the pipeline has no `Resize` step, the image is resized with `FilterType::Triangle`
+/// after the last step. Algorithms that hash the full image, such as `pdq` or `colorhash`,
+/// skip the `Resize` and `Convert` steps.
+///
+/// Grayscale hashes read the first channel of the image, so their pipelines should convert to
+/// `ColorSpace::Luma` after any color-dependent step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizePipeline {
+    /// The steps, applied in order.
+    pub steps: Vec<NormalizeStep>,
+}
+
+impl NormalizePipeline {
+    /// Creates the default pipeline, which resizes with `filter` and converts to grayscale like `proc`.
+    pub fn new(filter: FilterType) -> Self {
+        Self {
+            steps: vec![
+                NormalizeStep::Resize(filter, ResizeMode::Stretch),
+                NormalizeStep::Convert(ColorSpace::Luma),
+            ],
+        }
+    }
+
+    /// Adds an `Orient` step in front of the pipeline, so images are hashed the way they are displayed.
+    pub fn oriented(mut self) -> Self {
+        if !self.steps.contains(&NormalizeStep::Orient) {
+            self.steps.insert(0, NormalizeStep::Orient);
+        }
+        self
+    }
+
+    /// Replaces the `FlattenAlpha` steps of the pipeline according to `policy`.
+    ///
+    /// Transparent pixels are flattened right after the image is turned upright, before any
+    /// other step sees them. `AlphaPolicy::Ignore` removes flattening altogether.
+    pub fn with_alpha(mut self, policy: AlphaPolicy) -> Self {
+        self.steps
+            .retain(|step| !matches!(step, NormalizeStep::FlattenAlpha(_)));
+        if let Some(background) = policy.background() {
+            let index = self
+                .steps
+                .iter()
+                .take_while(|step| **step == NormalizeStep::Orient)
+                .count();
+            self.steps
+                .insert(index, NormalizeStep::FlattenAlpha(background));
+        }
+        self
+    }
+
+    /// Replaces the `TrimBorder` steps of the pipeline, or removes them if `tolerance` is `None`.
+    ///
+    /// Borders are trimmed right after the image is turned upright and flattened, so letterboxed
+    /// copies are resized from the same content as their originals.
+    pub fn with_trim(mut self, tolerance: Option<u8>) -> Self {
+        self.steps
+            .retain(|step| !matches!(step, NormalizeStep::TrimBorder { .. }));
+        if let Some(tolerance) = tolerance {
+            let index = self
+                .steps
+                .iter()
+                .take_while(|step| {
+                    matches!(step, NormalizeStep::Orient | NormalizeStep::FlattenAlpha(_))
+                })
+                .count();
+            self.steps
+                .insert(index, NormalizeStep::TrimBorder { tolerance });
+        }
+        self
+    }
+
+    /// Sets the `ResizeMode` of every `Resize` step of the pipeline.
+    pub fn with_resize_mode(mut self, mode: ResizeMode) -> Self {
+        for step in &mut self.steps {
+            if let NormalizeStep::Resize(_, current) = step {
+                *current = mode;
+            }
+        }
+        self
+    }
+
+    /// Splits the pipeline before its first `Resize` or `Convert` step.
+    ///
+    /// The first half only holds steps that work on the full image, such as `Orient` or
+    /// `TrimBorder`, so it can run once before an image is transformed or segmented.
+    /// The second half prepares each of the resulting images for the hashing algorithm.
+    pub(crate) fn split_at_resize(&self) -> (NormalizePipeline, NormalizePipeline) {
+        let index = self
+            .steps
+            .iter()
+            .position(|step| matches!(step, NormalizeStep::Resize(..) | NormalizeStep::Convert(_)))
+            .unwrap_or(self.steps.len());
+        let (full, rest) = self.steps.split_at(index);
+        (
+            NormalizePipeline {
+                steps: full.to_vec(),
+            },
+            NormalizePipeline {
+                steps: rest.to_vec(),
+            },
+        )
+    }
+
+    /// Runs the pipeline on an image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to the decoded `DynamicImage`.
+    /// * `orientation` - The orientation recorded in the image metadata, used by `NormalizeStep::Orient`.
+    /// * `size` - The width and height the hashing algorithm expects, or `None` to keep the full
+    ///   image and skip the `Resize` and `Convert` steps.
+    ///
+    /// # Returns
+    /// * The normalized `DynamicImage`.
+    pub fn apply(
+        &self,
+        image: &DynamicImage,
+        orientation: Orientation,
+        size: Option<(u32, u32)>,
+    ) -> Result<DynamicImage> {
+        self.run(image, orientation, size).map(Cow::into_owned)
+    }
+
+    /// Runs the pipeline, borrowing the image if no step changes it.
+    pub(crate) fn run<'a>(
+        &self,
+        image: &'a DynamicImage,
+        orientation: Orientation,
+        size: Option<(u32, u32)>,
+    ) -> Result<Cow<'a, DynamicImage>> {
+        self.run_trimmed(image, orientation, size)
+            .map(|(current, _trim)| current)
+    }
+
+    /// Runs the pipeline like `run`, also returning the border removed by its `TrimBorder` steps.
+    ///
+    /// The trim is measured on the image as it enters the first `TrimBorder` step, so after
+    /// orienting and flattening alpha but before resizing.
+    pub(crate) fn run_trimmed<'a>(
+        &self,
+        image: &'a DynamicImage,
+        orientation: Orientation,
+        size: Option<(u32, u32)>,
+    ) -> Result<(Cow<'a, DynamicImage>, Trim)> {
+        let mut current = Cow::Borrowed(image);
+        let mut trimmed = Trim::default();
+        for step in &self.steps {
+            current = match (step, size) {
+                (NormalizeStep::Orient, _) => {
+                    if orientation == Orientation::NoTransforms {
+                        current
+                    } else {
+                        let mut oriented = current.into_owned();
+                        oriented.apply_orientation(orientation);
+                        Cow::Owned(oriented)
+                    }
+                }
+                (NormalizeStep::FlattenAlpha(background), _) => {
+                    if current.color().has_alpha() {
+                        Cow::Owned(flatten_alpha(&current, *background))
+                    } else {
+                        current
+                    }
+                }
+                (NormalizeStep::TrimBorder { tolerance }, _) => {
+                    let trim = find_border(&current, *tolerance);
+                    if trim.is_empty() {
+                        current
+                    } else {
+                        trimmed.left += trim.left;
+                        trimmed.top += trim.top;
+                        trimmed.right += trim.right;
+                        trimmed.bottom += trim.bottom;
+                        Cow::Owned(trim.apply(&current))
+                    }
+                }
+                (NormalizeStep::Blur(sigma), _) => Cow::Owned(current.blur(*sigma)),
+                (NormalizeStep::Equalize, _) => Cow::Owned(equalize(&current)?),
+                (NormalizeStep::Resize(filter, mode), Some((width, height))) => {
+                    Cow::Owned(resize(&current, *filter, width, height, *mode))
+                }
+                (NormalizeStep::Convert(space), Some(_)) => Cow::Owned(match space {
+                    ColorSpace::Luma => current.grayscale(),
+                    ColorSpace::Rgb => DynamicImage::ImageRgb8(current.to_rgb8()),
+                }),
+                (NormalizeStep::Resize(..) | NormalizeStep::Convert(_), None) => current,
+            };
+        }
+
+        if let Some((width, height)) = size {
+            if current.dimensions() != (width, height) {
+                current = Cow::Owned(current.resize_exact(width, height, FilterType::Triangle));
+            }
+        }
+
+        Ok((current, trimmed))
+    }
+}
 
 /// Normalizes an image by resizing it to a given resolution and converting it to grayscale.
 ///
@@ -12,6 +289,9 @@ use image::{imageops::FilterType, DynamicImage};
 ///
 /// # Returns
 /// * A `DynamicImage` that has been resized to the given dimensions and converted to grayscale.
+///   Transparency is ignored, see `AlphaPolicy` and `NormalizePipeline::with_alpha` to composite
+///   transparent images onto a background first. The image is stretched to the new size,
+///   see `resize` to keep its aspect ratio instead.
 #[inline]
 pub fn proc(
     image: &DynamicImage,
@@ -21,3 +301,232 @@ pub fn proc(
 ) -> Result<DynamicImage> {
     Ok(image.resize_exact(width, height, filter).grayscale())
 }
+
+/// Resizes an image to the given resolution, fitting it according to `mode`.
+///
+/// # Arguments
+/// * `image` - A reference to a `DynamicImage` to be resized.
+/// * `filter` - The down sampling method to use during resizing.
+/// * `width` - The desired width of the resized image.
+/// * `height` - The desired height of the resized image.
+/// * `mode` - How to handle an aspect ratio that differs from `width` by `height`.
+///
+/// # Returns
+/// * A `DynamicImage` of exactly `width` by `height` pixels, keeping the color type of `image`.
+pub fn resize(
+    image: &DynamicImage,
+    filter: FilterType,
+    width: u32,
+    height: u32,
+    mode: ResizeMode,
+) -> DynamicImage {
+    let (image_width, image_height) = image.dimensions();
+    if image_width == 0 || image_height == 0 || width == 0 || height == 0 {
+        return image.resize_exact(width, height, filter);
+    }
+
+    // Compare aspect ratios without rounding: the image is wider if w / h > width / height
+    let image_is_wider = image_width as u64 * height as u64 > image_height as u64 * width as u64;
+    match mode {
+        ResizeMode::Stretch => image.resize_exact(width, height, filter),
+        ResizeMode::CenterCrop => {
+            let (crop_width, crop_height) = if image_is_wider {
+                let crop_width = (image_height as f64 * width as f64 / height as f64).round();
+                ((crop_width as u32).clamp(1, image_width), image_height)
+            } else {
+                let crop_height = (image_width as f64 * height as f64 / width as f64).round();
+                (image_width, (crop_height as u32).clamp(1, image_height))
+            };
+            image
+                .crop_imm(
+                    (image_width - crop_width) / 2,
+                    (image_height - crop_height) / 2,
+                    crop_width,
+                    crop_height,
+                )
+                .resize_exact(width, height, filter)
+        }
+        ResizeMode::Pad => {
+            let (fit_width, fit_height) = if image_is_wider {
+                let fit_height = (image_height as f64 * width as f64 / image_width as f64).round();
+                (width, (fit_height as u32).clamp(1, height))
+            } else {
+                let fit_width = (image_width as f64 * height as f64 / image_height as f64).round();
+                ((fit_width as u32).clamp(1, width), height)
+            };
+            let fitted = image.resize_exact(fit_width, fit_height, filter);
+
+            let mut canvas = DynamicImage::new(width, height, image.color());
+            imageops::overlay(
+                &mut canvas,
+                &fitted,
+                ((width - fit_width) / 2) as i64,
+                ((height - fit_height) / 2) as i64,
+            );
+            canvas
+        }
+    }
+}
+
+/// Equalizes the histogram of a grayscale image, spreading its brightness over the full range.
+///
+/// Follows OpenCV's `equalizeHist`: the darkest level present maps to 0 and the brightest to 255.
+///
+/// # Arguments
+/// * `image` - A reference to a `DynamicImage` to be equalized. It is converted to grayscale first.
+///
+/// # Returns
+/// * A grayscale `DynamicImage` with an equalized histogram.
+pub fn equalize(image: &DynamicImage) -> Result<DynamicImage> {
+    let mut gray = image.to_luma8();
+
+    let mut histogram = [0usize; 256];
+    for pixel in gray.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let total = gray.pixels().len();
+    let Some(first) = histogram.iter().position(|&count| count > 0) else {
+        return Ok(DynamicImage::ImageLuma8(gray));
+    };
+    if histogram[first] == total {
+        return Ok(DynamicImage::ImageLuma8(gray));
+    }
+
+    // Cumulative histogram, scaled so the first occupied level maps to 0
+    let scale = 255.0f32 / (total - histogram[first]) as f32;
+    let mut lut = [0u8; 256];
+    let mut sum = 0;
+    for level in first + 1..256 {
+        sum += histogram[level];
+        lut[level] = (sum as f32 * scale).round_ties_even().clamp(0.0, 255.0) as u8;
+    }
+
+    for pixel in gray.pixels_mut() {
+        pixel[0] = lut[pixel[0] as usize];
+    }
+    Ok(DynamicImage::ImageLuma8(gray))
+}
+
+/// Composites every pixel onto a solid background color according to its transparency.
+///
+/// # Arguments
+/// * `image` - A reference to a `DynamicImage` to be flattened.
+/// * `background` - The RGB color shown through transparent pixels.
+///
+/// # Returns
+/// * An RGB `DynamicImage` without transparency.
+pub fn flatten_alpha(image: &DynamicImage, background: [u8; 3]) -> DynamicImage {
+    let rgba = image.to_rgba8();
+    DynamicImage::ImageRgb8(RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |color: u8, background: u8| {
+            ((color as u32 * a as u32 + background as u32 * (255 - a as u32) + 127) / 255) as u8
+        };
+        Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    }))
+}
+
+/// The number of pixels removed from each edge of an image by `trim_border`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Trim {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl Trim {
+    /// Checks whether no pixels are removed.
+    pub fn is_empty(&self) -> bool {
+        *self == Trim::default()
+    }
+
+    /// Crops the trimmed edges from an image.
+    ///
+    /// # Arguments
+    /// * `image` - A reference to a `DynamicImage` to be cropped.
+    ///
+    /// # Returns
+    /// * The cropped `DynamicImage`, keeping at least one pixel in each direction.
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        if self.is_empty() {
+            return image.clone();
+        }
+        let width = image.width().saturating_sub(self.left + self.right).max(1);
+        let height = image.height().saturating_sub(self.top + self.bottom).max(1);
+        image.crop_imm(self.left, self.top, width, height)
+    }
+}
+
+/// Finds uniform borders, such as letterboxing or scanner margins, around an image.
+///
+/// Rows and columns count as border from each edge inward while all of their pixels are
+/// within `tolerance` of the top left pixel on every channel.
+///
+/// # Arguments
+/// * `image` - A reference to a `DynamicImage` to be inspected.
+/// * `tolerance` - The largest channel difference still counted as border.
+///
+/// # Returns
+/// * The `Trim` removing the borders, or an empty `Trim` if the whole image is uniform.
+pub fn find_border(image: &DynamicImage, tolerance: u8) -> Trim {
+    let rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    if width == 0 || height == 0 {
+        return Trim::default();
+    }
+
+    let reference = rgba.get_pixel(0, 0).0;
+    let is_border = |x: u32, y: u32| {
+        rgba.get_pixel(x, y)
+            .0
+            .iter()
+            .zip(&reference)
+            .all(|(&channel, &border)| channel.abs_diff(border) <= tolerance)
+    };
+    let row_is_border = |y: u32| (0..width).all(|x| is_border(x, y));
+    let column_is_border = |x: u32, top: u32, bottom: u32| (top..bottom).all(|y| is_border(x, y));
+
+    let Some(top) = (0..height).find(|&y| !row_is_border(y)) else {
+        return Trim::default();
+    };
+    let bottom = (top..height)
+        .rev()
+        .find(|&y| !row_is_border(y))
+        .unwrap_or(top)
+        + 1;
+    let left = (0..width)
+        .find(|&x| !column_is_border(x, top, bottom))
+        .unwrap_or(0);
+    let right = (left..width)
+        .rev()
+        .find(|&x| !column_is_border(x, top, bottom))
+        .unwrap_or(left)
+        + 1;
+
+    Trim {
+        left,
+        top,
+        right: width - right,
+        bottom: height - bottom,
+    }
+}
+
+/// Crops uniform borders, such as letterboxing or scanner margins, from an image.
+///
+/// See `find_border` for how borders are detected.
+///
+/// # Arguments
+/// * `image` - A reference to a `DynamicImage` to be trimmed.
+/// * `tolerance` - The largest channel difference still counted as border.
+///
+/// # Returns
+/// * The trimmed `DynamicImage`, or an unchanged copy if the whole image is uniform.
+pub fn trim_border(image: &DynamicImage, tolerance: u8) -> DynamicImage {
+    find_border(image, tolerance).apply(image)
+}
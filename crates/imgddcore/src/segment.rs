@@ -0,0 +1,297 @@
+use crate::dedupe::hash_image_with;
+use crate::hashing::ImageHash;
+use crate::normalize::NormalizePipeline;
+use anyhow::{anyhow, Result};
+use image::imageops::{self, FilterType};
+use image::metadata::Orientation;
+use image::{DynamicImage, GrayImage, Luma};
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Settings of the segmentation step of `crop_resistant_hash`.
+///
+/// The defaults match the `crop_resistant_hash` of the Python imagehash library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentOptions {
+    /// Keep only the largest segments, or every segment if `None`.
+    pub limit_segments: Option<usize>,
+    /// Brightness separating bright "hill" pixels from dark "valley" pixels.
+    pub segment_threshold: u8,
+    /// Segments with this many pixels or fewer are dropped.
+    pub min_segment_size: usize,
+    /// Side length of the grayscale image the segmentation runs on.
+    pub segmentation_image_size: u32,
+}
+
+impl Default for SegmentOptions {
+    fn default() -> Self {
+        Self {
+            limit_segments: None,
+            segment_threshold: 128,
+            min_segment_size: 500,
+            segmentation_image_size: 300,
+        }
+    }
+}
+
+/// The hashes of every segment of an image, as produced by `crop_resistant_hash`.
+///
+/// Two multi-hashes match when enough of their segments have a close counterpart,
+/// so an image still matches a copy that lost some of its regions to cropping.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageMultiHash {
+    /// One hash per segment, largest segment first when `limit_segments` was set.
+    pub segment_hashes: Vec<ImageHash>,
+}
+
+impl fmt::Display for ImageMultiHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hashes: Vec<String> = self.segment_hashes.iter().map(ImageHash::to_hex).collect();
+        f.write_str(&hashes.join(","))
+    }
+}
+
+impl ImageMultiHash {
+    /// Compares every segment with its closest counterpart in `other`.
+    ///
+    /// # Arguments
+    /// * `other` - The multi-hash to compare against.
+    /// * `hamming_cutoff` - The maximum distance of a matching segment.
+    ///   Defaults to a quarter of the segment hash length.
+    ///
+    /// # Returns
+    /// * The number of matching segments and the sum of their distances.
+    pub fn hash_diff(&self, other: &Self, hamming_cutoff: Option<u32>) -> (usize, u32) {
+        let cutoff = hamming_cutoff.unwrap_or_else(|| {
+            self.segment_hashes
+                .first()
+                .map_or(0, |hash| hash.len() as u32 / 4)
+        });
+
+        let distances: Vec<u32> = self
+            .segment_hashes
+            .iter()
+            .filter_map(|hash| {
+                other
+                    .segment_hashes
+                    .iter()
+                    .map(|other_hash| hash.hamming_distance(other_hash))
+                    .min()
+            })
+            .filter(|&distance| distance <= cutoff)
+            .collect();
+
+        (distances.len(), distances.iter().sum())
+    }
+
+    /// Checks whether two images match on at least `region_cutoff` segments.
+    ///
+    /// # Arguments
+    /// * `other` - The multi-hash to compare against.
+    /// * `region_cutoff` - The minimum number of matching segments.
+    /// * `hamming_cutoff` - The maximum distance of a matching segment, see `hash_diff`.
+    pub fn matches(&self, other: &Self, region_cutoff: usize, hamming_cutoff: Option<u32>) -> bool {
+        self.hash_diff(other, hamming_cutoff).0 >= region_cutoff
+    }
+}
+
+/// The pixel count and bounding box of a connected region of the segmentation image.
+struct Segment {
+    size: usize,
+    min_x: u32,
+    min_y: u32,
+    max_x: u32,
+    max_y: u32,
+}
+
+/// Computes a crop-resistant hash by hashing every segment of an image separately.
+///
+/// The image is shrunk to a blurred grayscale square and split into connected bright and dark regions.
+/// The bounding box of each region is cropped from the original image and hashed with `algo`.
+///
+/// # Arguments
+/// * `image` - The decoded image.
+/// * `filter` - The resize filter used to hash each segment.
+/// * `algo` - The hashing algorithm used for each segment, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid of each segment.
+/// * `options` - The segmentation settings.
+///
+/// # Returns
+/// * An `ImageMultiHash` with one hash per segment. Images without a large enough segment
+///   are hashed as a single segment.
+///
+/// # Errors
+/// Returns an error if the image is empty or a segment cannot be hashed.
+pub fn crop_resistant_hash(
+    image: &DynamicImage,
+    filter: FilterType,
+    algo: &str,
+    hash_size: u32,
+    options: &SegmentOptions,
+) -> Result<ImageMultiHash> {
+    crop_resistant_hash_with(
+        image,
+        Orientation::NoTransforms,
+        algo,
+        hash_size,
+        options,
+        &NormalizePipeline::new(filter),
+    )
+}
+
+/// Computes a crop-resistant hash like `crop_resistant_hash`, preprocessing the image with a
+/// custom pipeline.
+///
+/// The steps in front of the first `Resize` or `Convert` step, such as `Orient` or `TrimBorder`,
+/// run once on the full image before it is segmented. The remaining steps run on every segment.
+///
+/// # Arguments
+/// * `image` - The decoded image.
+/// * `orientation` - The orientation recorded in the image metadata, used by `NormalizeStep::Orient`.
+/// * `algo` - The hashing algorithm used for each segment, see `hash_image`.
+/// * `hash_size` - The side length of the hash grid of each segment.
+/// * `options` - The segmentation settings.
+/// * `pipeline` - The preprocessing steps, see `hash_image_with`.
+///
+/// # Returns
+/// * An `ImageMultiHash` with one hash per segment, see `crop_resistant_hash`.
+///
+/// # Errors
+/// Returns an error if the image cannot be normalized, is empty or a segment cannot be hashed.
+pub fn crop_resistant_hash_with(
+    image: &DynamicImage,
+    orientation: Orientation,
+    algo: &str,
+    hash_size: u32,
+    options: &SegmentOptions,
+    pipeline: &NormalizePipeline,
+) -> Result<ImageMultiHash> {
+    let (full, rest) = pipeline.split_at_resize();
+    let image = &*full.run(image, orientation, None)?;
+
+    let (width, height) = (image.width(), image.height());
+    let size = options.segmentation_image_size;
+    if width == 0 || height == 0 || size == 0 {
+        return Err(anyhow!(
+            "Cannot segment a {}x{} image at size {}",
+            width,
+            height,
+            size
+        ));
+    }
+
+    let gray = image
+        .grayscale()
+        .resize_exact(size, size, FilterType::Lanczos3)
+        .to_luma8();
+    let smoothed = median_filter(&imageops::blur(&gray, 2.0));
+
+    let mut segments: Vec<Segment> = find_segments(&smoothed, options.segment_threshold)
+        .into_iter()
+        .filter(|segment| segment.size > options.min_segment_size)
+        .collect();
+    if segments.is_empty() {
+        segments.push(Segment {
+            size: (size * size) as usize,
+            min_x: 0,
+            min_y: 0,
+            max_x: size - 1,
+            max_y: size - 1,
+        });
+    }
+    if let Some(limit) = options.limit_segments {
+        segments.sort_by_key(|segment| Reverse(segment.size));
+        segments.truncate(limit);
+    }
+
+    // Map each bounding box back onto the original image
+    let scale_x = width as f64 / size as f64;
+    let scale_y = height as f64 / size as f64;
+    let segment_hashes = segments
+        .iter()
+        .map(|segment| {
+            let left = ((segment.min_x as f64 * scale_x).round() as u32).min(width - 1);
+            let top = ((segment.min_y as f64 * scale_y).round() as u32).min(height - 1);
+            let right =
+                (((segment.max_x + 1) as f64 * scale_x).round() as u32).clamp(left + 1, width);
+            let bottom =
+                (((segment.max_y + 1) as f64 * scale_y).round() as u32).clamp(top + 1, height);
+
+            let crop = image.crop_imm(left, top, right - left, bottom - top);
+            hash_image_with(&crop, Orientation::NoTransforms, algo, hash_size, &rest)
+        })
+        .collect::<Result<Vec<ImageHash>>>()?;
+
+    Ok(ImageMultiHash { segment_hashes })
+}
+
+/// Splits an image into 4-connected regions of pixels above and at or below `threshold`.
+///
+/// Bright regions come first, each group ordered by its first pixel in row-major order.
+fn find_segments(image: &GrayImage, threshold: u8) -> Vec<Segment> {
+    let (width, height) = image.dimensions();
+    let bright: Vec<bool> = image.pixels().map(|pixel| pixel[0] > threshold).collect();
+    let mut assigned = vec![false; bright.len()];
+    let mut segments = Vec::new();
+
+    for hill in [true, false] {
+        for start in 0..bright.len() {
+            if assigned[start] || bright[start] != hill {
+                continue;
+            }
+
+            let mut segment = Segment {
+                size: 0,
+                min_x: u32::MAX,
+                min_y: u32::MAX,
+                max_x: 0,
+                max_y: 0,
+            };
+            let mut queue = VecDeque::from([start]);
+            assigned[start] = true;
+            while let Some(index) = queue.pop_front() {
+                let (x, y) = (
+                    (index % width as usize) as u32,
+                    (index / width as usize) as u32,
+                );
+                segment.size += 1;
+                segment.min_x = segment.min_x.min(x);
+                segment.min_y = segment.min_y.min(y);
+                segment.max_x = segment.max_x.max(x);
+                segment.max_y = segment.max_y.max(y);
+
+                let neighbours = [
+                    (x > 0).then(|| index - 1),
+                    (x + 1 < width).then(|| index + 1),
+                    (y > 0).then(|| index - width as usize),
+                    (y + 1 < height).then(|| index + width as usize),
+                ];
+                for next in neighbours.into_iter().flatten() {
+                    if !assigned[next] && bright[next] == hill {
+                        assigned[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            segments.push(segment);
+        }
+    }
+
+    segments
+}
+
+/// Replaces every pixel with the median of its 3x3 neighbourhood, repeating edge pixels.
+fn median_filter(image: &GrayImage) -> GrayImage {
+    let (width, height) = image.dimensions();
+    GrayImage::from_fn(width, height, |x, y| {
+        let mut window = [0u8; 9];
+        for (i, value) in window.iter_mut().enumerate() {
+            let nx = (x as i64 + i as i64 % 3 - 1).clamp(0, width as i64 - 1) as u32;
+            let ny = (y as i64 + i as i64 / 3 - 1).clamp(0, height as i64 - 1) as u32;
+            *value = image.get_pixel(nx, ny)[0];
+        }
+        window.sort_unstable();
+        Luma([window[4]])
+    })
+}
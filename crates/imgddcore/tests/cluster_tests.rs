@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use imgddcore::cluster::*;
+    use imgddcore::dedupe::similar_pairs;
+    use imgddcore::hashing::{ColorMomentHash, ImageHash};
+    use std::path::PathBuf;
+
+    fn mock_hash_paths() -> Vec<(ImageHash, PathBuf)> {
+        vec![
+            (ImageHash::from(0b0000_0011), PathBuf::from("c")),
+            (ImageHash::from(0b0000_0000), PathBuf::from("a")),
+            (ImageHash::from(0b0000_0001), PathBuf::from("b")),
+            (ImageHash::from(0b1111_0000), PathBuf::from("e")),
+            (ImageHash::from(0b1111_1000), PathBuf::from("d")),
+            (ImageHash::from(0b0101_0101_0101), PathBuf::from("f")),
+        ]
+    }
+
+    #[test]
+    fn test_cluster_duplicates_transitive() {
+        // "a" and "c" are 2 bits apart, but both are 1 bit from "b"
+        let clusters = cluster_duplicates(&mock_hash_paths(), 1);
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(
+            clusters[0],
+            DuplicateCluster {
+                id: 0,
+                members: vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")],
+                representative: PathBuf::from("a"),
+            }
+        );
+        assert_eq!(clusters[1].id, 1);
+        assert_eq!(
+            clusters[1].members,
+            vec![PathBuf::from("d"), PathBuf::from("e")]
+        );
+    }
+
+    #[test]
+    fn test_cluster_ids_are_stable() {
+        let mut reversed = mock_hash_paths();
+        reversed.reverse();
+
+        assert_eq!(
+            cluster_duplicates(&mock_hash_paths(), 1),
+            cluster_duplicates(&reversed, 1)
+        );
+    }
+
+    #[test]
+    fn test_cluster_pairs() {
+        let hash_paths = mock_hash_paths();
+
+        assert!(cluster_pairs::<_, u32>(&hash_paths, &[]).is_empty());
+
+        let clusters = cluster_pairs(&hash_paths, &[(0, 5, 6), (3, 5, 7)]);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].members,
+            vec![PathBuf::from("c"), PathBuf::from("e"), PathBuf::from("f")]
+        );
+        assert_eq!(clusters[0].representative, PathBuf::from("c"));
+    }
+
+    #[test]
+    fn test_cluster_color_moment_pairs() {
+        let image = image::open("../../imgs/test/apple_pie/21063.jpg").unwrap();
+        let other = image::open("../../imgs/test/baklava/21435.jpg").unwrap();
+        let hash_paths: Vec<(ColorMomentHash, PathBuf)> =
+            [(image.rotate90(), "b"), (other, "c"), (image.fliph(), "a")]
+                .into_iter()
+                .map(|(image, path)| {
+                    (
+                        ColorMomentHash::compute(&image).unwrap(),
+                        PathBuf::from(path),
+                    )
+                })
+                .collect();
+
+        // Rotated and flipped copies keep their color moments
+        let pairs = similar_pairs(&hash_paths, 0.001, ColorMomentHash::distance);
+        let clusters = cluster_pairs(&hash_paths, &pairs);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].members,
+            vec![PathBuf::from("a"), PathBuf::from("b")]
+        );
+    }
+}
@@ -1,17 +1,64 @@
 #[cfg(test)]
 mod tests {
     use image::imageops::FilterType;
-    use image::{DynamicImage, Rgba};
+    use image::metadata::Orientation;
+    use image::{DynamicImage, GrayImage, ImageFormat, Luma, Rgba};
     use imgddcore::dedupe::*;
+    use imgddcore::hashing::{ColorMomentHash, ImageHash};
+    use imgddcore::normalize::{AlphaPolicy, NormalizePipeline, NormalizeStep, ResizeMode, Trim};
     use std::fs::File;
-    use std::io::Write;
+    use std::io::{Cursor, Write};
     use std::panic;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     fn create_mock_image() -> DynamicImage {
         DynamicImage::ImageRgba8(image::ImageBuffer::from_pixel(9, 8, Rgba([255, 0, 0, 255])))
     }
 
+    /// Creates an opaque red square on a transparent background hiding `hidden` color values.
+    fn create_mock_logo(hidden: impl Fn(u32, u32) -> [u8; 3]) -> DynamicImage {
+        DynamicImage::ImageRgba8(image::ImageBuffer::from_fn(16, 16, |x, y| {
+            if (4..12).contains(&x) && (4..12).contains(&y) {
+                Rgba([255, 0, 0, 255])
+            } else {
+                let [r, g, b] = hidden(x, y);
+                Rgba([r, g, b, 0])
+            }
+        }))
+    }
+
+    /// Saves an image as a JPEG whose EXIF data records the given orientation tag value.
+    fn save_oriented_jpeg(image: &DynamicImage, path: &Path, orientation: u16) {
+        let mut jpeg = Cursor::new(Vec::new());
+        image.write_to(&mut jpeg, ImageFormat::Jpeg).unwrap();
+        let jpeg = jpeg.into_inner();
+
+        // Big-endian TIFF header with a single IFD entry: Orientation, SHORT, count 1
+        let mut exif = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01\x01\x12\0\x03\0\0\0\x01".to_vec();
+        exif.extend_from_slice(&orientation.to_be_bytes());
+        exif.extend_from_slice(&[0; 6]);
+
+        // Insert the APP1 segment right after the start of image marker
+        let mut file = File::create(path).unwrap();
+        file.write_all(&jpeg[..2]).unwrap();
+        file.write_all(&[0xFF, 0xE1]).unwrap();
+        file.write_all(&(exif.len() as u16 + 2).to_be_bytes())
+            .unwrap();
+        file.write_all(&exif).unwrap();
+        file.write_all(&jpeg[2..]).unwrap();
+    }
+
+    /// Creates a landscape image with large blocks that survive JPEG compression.
+    fn create_blocky_image() -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(64, 32, |x, y| {
+            Luma([if (x / 16 + y / 8 + x / 32) % 2 == 0 {
+                230
+            } else {
+                20
+            }])
+        }))
+    }
+
     #[test]
     fn test_collect_hashes() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -20,19 +67,343 @@ mod tests {
 
         let algorithms = ["dhash", "ahash", "mhash", "phash", "whash"];
         for algo in algorithms {
-            let hashes =
-                collect_hashes(&temp_dir.path().to_path_buf(), FilterType::Nearest, algo).unwrap();
+            let hashes = collect_hashes(temp_dir.path(), FilterType::Nearest, algo, 8).unwrap();
             assert_eq!(hashes.len(), 1, "Algorithm {} failed", algo);
         }
     }
 
+    #[test]
+    fn test_collect_hashes_hash_size() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("test_image.png");
+        create_mock_image().save(&image_path).unwrap();
+        let path = temp_dir.path().to_path_buf();
+
+        for (algo, bits) in [
+            ("dhash", 256),
+            ("verticaldhash", 256),
+            ("doubledhash", 512),
+            ("ahash", 256),
+            ("phash", 256),
+            ("whash", 256),
+            ("colorhash", 42),
+            ("pdq", 256),
+            ("blockhash", 256),
+            ("marrhildreth", 576),
+            ("blockmean", 256),
+            ("blockmeanoverlap", 961),
+        ] {
+            let hashes = collect_hashes(&path, FilterType::Nearest, algo, 16).unwrap();
+            assert_eq!(hashes[0].0.len(), bits, "Algorithm {} failed", algo);
+        }
+        let hashes = collect_hashes(&path, FilterType::Nearest, "mhash", 12).unwrap();
+        assert_eq!(hashes[0].0.len(), 144);
+
+        assert!(collect_hashes(&path, FilterType::Nearest, "whash", 12).is_err());
+        assert!(collect_hashes(&path, FilterType::Nearest, "blockhash", 9).is_err());
+        assert!(collect_hashes(&path, FilterType::Nearest, "dhash", 1).is_err());
+    }
+
+    #[test]
+    fn test_collect_pdq_hashes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_blocky_image()
+            .save(temp_dir.path().join("a_blocky.png"))
+            .unwrap();
+        GrayImage::from_pixel(64, 48, Luma([90]))
+            .save(temp_dir.path().join("b_flat.png"))
+            .unwrap();
+
+        let pipeline = NormalizePipeline::new(FilterType::Triangle);
+        let mut hashes = collect_pdq_hashes(temp_dir.path(), &pipeline);
+        hashes.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(hashes.len(), 2);
+
+        // The hashes match collect_hashes_with, which only drops the quality
+        let mut plain = collect_hashes_with(temp_dir.path(), "pdq", 8, &pipeline).unwrap();
+        plain.sort_by(|a, b| a.1.cmp(&b.1));
+        for ((hash, path, _), (plain_hash, plain_path)) in hashes.iter().zip(&plain) {
+            assert_eq!((hash, path), (plain_hash, plain_path));
+        }
+
+        // A flat image carries no information
+        assert!(hashes[0].2 > 0);
+        assert_eq!(hashes[1].2, 0);
+    }
+
+    #[test]
+    fn test_collect_canonical_hashes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image = DynamicImage::ImageLuma8(image::GrayImage::from_fn(40, 30, |x, y| {
+            image::Luma([(x * 6 + y * y / 4) as u8])
+        }));
+        image.save(temp_dir.path().join("original.png")).unwrap();
+        image
+            .rotate90()
+            .save(temp_dir.path().join("rotated.png"))
+            .unwrap();
+        image
+            .flipv()
+            .save(temp_dir.path().join("flipped.png"))
+            .unwrap();
+
+        let hashes =
+            collect_canonical_hashes(temp_dir.path(), FilterType::Triangle, "dhash", 8).unwrap();
+        assert_eq!(hashes.len(), 3);
+        assert!(hashes.iter().all(|(hash, _)| *hash == hashes[0].0));
+
+        let plain = collect_hashes(temp_dir.path(), FilterType::Triangle, "dhash", 8).unwrap();
+        assert!(plain.iter().any(|(hash, _)| *hash != plain[0].0));
+
+        assert!(
+            collect_canonical_hashes(temp_dir.path(), FilterType::Triangle, "whash", 12).is_err()
+        );
+    }
+
+    #[test]
+    fn test_collect_canonical_hashes_with_pipeline() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image = image::GrayImage::from_fn(40, 30, |x, y| image::Luma([(x * 6 + y * 3) as u8]));
+        DynamicImage::ImageLuma8(image.clone())
+            .save(temp_dir.path().join("original.png"))
+            .unwrap();
+
+        // A rotated copy with a white letterbox
+        let mut letterboxed = image::GrayImage::from_pixel(30, 60, image::Luma([255]));
+        image::imageops::replace(&mut letterboxed, &image::imageops::rotate90(&image), 0, 10);
+        letterboxed
+            .save(temp_dir.path().join("letterboxed.png"))
+            .unwrap();
+
+        // Borders are trimmed before the image is flipped and rotated
+        let pipeline = NormalizePipeline::new(FilterType::Triangle).with_trim(Some(0));
+        let hashes = collect_canonical_hashes_with(temp_dir.path(), "dhash", 8, &pipeline).unwrap();
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(hashes[0].0, hashes[1].0);
+
+        let plain =
+            collect_canonical_hashes(temp_dir.path(), FilterType::Triangle, "dhash", 8).unwrap();
+        assert_ne!(plain[0].0, plain[1].0);
+
+        assert!(collect_canonical_hashes_with(temp_dir.path(), "whash", 12, &pipeline).is_err());
+    }
+
+    #[test]
+    fn test_collect_hashes_with_pipeline() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let content = image::RgbImage::from_fn(40, 30, |x, y| {
+            image::Rgb([(x * 6) as u8, (y * 8) as u8, ((x + y) * 3) as u8])
+        });
+        let letterboxed = image::RgbImage::from_fn(40, 50, |x, y| match y {
+            10..40 => *content.get_pixel(x, y - 10),
+            _ => image::Rgb([0, 0, 0]),
+        });
+        content.save(temp_dir.path().join("content.png")).unwrap();
+        letterboxed
+            .save(temp_dir.path().join("letterboxed.png"))
+            .unwrap();
+
+        let plain = collect_hashes(temp_dir.path(), FilterType::Triangle, "ahash", 8).unwrap();
+        assert_ne!(plain[0].0, plain[1].0);
+
+        let pipeline = NormalizePipeline {
+            steps: vec![
+                NormalizeStep::TrimBorder { tolerance: 0 },
+                NormalizeStep::Resize(FilterType::Triangle, ResizeMode::Stretch),
+                NormalizeStep::Convert(imgddcore::normalize::ColorSpace::Luma),
+            ],
+        };
+        let trimmed = collect_hashes_with(temp_dir.path(), "ahash", 8, &pipeline).unwrap();
+        assert_eq!(trimmed.len(), 2);
+        assert_eq!(trimmed[0].0, trimmed[1].0);
+
+        assert!(collect_hashes_with(temp_dir.path(), "whash", 12, &pipeline).is_err());
+    }
+
+    #[test]
+    fn test_collect_trimmed_hashes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let content = image::RgbImage::from_fn(40, 30, |x, y| {
+            image::Rgb([50 + (x * 5) as u8, 50 + (y * 6) as u8, 100])
+        });
+        // Noisy black bars, as left by video compression
+        let bar = |x: u32, y: u32| image::Rgb([((x * 7 + y * 3) % 6) as u8, 0, 2]);
+        let letterboxed = image::RgbImage::from_fn(40, 50, |x, y| match y {
+            12..42 => *content.get_pixel(x, y - 12),
+            _ => bar(x, y),
+        });
+        let pillarboxed = image::RgbImage::from_fn(56, 30, |x, y| match x {
+            8..48 => *content.get_pixel(x - 8, y),
+            _ => bar(x, y),
+        });
+        content.save(temp_dir.path().join("a_content.png")).unwrap();
+        letterboxed
+            .save(temp_dir.path().join("b_letterboxed.png"))
+            .unwrap();
+        pillarboxed
+            .save(temp_dir.path().join("c_pillarboxed.png"))
+            .unwrap();
+
+        let pipeline = NormalizePipeline::new(FilterType::Triangle);
+        let mut hashes = collect_trimmed_hashes(temp_dir.path(), "ahash", 8, 8, &pipeline).unwrap();
+        hashes.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(hashes.len(), 3);
+
+        // Every copy hashes like the content, and the trims record the bars
+        assert!(hashes.iter().all(|(hash, ..)| *hash == hashes[0].0));
+        let trims: Vec<Trim> = hashes.iter().map(|(_, _, trim)| *trim).collect();
+        let bars = |left, top, right, bottom| Trim {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        assert_eq!(
+            trims,
+            vec![bars(0, 0, 0, 0), bars(0, 12, 0, 8), bars(8, 0, 8, 0)]
+        );
+
+        // Below the noise of the bars, nothing is trimmed
+        let untrimmed = collect_trimmed_hashes(temp_dir.path(), "ahash", 8, 0, &pipeline).unwrap();
+        assert!(untrimmed.iter().all(|(_, _, trim)| trim.is_empty()));
+
+        assert!(collect_trimmed_hashes(temp_dir.path(), "whash", 12, 8, &pipeline).is_err());
+    }
+
+    #[test]
+    fn test_collect_trimmed_hashes_transparent_border() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        // The transparent border hides noise, so it is only uniform once flattened
+        create_mock_logo(|x, y| [(x * 37 + y * 91) as u8, (x * y * 13) as u8, (y * 53) as u8])
+            .save(temp_dir.path().join("logo.png"))
+            .unwrap();
+
+        let pipeline = NormalizePipeline::new(FilterType::Triangle).with_alpha(AlphaPolicy::White);
+        let trimmed = collect_trimmed_hashes(temp_dir.path(), "ahash", 8, 8, &pipeline).unwrap();
+        assert_eq!(
+            trimmed[0].2,
+            Trim {
+                left: 4,
+                top: 4,
+                right: 4,
+                bottom: 4
+            }
+        );
+
+        // Hashes agree with the same pipeline run through `collect_hashes_with`
+        let hashes =
+            collect_hashes_with(temp_dir.path(), "ahash", 8, &pipeline.with_trim(Some(8))).unwrap();
+        assert_eq!(trimmed[0].0, hashes[0].0);
+    }
+
+    #[test]
+    fn test_hash_image_with_default_pipeline() {
+        let image = create_mock_image();
+        let pipeline = NormalizePipeline::new(FilterType::Triangle);
+
+        for algo in ["dhash", "doubledhash", "phash", "colorhash", "blockhash"] {
+            assert_eq!(
+                hash_image_with(&image, Orientation::NoTransforms, algo, 8, &pipeline).unwrap(),
+                hash_image(&image, FilterType::Triangle, algo, 8).unwrap(),
+                "Algorithm {} failed",
+                algo
+            );
+        }
+    }
+
+    #[test]
+    fn test_hash_image_with_alpha_policy() {
+        let clean = create_mock_logo(|_, _| [0, 0, 0]);
+        let noisy = create_mock_logo(|x, y| {
+            let noise = ((x * 37 + y * 91) ^ (x * y * 13)) % 256;
+            [noise as u8, (255 - noise) as u8, (noise * 7 % 256) as u8]
+        });
+        let hash = |image: &DynamicImage, policy| {
+            let pipeline = NormalizePipeline::new(FilterType::Triangle).with_alpha(policy);
+            hash_image_with(image, Orientation::NoTransforms, "ahash", 8, &pipeline).unwrap()
+        };
+
+        // Ignoring alpha hashes the noise hidden under the transparent pixels
+        assert_ne!(
+            hash(&clean, AlphaPolicy::Ignore),
+            hash(&noisy, AlphaPolicy::Ignore)
+        );
+        assert_eq!(
+            hash(&clean, AlphaPolicy::Ignore),
+            hash_image(&clean, FilterType::Triangle, "ahash", 8).unwrap()
+        );
+
+        // Compositing hashes only what is visible
+        for policy in [
+            AlphaPolicy::White,
+            AlphaPolicy::Black,
+            AlphaPolicy::Color([0, 128, 255]),
+        ] {
+            assert_eq!(hash(&clean, policy), hash(&noisy, policy), "{:?}", policy);
+        }
+
+        // The red square is darker than white but brighter than black
+        assert_ne!(
+            hash(&clean, AlphaPolicy::White),
+            hash(&clean, AlphaPolicy::Black)
+        );
+
+        // Opaque images are unaffected
+        assert_eq!(
+            hash(&create_mock_image(), AlphaPolicy::White),
+            hash(&create_mock_image(), AlphaPolicy::Ignore)
+        );
+    }
+
+    #[test]
+    fn test_hash_image_with_resize_mode() {
+        // A square thumbnail cut from the middle of a wide banner
+        let banner = DynamicImage::ImageLuma8(GrayImage::from_fn(90, 30, |x, y| {
+            Luma([((x * 7 + y * 13) % 97 + (x / 10) * 15) as u8])
+        }));
+        let thumbnail = banner.crop_imm(30, 0, 30, 30);
+        let hash = |image: &DynamicImage, mode| {
+            let pipeline = NormalizePipeline::new(FilterType::Triangle).with_resize_mode(mode);
+            hash_image_with(image, Orientation::NoTransforms, "ahash", 8, &pipeline).unwrap()
+        };
+
+        // Stretching squeezes the whole banner, cropping keeps only the thumbnail
+        assert_ne!(
+            hash(&banner, ResizeMode::Stretch),
+            hash(&thumbnail, ResizeMode::Stretch)
+        );
+        assert_eq!(
+            hash(&banner, ResizeMode::CenterCrop),
+            hash(&thumbnail, ResizeMode::CenterCrop)
+        );
+
+        // Padding keeps the proportions of the banner, so a squeezed copy no longer matches
+        let squeezed = banner.resize_exact(45, 30, FilterType::Triangle);
+        assert!(
+            hash(&banner, ResizeMode::Stretch)
+                .hamming_distance(&hash(&squeezed, ResizeMode::Stretch))
+                < hash(&banner, ResizeMode::Pad)
+                    .hamming_distance(&hash(&squeezed, ResizeMode::Pad))
+        );
+        assert_eq!(
+            hash(&banner, ResizeMode::Stretch),
+            hash_image(&banner, FilterType::Triangle, "ahash", 8).unwrap()
+        );
+    }
+
     #[test]
     fn test_sort_hashes() {
-        let mut hashes = vec![(2, PathBuf::from("b")), (1, PathBuf::from("a"))];
+        let mut hashes = vec![
+            (ImageHash::from(2), PathBuf::from("b")),
+            (ImageHash::from(1), PathBuf::from("a")),
+        ];
         sort_hashes(&mut hashes);
         assert_eq!(
             hashes,
-            vec![(1, PathBuf::from("a")), (2, PathBuf::from("b"))]
+            vec![
+                (ImageHash::from(1), PathBuf::from("a")),
+                (ImageHash::from(2), PathBuf::from("b"))
+            ]
         );
     }
 
@@ -43,11 +414,7 @@ mod tests {
         create_mock_image().save(&image_path).unwrap();
 
         let result = panic::catch_unwind(|| {
-            collect_hashes(
-                &temp_dir.path().to_path_buf(),
-                FilterType::Nearest,
-                "unsupported_algo",
-            )
+            collect_hashes(temp_dir.path(), FilterType::Nearest, "unsupported_algo", 8)
         });
 
         assert!(result.is_err()); // Should panic due to unsupported algorithm
@@ -62,7 +429,7 @@ mod tests {
         let mut file = File::create(&invalid_image_path).unwrap();
         file.write_all(b"not a valid image").unwrap();
 
-        let result = collect_hashes(&temp_dir.path().to_path_buf(), FilterType::Nearest, "dhash");
+        let result = collect_hashes(temp_dir.path(), FilterType::Nearest, "dhash", 8);
         assert!(result.is_ok()); // Valid path, but should log errors for invalid image
     }
 
@@ -74,7 +441,7 @@ mod tests {
         // Create empty file that can't be decoded
         File::create(&invalid_image_path).unwrap();
 
-        let result = collect_hashes(&temp_dir.path().to_path_buf(), FilterType::Nearest, "dhash");
+        let result = collect_hashes(temp_dir.path(), FilterType::Nearest, "dhash", 8);
         assert!(result.is_ok()); // Valid path, but decode errors should be logged
     }
 
@@ -96,6 +463,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_open_image_with_orientation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("rotated.jpg");
+        save_oriented_jpeg(&create_blocky_image(), &image_path, 6);
+
+        // Orientation 6 asks for a clockwise quarter turn, so the landscape image turns portrait
+        let oriented = open_image_with(&image_path, true).unwrap();
+        assert_eq!((oriented.width(), oriented.height()), (32, 64));
+
+        let raw = open_image_with(&image_path, false).unwrap();
+        assert_eq!((raw.width(), raw.height()), (64, 32));
+        assert_eq!(raw, open_image(&image_path).unwrap());
+    }
+
+    #[test]
+    fn test_collect_hashes_with_orientation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image = create_blocky_image();
+        save_oriented_jpeg(&image, &temp_dir.path().join("a_rotated.jpg"), 6);
+        image
+            .rotate90()
+            .save(temp_dir.path().join("b_upright.jpg"))
+            .unwrap();
+
+        let pipeline = NormalizePipeline::new(FilterType::Triangle);
+        let oriented = pipeline.clone().oriented();
+        assert_eq!(oriented.steps[0], NormalizeStep::Orient);
+        assert_eq!(oriented.clone().oriented(), oriented);
+
+        let distance = |pipeline: &NormalizePipeline| {
+            let mut hashes = collect_hashes_with(temp_dir.path(), "ahash", 8, pipeline).unwrap();
+            hashes.sort_by(|a, b| a.1.cmp(&b.1));
+            hashes[0].0.hamming_distance(&hashes[1].0)
+        };
+        assert!(distance(&oriented) <= 2);
+        assert!(distance(&pipeline) > 16);
+    }
+
     #[test]
     fn test_find_duplicates_remove_file_success() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -107,10 +513,19 @@ mod tests {
         std::fs::write(&file_path_2, b"file 2 content").unwrap();
 
         // Mock duplicate hash paths
-        let hash_paths = vec![(1, file_path_1.clone()), (1, file_path_2.clone())];
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path_1.clone()),
+            (ImageHash::from(1), file_path_2.clone()),
+        ];
 
-        // Test with `remove = true` to trigger file deletion
-        let result = find_duplicates(&hash_paths, true);
+        // Test with `DuplicateAction::Remove` to trigger file deletion
+        let result = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Remove,
+            &KeepPolicy::First,
+            None,
+        );
         assert!(result.is_ok());
 
         // First file should remain
@@ -124,7 +539,7 @@ mod tests {
     fn test_find_duplicates_remove_file_error() {
         let temp_dir = tempfile::tempdir().unwrap();
         let file_path_1 = temp_dir.path().join("test_file_1.txt");
-        let file_path_2 = temp_dir.path().join("nonexistent_file.txt");
+        let file_path_2 = temp_dir.path().join("test_file_2_nonexistent.txt");
 
         // Create single dummy file
         std::fs::write(&file_path_1, b"file 1 content").unwrap();
@@ -133,10 +548,19 @@ mod tests {
         assert!(file_path_1.exists());
 
         // Mock duplicate hash paths, including a non-existent file
-        let hash_paths = vec![(1, file_path_1.clone()), (1, file_path_2.clone())];
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path_1.clone()),
+            (ImageHash::from(1), file_path_2.clone()),
+        ];
 
-        // Test with `remove = true` to trigger file deletion
-        let result = find_duplicates(&hash_paths, true);
+        // Test with `DuplicateAction::Remove` to trigger file deletion
+        let result = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Remove,
+            &KeepPolicy::First,
+            None,
+        );
         assert!(result.is_ok());
 
         // First file should remain untouched
@@ -149,4 +573,584 @@ mod tests {
             file_path_2.display()
         );
     }
+
+    #[test]
+    fn test_hamming_distance() {
+        let hash = |value: u64| ImageHash::from(value);
+        assert_eq!(hamming_distance(&hash(0b1010), &hash(0b1010)), 0);
+        assert_eq!(hamming_distance(&hash(0b1010), &hash(0b0101)), 4);
+        assert_eq!(hamming_distance(&hash(0), &hash(u64::MAX)), 64);
+    }
+
+    #[test]
+    fn test_find_duplicates_max_distance() {
+        let hash_paths = vec![
+            (ImageHash::from(0b0000), PathBuf::from("a")),
+            (ImageHash::from(0b0001), PathBuf::from("b")),
+            (ImageHash::from(0b0011), PathBuf::from("c")),
+            (ImageHash::from(0b1111_0000), PathBuf::from("d")),
+        ];
+
+        // Exact matching finds nothing
+        let exact = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Report,
+            &KeepPolicy::First,
+            None,
+        )
+        .unwrap();
+        assert!(exact.is_empty());
+
+        // Paths within 2 bits of the first path are grouped together
+        let near = find_duplicates(
+            &hash_paths,
+            2,
+            &DuplicateAction::Report,
+            &KeepPolicy::First,
+            None,
+        )
+        .unwrap();
+        assert_eq!(near.len(), 1);
+        assert_eq!(near[0].hash, ImageHash::from(0b0000));
+        assert_eq!(
+            near[0].paths(),
+            vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")]
+        );
+    }
+
+    #[test]
+    fn test_find_duplicates_cluster_ids() {
+        // a-b, b-c and c-d are within 2 bits, but a and c or b and d are not
+        let hash_paths = vec![
+            (ImageHash::from(0x00), PathBuf::from("a")),
+            (ImageHash::from(0x03), PathBuf::from("b")),
+            (ImageHash::from(0x0f), PathBuf::from("c")),
+            (ImageHash::from(0x3f), PathBuf::from("d")),
+            (ImageHash::from(0xff00), PathBuf::from("e")),
+            (ImageHash::from(0xff00), PathBuf::from("f")),
+        ];
+
+        // Transitively linked groups share a cluster ID
+        let near = find_duplicates(
+            &hash_paths,
+            2,
+            &DuplicateAction::Report,
+            &KeepPolicy::First,
+            None,
+        )
+        .unwrap();
+        let clusters: Vec<(Vec<PathBuf>, usize)> = near
+            .iter()
+            .map(|group| (group.paths(), group.cluster_id))
+            .collect();
+        assert_eq!(
+            clusters,
+            vec![
+                (vec![PathBuf::from("a"), PathBuf::from("b")], 0),
+                (vec![PathBuf::from("c"), PathBuf::from("d")], 0),
+                (vec![PathBuf::from("e"), PathBuf::from("f")], 1),
+            ]
+        );
+
+        // Exact groups are their own clusters
+        let exact = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Report,
+            &KeepPolicy::First,
+            None,
+        )
+        .unwrap();
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].cluster_id, 0);
+    }
+
+    #[test]
+    fn test_multi_index_pairs() {
+        let hash_paths = vec![
+            (ImageHash::from(0b0000), PathBuf::from("a")),
+            (ImageHash::from(0b0001), PathBuf::from("b")),
+            (ImageHash::from(0b0011), PathBuf::from("c")),
+            (ImageHash::from(0b1111_0000), PathBuf::from("d")),
+        ];
+
+        assert!(multi_index_pairs(&hash_paths, 0).is_empty());
+        assert_eq!(
+            multi_index_pairs(&hash_paths, 2),
+            vec![(0, 1, 1), (0, 2, 2), (1, 2, 1)]
+        );
+        assert_eq!(multi_index_pairs(&hash_paths, 64).len(), 6);
+    }
+
+    #[test]
+    fn test_similar_pairs() {
+        let hash_paths = vec![
+            (1.0, PathBuf::from("a")),
+            (1.5, PathBuf::from("b")),
+            (4.0, PathBuf::from("c")),
+            (4.25, PathBuf::from("d")),
+        ];
+        let distance = |a: &f64, b: &f64| (a - b).abs();
+
+        assert!(similar_pairs(&hash_paths, 0.1, distance).is_empty());
+        assert_eq!(
+            similar_pairs(&hash_paths, 0.5, distance),
+            vec![(0, 1, 0.5), (2, 3, 0.25)]
+        );
+        assert_eq!(similar_pairs(&hash_paths, 10.0, distance).len(), 6);
+
+        // Matches multi-index hashing with the Hamming distance
+        let hashes = vec![
+            (ImageHash::from(0b0000), PathBuf::from("a")),
+            (ImageHash::from(0b0001), PathBuf::from("b")),
+            (ImageHash::from(0b0111), PathBuf::from("c")),
+        ];
+        let pairs = similar_pairs(&hashes, 2.0, |a, b| hamming_distance(a, b) as f64);
+        let expected: Vec<(usize, usize, f64)> = multi_index_pairs(&hashes, 2)
+            .into_iter()
+            .map(|(i, j, d)| (i, j, d as f64))
+            .collect();
+        assert_eq!(pairs, expected);
+    }
+
+    #[test]
+    fn test_collect_features_color_moments() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image = image::open("../../imgs/test/apple_pie/21063.jpg").unwrap();
+        image.save(temp_dir.path().join("original.png")).unwrap();
+        image
+            .rotate90()
+            .save(temp_dir.path().join("rotated.png"))
+            .unwrap();
+        image::open("../../imgs/test/baklava/21435.jpg")
+            .unwrap()
+            .save(temp_dir.path().join("other.png"))
+            .unwrap();
+        File::create(temp_dir.path().join("invalid.png")).unwrap();
+
+        let mut features = collect_features(temp_dir.path(), ColorMomentHash::compute);
+        features.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(features.len(), 3);
+
+        // Only the rotated copy is close to the original
+        let pairs = similar_pairs(&features, 0.001, ColorMomentHash::distance);
+        assert_eq!(pairs.len(), 1);
+        let (i, j, _) = pairs[0];
+        assert!(features[i].1.ends_with("original.png"));
+        assert!(features[j].1.ends_with("rotated.png"));
+    }
+
+    #[test]
+    fn test_multi_index_pairs_matches_linear_scan() {
+        // Pseudo-random hashes with planted near-duplicates
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let mut hash_paths: Vec<(ImageHash, PathBuf)> = Vec::new();
+        for i in 0..300 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            hash_paths.push((ImageHash::from(state), PathBuf::from(format!("{i}"))));
+            hash_paths.push((
+                ImageHash::from(state ^ (0b101 << (i % 60))),
+                PathBuf::from(format!("{i}-near")),
+            ));
+        }
+
+        for max_distance in [0, 1, 2, 5, 12] {
+            let mut expected = Vec::new();
+            for i in 0..hash_paths.len() {
+                for j in (i + 1)..hash_paths.len() {
+                    let distance = hamming_distance(&hash_paths[i].0, &hash_paths[j].0);
+                    if distance <= max_distance {
+                        expected.push((i, j, distance));
+                    }
+                }
+            }
+            assert_eq!(multi_index_pairs(&hash_paths, max_distance), expected);
+        }
+    }
+
+    #[test]
+    fn test_multi_index_pairs_wide_hashes() {
+        // 192-bit hashes, split into substrings spanning several words
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut hash_paths: Vec<(ImageHash, PathBuf)> = Vec::new();
+        for i in 0..100 {
+            let words = [next(), next(), next()];
+            let bits = |flip: usize| {
+                (0..192).map(move |k| (words[k / 64] >> (63 - k % 64) & 1 == 1) ^ (k % 50 == flip))
+            };
+            hash_paths.push((
+                ImageHash::from_bits(bits(usize::MAX)),
+                PathBuf::from(format!("{i}")),
+            ));
+            hash_paths.push((
+                ImageHash::from_bits(bits(i % 50)),
+                PathBuf::from(format!("{i}-near")),
+            ));
+        }
+
+        for max_distance in [0, 3, 4, 20] {
+            let mut expected = Vec::new();
+            for i in 0..hash_paths.len() {
+                for j in (i + 1)..hash_paths.len() {
+                    let distance = hamming_distance(&hash_paths[i].0, &hash_paths[j].0);
+                    if distance <= max_distance {
+                        expected.push((i, j, distance));
+                    }
+                }
+            }
+            assert_eq!(multi_index_pairs(&hash_paths, max_distance), expected);
+        }
+    }
+
+    #[test]
+    fn test_select_survivor() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let small = temp_dir.path().join("small.png");
+        let large = temp_dir.path().join("originals").join("large.jpg");
+        std::fs::create_dir(temp_dir.path().join("originals")).unwrap();
+
+        DynamicImage::ImageRgba8(image::ImageBuffer::from_pixel(4, 4, Rgba([255, 0, 0, 255])))
+            .save(&small)
+            .unwrap();
+        DynamicImage::ImageRgb8(image::ImageBuffer::from_pixel(
+            64,
+            64,
+            image::Rgb([255, 0, 0]),
+        ))
+        .save(&large)
+        .unwrap();
+
+        // Make the small image the oldest
+        let hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        File::options()
+            .write(true)
+            .open(&small)
+            .unwrap()
+            .set_modified(hour_ago)
+            .unwrap();
+
+        let paths = vec![small.clone(), large.clone()];
+        assert_eq!(select_survivor(&paths, &KeepPolicy::First), Some(0));
+        assert_eq!(
+            select_survivor(&paths, &KeepPolicy::LargestResolution),
+            Some(1)
+        );
+        assert_eq!(
+            select_survivor(&paths, &KeepPolicy::LargestFileSize),
+            Some(1)
+        );
+        assert_eq!(select_survivor(&paths, &KeepPolicy::Oldest), Some(0));
+        assert_eq!(select_survivor(&paths, &KeepPolicy::Newest), Some(1));
+        assert_eq!(
+            select_survivor(
+                &paths,
+                &KeepPolicy::PreferredFormat(vec![ImageFormat::Jpeg, ImageFormat::Png])
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            select_survivor(
+                &paths,
+                &KeepPolicy::PathPriority(vec![temp_dir.path().join("originals")])
+            ),
+            Some(1)
+        );
+        assert_eq!(select_survivor(&[], &KeepPolicy::First), None);
+    }
+
+    #[test]
+    fn test_find_duplicates_remove_with_keep_policy() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path_1 = temp_dir.path().join("test_file_1.txt");
+        let file_path_2 = temp_dir.path().join("test_file_2.txt");
+
+        // Second file is larger
+        std::fs::write(&file_path_1, b"small").unwrap();
+        std::fs::write(&file_path_2, b"larger content").unwrap();
+
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path_1.clone()),
+            (ImageHash::from(1), file_path_2.clone()),
+        ];
+        let result = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Remove,
+            &KeepPolicy::LargestFileSize,
+            None,
+        );
+        assert!(result.is_ok());
+
+        // Largest file should remain
+        assert!(!file_path_1.exists());
+        assert!(file_path_2.exists());
+    }
+
+    #[test]
+    fn test_quarantine_file() {
+        let root = tempfile::tempdir().unwrap();
+        let quarantine = tempfile::tempdir().unwrap();
+        let nested = root.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let file_path = nested.join("c.jpg");
+        std::fs::write(&file_path, b"first").unwrap();
+        let moved = quarantine_file(&file_path, root.path(), quarantine.path()).unwrap();
+
+        // Relative path is preserved
+        assert_eq!(moved, quarantine.path().join("a").join("b").join("c.jpg"));
+        assert!(!file_path.exists());
+        assert_eq!(std::fs::read(&moved).unwrap(), b"first");
+
+        // Name collisions get a numeric suffix
+        std::fs::write(&file_path, b"second").unwrap();
+        let moved = quarantine_file(&file_path, root.path(), quarantine.path()).unwrap();
+        assert_eq!(moved, quarantine.path().join("a").join("b").join("c_1.jpg"));
+        assert_eq!(std::fs::read(&moved).unwrap(), b"second");
+
+        // Missing files return the rename error without attempting a copy
+        let result = quarantine_file(&file_path, root.path(), quarantine.path());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Error moving file"));
+        assert!(!quarantine
+            .path()
+            .join("a")
+            .join("b")
+            .join("c_2.jpg")
+            .exists());
+    }
+
+    #[test]
+    fn test_quarantine_dir_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let file_path = root.path().join("c.jpg");
+        std::fs::write(&file_path, b"content").unwrap();
+
+        // The root itself and its subdirectories, existing or not, are rejected
+        for dir in [
+            root.path().to_path_buf(),
+            root.path().join("quarantine"),
+            root.path().join("a").join("..").join("quarantine"),
+        ] {
+            assert!(
+                check_quarantine_dir(root.path(), &dir).is_err(),
+                "{:?}",
+                dir
+            );
+            assert!(quarantine_file(&file_path, root.path(), &dir).is_err());
+        }
+        assert!(file_path.exists());
+
+        // Siblings sharing a name prefix are outside of the root
+        let sibling = PathBuf::from(format!("{}-quarantine", root.path().display()));
+        assert!(check_quarantine_dir(root.path(), &sibling).is_ok());
+
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path.clone()),
+            (ImageHash::from(1), root.path().join("d.jpg")),
+        ];
+        let action = DuplicateAction::Quarantine {
+            root: root.path().to_path_buf(),
+            dir: root.path().join("quarantine"),
+        };
+        assert!(find_duplicates(&hash_paths, 0, &action, &KeepPolicy::First, None).is_err());
+    }
+
+    #[test]
+    fn test_find_duplicates_quarantine() {
+        let root = tempfile::tempdir().unwrap();
+        let quarantine = tempfile::tempdir().unwrap();
+        let file_path_1 = root.path().join("test_file_1.txt");
+        let file_path_2 = root.path().join("test_file_2.txt");
+        std::fs::write(&file_path_1, b"file 1 content").unwrap();
+        std::fs::write(&file_path_2, b"file 2 content").unwrap();
+
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path_1.clone()),
+            (ImageHash::from(1), file_path_2.clone()),
+        ];
+        let action = DuplicateAction::Quarantine {
+            root: root.path().to_path_buf(),
+            dir: quarantine.path().to_path_buf(),
+        };
+        let result = find_duplicates(&hash_paths, 0, &action, &KeepPolicy::First, None);
+        assert!(result.is_ok());
+
+        // Duplicate is moved rather than deleted
+        assert!(file_path_1.exists());
+        assert!(!file_path_2.exists());
+        assert!(quarantine.path().join("test_file_2.txt").exists());
+    }
+
+    #[test]
+    fn test_find_duplicates_link() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path_1 = temp_dir.path().join("test_file_1.txt");
+        let file_path_2 = temp_dir.path().join("test_file_2.txt");
+        let file_path_3 = temp_dir.path().join("test_file_3.txt");
+        std::fs::write(&file_path_1, b"file 1 content").unwrap();
+        std::fs::write(&file_path_2, b"file 2 content").unwrap();
+        std::fs::write(&file_path_3, b"file 3 content").unwrap();
+
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path_1.clone()),
+            (ImageHash::from(1), file_path_2.clone()),
+        ];
+        let action = DuplicateAction::Link(LinkKind::Hard);
+        let result = find_duplicates(&hash_paths, 0, &action, &KeepPolicy::First, None);
+        assert!(result.is_ok());
+
+        // Duplicate path keeps existing but now shares the kept file's content
+        assert_eq!(std::fs::read(&file_path_2).unwrap(), b"file 1 content");
+        let leftovers = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().contains(".imgdd-link")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        #[cfg(unix)]
+        {
+            link_file(&file_path_3, &file_path_1, LinkKind::Symbolic).unwrap();
+            let target = std::fs::read_link(&file_path_3).unwrap();
+            assert_eq!(target, std::fs::canonicalize(&file_path_1).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_link_file_existing_temporary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("test_file.txt");
+        let target = temp_dir.path().join("target.txt");
+        std::fs::write(&file_path, b"content").unwrap();
+        std::fs::write(&target, b"target content").unwrap();
+
+        // A temporary name left by an interrupted run and an unrelated file with the next name
+        let stale = temp_dir
+            .path()
+            .join(format!("test_file.txt.imgdd-link-{}-0", std::process::id()));
+        let legacy = temp_dir.path().join("test_file.txt.imgdd-link");
+        std::fs::write(&stale, b"stale").unwrap();
+        std::fs::write(&legacy, b"legacy").unwrap();
+
+        link_file(&file_path, &target, LinkKind::Hard).unwrap();
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"target content");
+        assert_eq!(std::fs::read(&stale).unwrap(), b"stale");
+        assert_eq!(std::fs::read(&legacy).unwrap(), b"legacy");
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 4);
+    }
+
+    #[test]
+    fn test_link_file_missing_target() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("test_file.txt");
+        std::fs::write(&file_path, b"content").unwrap();
+
+        // The original file is left untouched when the link cannot be created
+        let result = link_file(
+            &file_path,
+            &temp_dir.path().join("missing.txt"),
+            LinkKind::Hard,
+        );
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"content");
+    }
+
+    #[test]
+    fn test_find_duplicates_groups() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let image_path = temp_dir.path().join("c.png");
+        let file_path = temp_dir.path().join("a.txt");
+        create_mock_image().save(&image_path).unwrap();
+        std::fs::write(&file_path, b"content").unwrap();
+
+        // A run of three identical hashes lists every path once, sorted by path
+        let hash_paths = vec![
+            (ImageHash::from(7), image_path.clone()),
+            (ImageHash::from(7), file_path.clone()),
+            (ImageHash::from(7), image_path.clone()),
+            (ImageHash::from(9), temp_dir.path().join("b.txt")),
+        ];
+        let groups = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Report,
+            &KeepPolicy::First,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].hash, ImageHash::from(7));
+        assert_eq!(
+            groups[0].members,
+            vec![
+                DuplicateMember {
+                    path: file_path,
+                    hash: ImageHash::from(7),
+                    file_size: 7,
+                    dimensions: None,
+                },
+                DuplicateMember {
+                    file_size: std::fs::metadata(&image_path).unwrap().len(),
+                    path: image_path,
+                    hash: ImageHash::from(7),
+                    dimensions: Some((9, 8)),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_duplicates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path_1 = temp_dir.path().join("test_file_1.txt");
+        let file_path_2 = temp_dir.path().join("test_file_2.txt");
+        let file_path_3 = temp_dir.path().join("test_file_3.txt");
+        std::fs::write(&file_path_1, b"1").unwrap();
+        std::fs::write(&file_path_2, b"22").unwrap();
+        std::fs::write(&file_path_3, b"333").unwrap();
+
+        let hash_paths = vec![
+            (ImageHash::from(1), file_path_1.clone()),
+            (ImageHash::from(1), file_path_2.clone()),
+            (ImageHash::from(1), file_path_3.clone()),
+        ];
+        let duplicates = find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Report,
+            &KeepPolicy::First,
+            None,
+        )
+        .unwrap();
+
+        let plan = plan_duplicates(&duplicates, &KeepPolicy::LargestFileSize);
+        assert_eq!(
+            plan,
+            vec![PlannedAction {
+                keep: file_path_3.clone(),
+                remove: vec![file_path_1.clone(), file_path_2.clone()],
+                reason: "largest file size".to_string(),
+                bytes_reclaimed: 3,
+            }]
+        );
+
+        // Planning never touches the filesystem
+        assert!(file_path_1.exists() && file_path_2.exists() && file_path_3.exists());
+        assert!(plan_duplicates(&[], &KeepPolicy::First).is_empty());
+    }
 }
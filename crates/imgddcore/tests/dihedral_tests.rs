@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use image::imageops::FilterType;
+    use image::metadata::Orientation;
+    use image::{DynamicImage, GrayImage, Luma};
+    use imgddcore::dedupe::hash_image;
+    use imgddcore::dihedral::*;
+    use imgddcore::normalize::NormalizePipeline;
+
+    /// Creates an image without any symmetry, so every transform looks different.
+    fn create_mock_image() -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(64, 48, |x, y| {
+            let value = match (x, y) {
+                (0..20, 0..12) => 240,
+                (40..64, 30..48) => 20 + x,
+                _ => (x * 3 + y * y / 8) % 200,
+            };
+            Luma([value as u8])
+        }))
+    }
+
+    #[test]
+    fn test_transform_composition() {
+        let image =
+            DynamicImage::ImageLuma8(GrayImage::from_fn(3, 2, |x, y| Luma([(y * 3 + x) as u8])));
+
+        for first in Transform::ALL {
+            assert_eq!(first.then(first.inverse()), Transform::Identity);
+            assert_eq!(first.inverse().apply(&first.apply(&image)), image);
+
+            for second in Transform::ALL {
+                assert_eq!(
+                    first.then(second).apply(&image),
+                    second.apply(&first.apply(&image)),
+                    "{:?} then {:?}",
+                    first,
+                    second
+                );
+            }
+        }
+
+        assert_eq!(
+            Transform::FlipHorizontal.apply(&image),
+            DynamicImage::ImageLuma8(image.to_luma8()).fliph()
+        );
+        assert_eq!(
+            Transform::Transpose
+                .apply(&image)
+                .to_luma8()
+                .get_pixel(1, 2)[0],
+            5
+        );
+    }
+
+    #[test]
+    fn test_dihedral_hash_finds_transform() -> Result<()> {
+        let image = create_mock_image();
+        let original = dihedral_hash(&image, FilterType::Triangle, "phash", 8)?;
+        assert_eq!(
+            original.hash(Transform::Identity),
+            &hash_image(&image, FilterType::Triangle, "phash", 8)?
+        );
+
+        for transform in Transform::ALL {
+            let copy = dihedral_hash(&transform.apply(&image), FilterType::Triangle, "phash", 8)?;
+
+            // Resized copies round a few pixels differently, shifting pHash by up to 2 bits
+            assert_eq!(copy.canonical().1, original.canonical().1);
+            let (found, distance) = original.find_transform(&copy);
+            assert_eq!(found, transform);
+            assert!(distance <= 2, "{:?} is {} bits away", transform, distance);
+            assert_eq!(original.matches(&copy, 2), Some(transform));
+            assert_eq!(copy.matches(&original, 2), Some(transform.inverse()));
+        }
+
+        let (transform, hash) = original.canonical();
+        assert_eq!(original.hash(transform), hash);
+        assert_eq!(
+            canonical_hash(&image, FilterType::Triangle, "phash", 8)?,
+            hash.clone()
+        );
+
+        // An unrelated image matches no transform
+        let other = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 48, |x, y| {
+            Luma([((x / 8 + y / 8) % 2 * 200) as u8])
+        }));
+        let other = dihedral_hash(&other, FilterType::Triangle, "phash", 8)?;
+        assert_eq!(original.matches(&other, 4), None);
+
+        // Algorithms hashing non-square images transform before resizing, so copies match exactly
+        let original = dihedral_hash(&image, FilterType::Triangle, "dhash", 8)?;
+        for transform in Transform::ALL {
+            let copy = dihedral_hash(&transform.apply(&image), FilterType::Triangle, "dhash", 8)?;
+            assert_eq!(copy.matches(&original, 0), Some(transform.inverse()));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dihedral_hash_with_pipeline() -> Result<()> {
+        let image = create_mock_image();
+        let pipeline = NormalizePipeline::new(FilterType::Triangle).oriented();
+
+        // The image is turned upright before it is transformed
+        for algo in ["phash", "dhash"] {
+            let oriented = dihedral_hash_with(&image, Orientation::Rotate90, algo, 8, &pipeline)?;
+            let rotated = dihedral_hash(&image.rotate90(), FilterType::Triangle, algo, 8)?;
+            assert_eq!(oriented, rotated);
+            assert_eq!(
+                canonical_hash_with(&image, Orientation::Rotate90, algo, 8, &pipeline)?,
+                canonical_hash(&image.rotate90(), FilterType::Triangle, algo, 8)?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_symmetric_image_keeps_identity() -> Result<()> {
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(16, 16, Luma([90])));
+        let hash = dihedral_hash(&flat, FilterType::Triangle, "ahash", 8)?;
+
+        assert_eq!(hash.canonical().0, Transform::Identity);
+        assert_eq!(hash.find_transform(&hash), (Transform::Identity, 0));
+
+        Ok(())
+    }
+}
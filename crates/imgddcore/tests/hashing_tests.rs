@@ -2,7 +2,7 @@
 mod tests {
     use anyhow::Result;
     use image::{DynamicImage, Rgba};
-    use imgddcore::hashing::ImageHash;
+    use imgddcore::hashing::{BlockMeanMode, ColorMomentHash, ImageHash, RadialVarianceHash};
 
     /// Creates a mock image with alternating pixel values for testing.
     fn create_mock_image(size: (u32, u32)) -> DynamicImage {
@@ -63,6 +63,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_dhash_vertical() -> Result<()> {
+        // Alternating columns never change from top to bottom
+        let hash = ImageHash::dhash_vertical(&create_mock_image((8, 9)))?;
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash.get_hash(), 0);
+
+        // Alternating rows get brighter on every even row
+        let rotated = create_mock_image((9, 8)).rotate90();
+        let hash = ImageHash::dhash_vertical(&rotated)?;
+        assert_eq!(hash.get_hash(), 0x00FF_00FF_00FF_00FF);
+        assert_eq!(
+            ImageHash::dhash(&rotated.rotate270())?,
+            ImageHash::from(0x5555_5555_5555_5555)
+        );
+
+        assert!(ImageHash::dhash_vertical(&create_mock_image((8, 1))).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_phash() -> Result<()> {
         let test_image = create_mock_image((32, 32));
@@ -93,4 +114,424 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hash_sizes() -> Result<()> {
+        // The hash size follows the dimensions of the normalized image
+        assert_eq!(ImageHash::ahash(&create_mock_image((16, 16)))?.len(), 256);
+        assert_eq!(ImageHash::mhash(&create_mock_image((12, 12)))?.len(), 144);
+        assert_eq!(ImageHash::dhash(&create_mock_image((17, 16)))?.len(), 256);
+        assert_eq!(ImageHash::phash(&create_mock_image((64, 64)))?.len(), 256);
+        assert_eq!(ImageHash::whash(&create_mock_image((16, 16)))?.len(), 256);
+        assert!(ImageHash::whash(&create_mock_image((12, 12))).is_err());
+
+        // Larger hashes keep the bit pattern of the 8x8 hash
+        let hash = ImageHash::ahash(&create_mock_image((16, 16)))?;
+        assert_eq!(hash.to_hex(), "a".repeat(64));
+        assert_eq!(hash.get_hash(), 0xaaaa_aaaa_aaaa_aaaa);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_encoding() -> Result<()> {
+        let hash = ImageHash::from(0xff);
+        assert_eq!(hash.to_hex(), "00000000000000ff");
+        assert_eq!(hash.to_string(), "00000000000000ff");
+        assert_eq!(ImageHash::from_hex("00000000000000ff", 64)?, hash);
+
+        // Sizes that are not a multiple of four are padded with leading zero bits
+        let hash = ImageHash::from_bits([true, false, true, true, false, true]);
+        assert_eq!(hash.to_hex(), "2d");
+        assert_eq!(ImageHash::from_hex("2d", 6)?, hash);
+
+        let hash = ImageHash::from_hex(&"0123456789abcdef".repeat(3), 192)?;
+        assert_eq!(hash.len(), 192);
+        assert_eq!(hash.to_hex(), "0123456789abcdef".repeat(3));
+        assert!(ImageHash::from_hex("xyz", 12).is_err());
+
+        // The length must match the digits, and the padding must be zero
+        assert!(ImageHash::from_hex("2d", 9).is_err());
+        assert!(ImageHash::from_hex("2d", 4).is_err());
+        assert!(ImageHash::from_hex("ed", 6).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_round_trip() -> Result<()> {
+        // colorhash, 9x9 grids and blockmeanoverlap produce sizes that are not a multiple of four
+        for len in [1, 42, 81, 961] {
+            let hash = ImageHash::from_bits((0..len).map(|i| (i * 7 + i / 3) % 5 < 2));
+            let parsed = ImageHash::from_hex(&hash.to_hex(), len)?;
+            assert_eq!(parsed, hash, "{} bits", len);
+            assert_eq!(parsed.hamming_distance(&hash), 0);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hamming_distance() -> Result<()> {
+        let a = ImageHash::from_hex(&"0".repeat(36), 144)?;
+        let b = ImageHash::from_hex(&format!("{}{}", "f".repeat(18), "0".repeat(18)), 144)?;
+        assert_eq!(a.hamming_distance(&b), 72);
+        assert_eq!(b.hamming_distance(&b), 0);
+
+        // Extra bits of a longer hash count as differences
+        let short = ImageHash::from_bits([true, false]);
+        let long = ImageHash::from_bits([true, false, false, false]);
+        assert_eq!(short.hamming_distance(&long), 2);
+        assert_eq!(long.hamming_distance(&short), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_colorhash() -> Result<()> {
+        let solid = |color: [u8; 3]| {
+            DynamicImage::ImageRgb8(image::ImageBuffer::from_pixel(4, 4, image::Rgb(color)))
+        };
+
+        // Bright red fills the first bright hue bin, black the black bin
+        let red = ImageHash::colorhash(&solid([255, 0, 0]), 3)?;
+        assert_eq!(red.len(), 42);
+        assert_eq!(red.to_hex(), "00000038000");
+        assert_eq!(
+            ImageHash::colorhash(&solid([0, 0, 0]), 3)?.to_hex(),
+            "38000000000"
+        );
+
+        // A recolored image no longer hashes the same
+        let cyan = ImageHash::colorhash(&solid([0, 255, 255]), 3)?;
+        assert_eq!(cyan.to_hex(), "00000000e00");
+        assert_ne!(red, cyan);
+
+        // Half gray, half red
+        let mixed = DynamicImage::ImageRgb8(image::ImageBuffer::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([128, 128, 128])
+            }
+        }));
+        assert_eq!(ImageHash::colorhash(&mixed, 3)?.to_hex(), "06000038000");
+        assert_eq!(ImageHash::colorhash(&mixed, 4)?.len(), 56);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pdq() -> Result<()> {
+        // Reference vectors of Facebook's PDQ implementation
+        for (file, expected) in [
+            (
+                "bridge-1-original.jpg",
+                "f8f8f0cee0f4a84f06370a22038f63f0b36e2ed596621e1d33e6b39c4e9c9b22",
+            ),
+            (
+                "bridge-2-rotate-90.jpg",
+                "30a10efd71cc3d429013d48d0ffffc52e34e0e17ada952a9d29685211ea9e5af",
+            ),
+            (
+                "bridge-5-flipx.jpg",
+                "f8f80f31e0f417b20e37f5cd028f980fb36ed02a9662c1e233e64c634e9c64dd",
+            ),
+        ] {
+            let image = image::open(format!("../../imgs/pdq/{}", file))?;
+            let (hash, quality) = ImageHash::pdq(&image)?;
+            assert_eq!(hash.len(), 256);
+            assert_eq!(hash.to_hex(), expected, "PDQ of {} does not match", file);
+            assert_eq!(quality, 100, "Quality of {} does not match", file);
+        }
+
+        // A flat image carries no information
+        let flat = DynamicImage::ImageRgb8(image::ImageBuffer::from_pixel(
+            100,
+            80,
+            image::Rgb([90, 120, 30]),
+        ));
+        let (_, quality) = ImageHash::pdq(&flat)?;
+        assert_eq!(quality, 0);
+
+        let empty = DynamicImage::ImageRgb8(image::ImageBuffer::new(0, 0));
+        assert!(ImageHash::pdq(&empty).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_blockhash() -> Result<()> {
+        // Reference vectors of blockhash.io, including a non-square image with transparency
+        for (file, bits, expected) in [
+            ("16x16_rgb.png", 8, "f0f0e7c0d8f0f864"),
+            ("241x159_ya.png", 8, "3c3c2e4ecf84819f"),
+            (
+                "241x159_ya.png",
+                16,
+                "0ff80ff807f007f007f80df821fc11fcf0ffb0ff5031c021e001c00180ffffff",
+            ),
+            (
+                "26x17_rgb.png",
+                16,
+                "00001db07ff43ffe0ffc07fc07f003f00bd01ff83ff803e03ffc1ff807f00070",
+            ),
+        ] {
+            let image = image::open(format!("../../imgs/blockhash/{}", file))?;
+            let hash = ImageHash::blockhash(&image, bits)?;
+            assert_eq!(hash.len(), (bits * bits) as usize);
+            assert_eq!(
+                hash.to_hex(),
+                expected,
+                "Blockhash of {} with {} bits does not match",
+                file,
+                bits
+            );
+        }
+
+        // Blocks equal to the median of a bright image are set
+        let white = DynamicImage::ImageRgb8(image::ImageBuffer::from_pixel(
+            32,
+            32,
+            image::Rgb([255, 255, 255]),
+        ));
+        assert_eq!(ImageHash::blockhash(&white, 8)?.get_hash(), u64::MAX);
+
+        assert!(ImageHash::blockhash(&white, 7).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_marr_hildreth() -> Result<()> {
+        // Regression vector generated by imgs/img_hash/reference.py, an independent Python
+        // transcription of the algorithm. It is not checked against OpenCV.
+        let fixture = image::open("../../imgs/img_hash/gray_512.png")?;
+        assert_eq!(
+            ImageHash::marr_hildreth(&fixture)?.to_hex(),
+            "648db903ff206f485b25ec241d0e4b624cd99b7406de02de90b7a4d803f480725c9db12724eda923f9207e4cdb27fc01ff807f626cdcdb1276d656ded36124e48df903ff006dc91b"
+        );
+
+        let image = image::open("../../imgs/test/apple_pie/21063.jpg")?;
+        let normalized = image
+            .resize_exact(512, 512, image::imageops::FilterType::Triangle)
+            .grayscale();
+        let hash = ImageHash::marr_hildreth(&normalized)?;
+        assert_eq!(hash.len(), 576);
+        assert_eq!(
+            hash.hamming_distance(&ImageHash::marr_hildreth(&normalized)?),
+            0
+        );
+
+        // Histogram equalization cancels a uniform brightness change
+        let brighter = normalized.brighten(20);
+        let distance = hash.hamming_distance(&ImageHash::marr_hildreth(&brighter)?);
+        assert!(distance < 8, "Brightened copy is {} bits away", distance);
+
+        let flipped = normalized.fliph();
+        let distance = hash.hamming_distance(&ImageHash::marr_hildreth(&flipped)?);
+        assert!(
+            distance > 200,
+            "Flipped image is only {} bits away",
+            distance
+        );
+
+        assert!(ImageHash::marr_hildreth(&create_mock_image((64, 64))).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_radial_variance() -> Result<()> {
+        // Regression vectors generated by imgs/img_hash/reference.py, an independent Python
+        // transcription of the algorithm. They are not checked against OpenCV.
+        for (file, expected) in [
+            (
+                "gray_512.png",
+                "54fe6000194d622f243a5c584b4e5d5d57524d544d4f454e4f545352565656535456575554555653",
+            ),
+            (
+                "gray_300x200.png",
+                "61ff6500240c5c33537975874a3f316279836a6d66594f4e687a6e6356545d5b636c67695b545961",
+            ),
+        ] {
+            let image = image::open(format!("../../imgs/img_hash/{}", file))?;
+            let hash = RadialVarianceHash::compute(&image)?;
+            assert_eq!(hash.to_hex(), expected, "rvHash of {} does not match", file);
+        }
+
+        let image = image::open("../../imgs/test/apple_pie/21063.jpg")?;
+        let hash = RadialVarianceHash::compute(&image)?;
+        assert_eq!(hash.coefficients().len(), 40);
+        assert_eq!(hash.to_hex().len(), 80);
+        assert!((hash.cross_correlation(&hash) - 1.0).abs() < 1e-9);
+
+        let brighter = RadialVarianceHash::compute(&image.brighten(20))?;
+        let similar = hash.cross_correlation(&brighter);
+        assert!(similar > 0.9, "Brightened copy correlates at {}", similar);
+
+        let other =
+            RadialVarianceHash::compute(&image::open("../../imgs/test/baklava/21435.jpg")?)?;
+        let different = hash.cross_correlation(&other);
+        assert!(
+            different < similar,
+            "Different image correlates at {}",
+            different
+        );
+
+        let rebuilt = RadialVarianceHash::from_coefficients(hash.coefficients().to_vec());
+        assert_eq!(rebuilt, hash);
+        assert_eq!(
+            hash.cross_correlation(&RadialVarianceHash::from_coefficients(vec![])),
+            0.0
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_color_moment_hash() -> Result<()> {
+        // Regression moments generated by imgs/img_hash/reference.py, an independent Python
+        // transcription of the algorithm. They are not checked against OpenCV. The script computes
+        // central moments exactly, so summing in floating point drifts slightly.
+        let expected = [
+            0.0026057719450284494,
+            1.6307310639537115e-09,
+            5.447608292535146e-13,
+            3.178777243444835e-13,
+            -4.9458038737004e-26,
+            -9.08414302016236e-18,
+            1.2268582472321085e-25,
+            0.004143605908328012,
+            3.540915035959825e-08,
+            9.848541478630938e-11,
+            6.088496751428408e-10,
+            -9.568617502510959e-20,
+            3.843078701075393e-14,
+            1.1433392456318597e-19,
+            0.0013281425111236317,
+            1.2995459437129644e-10,
+            1.3692940426685597e-12,
+            1.5258069610215714e-11,
+            -6.822669709533611e-23,
+            -6.480159532319767e-17,
+            -1.446165267342856e-23,
+            0.0014314644313922183,
+            2.888010231075143e-10,
+            1.969641554118632e-12,
+            2.1895313816453817e-11,
+            -1.409164885046414e-22,
+            -6.919097228884927e-17,
+            -2.8588719279639724e-23,
+            0.0013020359402806073,
+            6.348223267294183e-13,
+            4.868241851269038e-16,
+            5.326167865529637e-17,
+            3.996143498678755e-33,
+            -2.3449825484847865e-23,
+            7.588583437716278e-33,
+            0.0013021804322319998,
+            6.59099181755894e-13,
+            8.343246447079557e-16,
+            2.539970870972564e-16,
+            -3.836840497532037e-32,
+            5.074502789324004e-23,
+            1.1045146659813635e-31,
+        ];
+        let fixture = ColorMomentHash::compute(&image::open("../../imgs/img_hash/rgb_512.png")?)?;
+        assert_eq!(fixture.moments().len(), expected.len());
+        for (i, (&moment, expected)) in fixture.moments().iter().zip(expected).enumerate() {
+            assert!(
+                (moment - expected).abs() <= 1e-8 * expected.abs(),
+                "Moment {} is {}, expected {}",
+                i,
+                moment,
+                expected
+            );
+        }
+
+        let image = image::open("../../imgs/test/apple_pie/21063.jpg")?;
+        let hash = ColorMomentHash::compute(&image)?;
+        assert_eq!(hash.moments().len(), 42);
+        assert_eq!(hash.to_string().split(',').count(), 42);
+        assert_eq!(hash.distance(&hash), 0.0);
+
+        // Hu moments are invariant to rotation
+        let rotated = ColorMomentHash::compute(&image.rotate90())?;
+        let other = ColorMomentHash::compute(&image::open("../../imgs/test/baklava/21435.jpg")?)?;
+        assert!(
+            hash.distance(&rotated) * 10.0 < hash.distance(&other),
+            "Rotated copy is {} away, other image {}",
+            hash.distance(&rotated),
+            hash.distance(&other)
+        );
+
+        // Only the first Hu moment of a flat channel is non-zero
+        let gray =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 64, image::Luma([128])));
+        let flat = ColorMomentHash::compute(&gray)?;
+        assert!(flat.moments()[..7].iter().all(|&m| m == 0.0));
+        assert!(flat.moments()[14] > 0.0);
+        assert!(flat.moments()[15..21].iter().all(|&m| m.abs() < 1e-12));
+
+        let rebuilt = ColorMomentHash::from_moments(hash.moments().to_vec());
+        assert_eq!(rebuilt, hash);
+        assert_eq!(
+            hash.distance(&ColorMomentHash::from_moments(vec![])),
+            f64::INFINITY
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_mean() -> Result<()> {
+        // Regression vectors generated by imgs/img_hash/reference.py, an independent Python
+        // transcription of the algorithm. They are not checked against OpenCV.
+        let fixture = image::open("../../imgs/img_hash/gray_256.png")?;
+        for (mode, expected) in [
+            (
+                BlockMeanMode::Disjoint,
+                "c07fc07f3f803f803f807f807f80ff7ffe7fc01fc0033f003f003f803f803f80",
+            ),
+            (
+                BlockMeanMode::Overlapping,
+                "1c0007fff8000ffff0001fffe0001ffe1fff00003fff00007ffe0001fffc0003fff80007fff0001fffe0003fffc0007fff0003fffc7ffffff9ffffffe3ffffff87fff9fc0ffff00003ffe000001fc000003f800000383ff80000fff00001ffe00003ffe00007ffe0000fffc0001fff80003fff00007ffc000",
+            ),
+        ] {
+            let hash = ImageHash::block_mean(&fixture, mode)?;
+            assert_eq!(hash.to_hex(), expected, "bmHash in {:?} mode does not match", mode);
+        }
+
+        // Dark, middle and bright bands of columns
+        let bands = |middle: u32, bright: u32| {
+            DynamicImage::ImageLuma8(image::GrayImage::from_fn(256, 256, |x, _| {
+                image::Luma([match x {
+                    x if x >= bright => 200,
+                    x if x >= middle => 120,
+                    _ => 50,
+                }])
+            }))
+        };
+
+        // The median falls in the middle band, whose blocks are set along with the bright ones
+        let hash = ImageHash::block_mean(&bands(64, 160), BlockMeanMode::Disjoint)?;
+        assert_eq!(hash.len(), 256);
+        assert_eq!(hash, ImageHash::from_bits((0..256).map(|i| i % 16 >= 4)));
+
+        // Overlapping blocks straddling the dark edge average below the median, those at the bright edge above
+        let hash = ImageHash::block_mean(&bands(64, 160), BlockMeanMode::Overlapping)?;
+        assert_eq!(hash.len(), 961);
+        assert_eq!(hash, ImageHash::from_bits((0..961).map(|i| i % 31 >= 8)));
+
+        // With as many bright blocks as dark ones, the upper median is bright and every bright block reaches it
+        let hash = ImageHash::block_mean(&bands(128, 128), BlockMeanMode::Disjoint)?;
+        assert_eq!(hash, ImageHash::from_bits((0..256).map(|i| i % 16 >= 8)));
+
+        assert!(
+            ImageHash::block_mean(&create_mock_image((64, 64)), BlockMeanMode::Disjoint).is_err()
+        );
+
+        Ok(())
+    }
 }
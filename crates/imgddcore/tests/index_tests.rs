@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use imgddcore::dedupe::hamming_distance;
+    use imgddcore::hashing::ImageHash;
+    use imgddcore::index::BKTree;
+    use std::path::PathBuf;
+
+    fn mock_hash_paths() -> Vec<(ImageHash, PathBuf)> {
+        vec![
+            (ImageHash::from(0b0000_0000), PathBuf::from("a")),
+            (ImageHash::from(0b0000_0001), PathBuf::from("b")),
+            (ImageHash::from(0b0000_0011), PathBuf::from("c")),
+            (ImageHash::from(0b0000_0111), PathBuf::from("d")),
+            (ImageHash::from(0b1111_0000), PathBuf::from("e")),
+            (ImageHash::from(0b0000_0000), PathBuf::from("f")),
+        ]
+    }
+
+    #[test]
+    fn test_bktree_insert() {
+        let mut tree = BKTree::new();
+        assert!(tree.is_empty());
+
+        tree.insert(ImageHash::from(1), PathBuf::from("a"));
+        tree.insert(ImageHash::from(1), PathBuf::from("b"));
+        assert_eq!(tree.len(), 2);
+
+        let tree = BKTree::from_hashes(&mock_hash_paths());
+        assert_eq!(tree.len(), 6);
+    }
+
+    #[test]
+    fn test_bktree_find() {
+        let tree = BKTree::from_hashes(&mock_hash_paths());
+
+        let exact: Vec<&PathBuf> = tree
+            .find(&ImageHash::from(0), 0)
+            .into_iter()
+            .map(|m| m.2)
+            .collect();
+        assert_eq!(exact, vec![&PathBuf::from("a"), &PathBuf::from("f")]);
+
+        let near: Vec<(u32, &PathBuf)> = tree
+            .find(&ImageHash::from(0), 2)
+            .into_iter()
+            .map(|m| (m.0, m.2))
+            .collect();
+        assert_eq!(
+            near,
+            vec![
+                (0, &PathBuf::from("a")),
+                (0, &PathBuf::from("f")),
+                (1, &PathBuf::from("b")),
+                (2, &PathBuf::from("c")),
+            ]
+        );
+
+        assert!(BKTree::new().find(&ImageHash::from(0), 64).is_empty());
+    }
+
+    #[test]
+    fn test_bktree_find_matches_linear_scan() {
+        // Pseudo-random hashes to exercise deeper trees
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let hash_paths: Vec<(ImageHash, PathBuf)> = (0..500)
+            .map(|i| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (ImageHash::from(state), PathBuf::from(format!("{i}")))
+            })
+            .collect();
+        let tree = BKTree::from_hashes(&hash_paths);
+
+        let query = ImageHash::from(hash_paths[42].0.get_hash() ^ 0b1011);
+        for max_distance in [0, 3, 20, 30] {
+            let expected = hash_paths
+                .iter()
+                .filter(|(hash, _)| hamming_distance(hash, &query) <= max_distance)
+                .count();
+            assert_eq!(tree.find(&query, max_distance).len(), expected);
+        }
+
+        let mut distances: Vec<u32> = hash_paths
+            .iter()
+            .map(|(hash, _)| hamming_distance(hash, &query))
+            .collect();
+        distances.sort_unstable();
+        let nearest: Vec<u32> = tree.nearest(&query, 10).into_iter().map(|m| m.0).collect();
+        assert_eq!(nearest, distances[..10]);
+    }
+
+    #[test]
+    fn test_bktree_nearest() {
+        let tree = BKTree::from_hashes(&mock_hash_paths());
+
+        let nearest = tree.nearest(&ImageHash::from(0b0000_0111), 2);
+        assert_eq!(nearest.len(), 2);
+        assert_eq!(
+            nearest[0],
+            (0, &ImageHash::from(0b0000_0111), &PathBuf::from("d"))
+        );
+        assert_eq!(
+            nearest[1],
+            (1, &ImageHash::from(0b0000_0011), &PathBuf::from("c"))
+        );
+
+        assert_eq!(tree.nearest(&ImageHash::from(0), 100).len(), 6);
+        assert!(tree.nearest(&ImageHash::from(0), 0).is_empty());
+    }
+
+    #[test]
+    fn test_bktree_nearest_ties() {
+        // Five hashes one bit away from the query, inserted in both orders
+        let mut hash_paths: Vec<(ImageHash, PathBuf)> = ["e", "d", "c", "b", "a"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (ImageHash::from(1 << i), PathBuf::from(name)))
+            .collect();
+
+        for _ in 0..2 {
+            let tree = BKTree::from_hashes(&hash_paths);
+            let nearest: Vec<(u32, &PathBuf)> = tree
+                .nearest(&ImageHash::from(0), 3)
+                .into_iter()
+                .map(|(distance, _, path)| (distance, path))
+                .collect();
+            assert_eq!(
+                nearest,
+                vec![
+                    (1, &PathBuf::from("a")),
+                    (1, &PathBuf::from("b")),
+                    (1, &PathBuf::from("c")),
+                ]
+            );
+            hash_paths.reverse();
+        }
+    }
+}
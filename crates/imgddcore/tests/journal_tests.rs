@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod tests {
+    use imgddcore::dedupe::*;
+    use imgddcore::hashing::ImageHash;
+    use imgddcore::journal::*;
+
+    #[test]
+    fn test_journal_record_and_read() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal_path = temp_dir.path().join("journal.jsonl");
+
+        let mut journal = Journal::open(&journal_path, "dhash").unwrap();
+        journal
+            .record(
+                JournalAction::Remove,
+                &temp_dir.path().join("b.jpg"),
+                None,
+                &temp_dir.path().join("a.jpg"),
+                &ImageHash::from(0xff),
+            )
+            .unwrap();
+        drop(journal);
+
+        // Reopening appends rather than truncating
+        let mut journal = Journal::open(&journal_path, "phash").unwrap();
+        journal
+            .record(
+                JournalAction::Move,
+                &temp_dir.path().join("c.jpg"),
+                Some(&temp_dir.path().join("quarantine").join("c.jpg")),
+                &temp_dir.path().join("a.jpg"),
+                &ImageHash::from(0x1),
+            )
+            .unwrap();
+
+        let entries = read_journal(&journal_path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, JournalAction::Remove);
+        assert_eq!(entries[0].original, temp_dir.path().join("b.jpg"));
+        assert_eq!(entries[0].destination, None);
+        assert_eq!(entries[0].survivor, temp_dir.path().join("a.jpg"));
+        assert_eq!(entries[0].hash, "00000000000000ff");
+        assert_eq!(entries[0].algo, "dhash");
+        assert!(entries[0].timestamp > 0);
+        assert_eq!(entries[1].action, JournalAction::Move);
+        assert_eq!(entries[1].algo, "phash");
+    }
+
+    #[test]
+    fn test_read_journal_invalid_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal_path = temp_dir.path().join("journal.jsonl");
+        std::fs::write(&journal_path, b"not json\n").unwrap();
+
+        let result = read_journal(&journal_path);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("line 1"));
+    }
+
+    #[test]
+    fn test_find_duplicates_journal_and_undo() {
+        let root = tempfile::tempdir().unwrap();
+        let quarantine = tempfile::tempdir().unwrap();
+        let journal_path = quarantine.path().join("journal.jsonl");
+        let kept = root.path().join("a.jpg");
+        let moved = root.path().join("nested").join("b.jpg");
+        let removed = root.path().join("c.jpg");
+        std::fs::create_dir(root.path().join("nested")).unwrap();
+        std::fs::write(&kept, b"a").unwrap();
+        std::fs::write(&moved, b"b").unwrap();
+        std::fs::write(&removed, b"c").unwrap();
+
+        // Quarantine one duplicate
+        let mut journal = Journal::open(&journal_path, "dhash").unwrap();
+        let action = DuplicateAction::Quarantine {
+            root: root.path().to_path_buf(),
+            dir: quarantine.path().join("files"),
+        };
+        let hash_paths = vec![
+            (ImageHash::from(7), kept.clone()),
+            (ImageHash::from(7), moved.clone()),
+        ];
+        find_duplicates(
+            &hash_paths,
+            0,
+            &action,
+            &KeepPolicy::First,
+            Some(&mut journal),
+        )
+        .unwrap();
+
+        // Remove another duplicate
+        let hash_paths = vec![
+            (ImageHash::from(9), kept.clone()),
+            (ImageHash::from(9), removed.clone()),
+        ];
+        find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Remove,
+            &KeepPolicy::First,
+            Some(&mut journal),
+        )
+        .unwrap();
+        assert!(!moved.exists());
+        assert!(!removed.exists());
+
+        let entries = read_journal(&journal_path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].original, moved);
+        assert_eq!(
+            entries[0].destination,
+            Some(quarantine.path().join("files").join("nested").join("b.jpg"))
+        );
+        assert_eq!(entries[0].survivor, kept);
+        assert_eq!(entries[0].hash, "0000000000000007");
+        assert_eq!(entries[1].action, JournalAction::Remove);
+
+        // Only the quarantined file can be restored
+        let restored = undo(&journal_path).unwrap();
+        assert_eq!(restored, vec![moved.clone()]);
+        assert_eq!(std::fs::read(&moved).unwrap(), b"b");
+        assert!(!removed.exists());
+
+        // Undoing twice is a no-op
+        assert!(undo(&journal_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_journal_revert() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let journal_path = temp_dir.path().join("journal.jsonl");
+
+        let mut journal = Journal::open(&journal_path, "dhash").unwrap();
+        for name in ["b.jpg", "c.jpg"] {
+            journal
+                .record(
+                    JournalAction::Remove,
+                    &temp_dir.path().join(name),
+                    None,
+                    &temp_dir.path().join("a.jpg"),
+                    &ImageHash::from(0xff),
+                )
+                .unwrap();
+        }
+
+        // Only the last entry is reverted, and only once
+        journal.revert().unwrap();
+        journal.revert().unwrap();
+        let entries = read_journal(&journal_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original, temp_dir.path().join("b.jpg"));
+    }
+
+    #[test]
+    fn test_find_duplicates_journal_failed_action() {
+        let root = tempfile::tempdir().unwrap();
+        let journal_path = root.path().join("journal.jsonl");
+        let kept = root.path().join("a.jpg");
+        let missing = root.path().join("b.jpg");
+        let removed = root.path().join("c.jpg");
+        std::fs::write(&kept, b"a").unwrap();
+        std::fs::write(&removed, b"c").unwrap();
+
+        let mut journal = Journal::open(&journal_path, "dhash").unwrap();
+        let hash_paths = vec![
+            (ImageHash::from(7), kept.clone()),
+            (ImageHash::from(7), missing.clone()),
+            (ImageHash::from(7), removed.clone()),
+        ];
+        find_duplicates(
+            &hash_paths,
+            0,
+            &DuplicateAction::Remove,
+            &KeepPolicy::First,
+            Some(&mut journal),
+        )
+        .unwrap();
+
+        // The entry of the file that could not be removed is reverted
+        let entries = read_journal(&journal_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].original, removed);
+        assert!(!removed.exists());
+    }
+}
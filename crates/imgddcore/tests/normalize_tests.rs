@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
     use image::imageops::FilterType;
-    use image::{DynamicImage, Rgba};
+    use image::metadata::Orientation;
+    use image::{DynamicImage, GenericImageView, Rgba};
     use imgddcore::normalize::*;
 
     fn create_mock_image() -> DynamicImage {
@@ -23,4 +24,281 @@ mod tests {
         assert_eq!(normalized88.width(), 8);
         assert_eq!(normalized88.height(), 8);
     }
+
+    #[test]
+    fn test_resize_modes() {
+        // A wide image made of a dark, a bright and a gray square
+        let wide = DynamicImage::ImageLuma8(image::GrayImage::from_fn(60, 20, |x, _| {
+            image::Luma([[0, 255, 100][x as usize / 20]])
+        }));
+
+        let stretched = resize(&wide, FilterType::Nearest, 6, 6, ResizeMode::Stretch).to_luma8();
+        let row: Vec<u8> = (0..6).map(|x| stretched.get_pixel(x, 3)[0]).collect();
+        assert_eq!(row, vec![0, 0, 255, 255, 100, 100]);
+
+        // Only the bright middle square survives the crop
+        let cropped = resize(&wide, FilterType::Nearest, 6, 6, ResizeMode::CenterCrop);
+        assert_eq!(cropped.dimensions(), (6, 6));
+        assert!(cropped.to_luma8().pixels().all(|p| p[0] == 255));
+
+        // The whole image is scaled to 6x2 and centered between black bars
+        let padded = resize(&wide, FilterType::Nearest, 6, 6, ResizeMode::Pad).to_luma8();
+        let column: Vec<u8> = (0..6).map(|y| padded.get_pixel(2, y)[0]).collect();
+        assert_eq!(column, vec![0, 0, 255, 255, 0, 0]);
+        let row: Vec<u8> = (0..6).map(|x| padded.get_pixel(x, 2)[0]).collect();
+        assert_eq!(row, vec![0, 0, 255, 255, 100, 100]);
+
+        // Tall images are cropped and padded along the other axis
+        let tall = DynamicImage::ImageRgba8(image::ImageBuffer::from_fn(10, 40, |_, y| {
+            Rgba([(y * 6) as u8, 0, 0, 255])
+        }));
+        let cropped = resize(&tall, FilterType::Nearest, 8, 8, ResizeMode::CenterCrop);
+        assert_eq!(cropped.get_pixel(0, 0), tall.get_pixel(0, 15));
+        let padded = resize(&tall, FilterType::Nearest, 8, 8, ResizeMode::Pad);
+        assert_eq!(padded.color(), image::ColorType::Rgba8);
+        assert_eq!(padded.get_pixel(0, 4), Rgba([0, 0, 0, 0]));
+        assert_eq!(padded.get_pixel(3, 4)[3], 255);
+        assert_eq!(padded.get_pixel(5, 4)[3], 0);
+
+        // Matching aspect ratios resize the same in every mode
+        for mode in [ResizeMode::CenterCrop, ResizeMode::Pad] {
+            assert_eq!(
+                resize(&wide, FilterType::Triangle, 9, 3, mode),
+                resize(&wide, FilterType::Triangle, 9, 3, ResizeMode::Stretch)
+            );
+        }
+
+        let pipeline =
+            NormalizePipeline::new(FilterType::Triangle).with_resize_mode(ResizeMode::Pad);
+        assert_eq!(
+            pipeline.steps[0],
+            NormalizeStep::Resize(FilterType::Triangle, ResizeMode::Pad)
+        );
+        let normalized = pipeline
+            .apply(&wide, Orientation::NoTransforms, Some((6, 6)))
+            .unwrap();
+        assert_eq!(
+            normalized,
+            resize(&wide, FilterType::Triangle, 6, 6, ResizeMode::Pad).grayscale()
+        );
+    }
+
+    #[test]
+    fn test_equalize() {
+        // Four levels spread over the full range
+        let image = DynamicImage::ImageLuma8(image::GrayImage::from_fn(4, 4, |x, _| {
+            image::Luma([100 + x as u8 * 10])
+        }));
+        let equalized = equalize(&image).unwrap().to_luma8();
+        let row: Vec<u8> = (0..4).map(|x| equalized.get_pixel(x, 0)[0]).collect();
+        assert_eq!(row, vec![0, 85, 170, 255]);
+
+        // A flat image is left unchanged
+        let flat = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(4, 4, image::Luma([42])));
+        assert!(equalize(&flat)
+            .unwrap()
+            .to_luma8()
+            .pixels()
+            .all(|p| p[0] == 42));
+    }
+
+    /// Creates a gradient surrounded by a black border of 4 pixels on the sides and 2 on top and bottom.
+    fn create_letterboxed_image() -> DynamicImage {
+        DynamicImage::ImageRgba8(image::ImageBuffer::from_fn(20, 12, |x, y| {
+            if (4..16).contains(&x) && (2..10).contains(&y) {
+                Rgba([(x * 10) as u8, (y * 20) as u8, 100, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_default_pipeline_matches_proc() {
+        let image = create_letterboxed_image();
+        let pipeline = NormalizePipeline::new(FilterType::Triangle);
+
+        let normalized = pipeline
+            .apply(&image, Orientation::NoTransforms, Some((9, 8)))
+            .unwrap();
+        assert_eq!(
+            normalized,
+            proc(&image, FilterType::Triangle, 9, 8).unwrap()
+        );
+
+        // The full image skips resizing and color conversion
+        let full = pipeline
+            .apply(&image, Orientation::NoTransforms, None)
+            .unwrap();
+        assert_eq!(full, image);
+    }
+
+    #[test]
+    fn test_pipeline_steps() {
+        let image = create_letterboxed_image();
+        let pipeline = NormalizePipeline {
+            steps: vec![
+                NormalizeStep::Orient,
+                NormalizeStep::TrimBorder { tolerance: 0 },
+                NormalizeStep::Convert(ColorSpace::Rgb),
+            ],
+        };
+
+        // Rotated upright, then trimmed to the 8x12 gradient, then resized at the end
+        let full = pipeline.apply(&image, Orientation::Rotate90, None).unwrap();
+        assert_eq!(full.dimensions(), (8, 12));
+        let resized = pipeline
+            .apply(&image, Orientation::Rotate90, Some((4, 4)))
+            .unwrap();
+        assert_eq!(resized.dimensions(), (4, 4));
+        assert_eq!(resized.color(), image::ColorType::Rgb8);
+
+        let pipeline = NormalizePipeline {
+            steps: vec![
+                NormalizeStep::Blur(1.0),
+                NormalizeStep::Resize(FilterType::Nearest, ResizeMode::Stretch),
+                NormalizeStep::Equalize,
+            ],
+        };
+        let equalized = pipeline
+            .apply(&image, Orientation::NoTransforms, Some((10, 6)))
+            .unwrap()
+            .to_luma8();
+        assert_eq!(equalized.dimensions(), (10, 6));
+        assert_eq!(equalized.get_pixel(0, 0)[0], 0);
+        assert_eq!(equalized.pixels().map(|p| p[0]).max(), Some(255));
+    }
+
+    #[test]
+    fn test_flatten_alpha() {
+        let image = DynamicImage::ImageRgba8(image::ImageBuffer::from_fn(2, 1, |x, _| {
+            Rgba([255, 0, 0, if x == 0 { 0 } else { 128 }])
+        }));
+
+        let flattened = flatten_alpha(&image, [255, 255, 255]).to_rgb8();
+        assert_eq!(flattened.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(flattened.get_pixel(1, 0).0, [255, 127, 127]);
+
+        let pipeline = NormalizePipeline {
+            steps: vec![NormalizeStep::FlattenAlpha([0, 0, 0])],
+        };
+        let flattened = pipeline
+            .apply(&image, Orientation::NoTransforms, None)
+            .unwrap()
+            .to_rgb8();
+        assert_eq!(flattened.get_pixel(0, 0).0, [0, 0, 0]);
+        assert_eq!(flattened.get_pixel(1, 0).0, [128, 0, 0]);
+    }
+
+    #[test]
+    fn test_alpha_policy() {
+        assert_eq!(AlphaPolicy::default(), AlphaPolicy::Ignore);
+        assert_eq!(AlphaPolicy::Ignore.background(), None);
+        assert_eq!(AlphaPolicy::White.background(), Some([255, 255, 255]));
+        assert_eq!(AlphaPolicy::Black.background(), Some([0, 0, 0]));
+        assert_eq!(AlphaPolicy::Color([1, 2, 3]).background(), Some([1, 2, 3]));
+
+        // Flattening runs right after orienting the image
+        let oriented = NormalizePipeline::new(FilterType::Triangle).oriented();
+        let white = oriented.clone().with_alpha(AlphaPolicy::White);
+        assert_eq!(
+            white.steps,
+            vec![
+                NormalizeStep::Orient,
+                NormalizeStep::FlattenAlpha([255, 255, 255]),
+                NormalizeStep::Resize(FilterType::Triangle, ResizeMode::Stretch),
+                NormalizeStep::Convert(ColorSpace::Luma),
+            ]
+        );
+
+        // A new policy replaces the previous one
+        let gray = white.with_alpha(AlphaPolicy::Color([128, 128, 128]));
+        assert_eq!(gray.steps.len(), 4);
+        assert_eq!(gray.steps[1], NormalizeStep::FlattenAlpha([128, 128, 128]));
+        assert_eq!(gray.with_alpha(AlphaPolicy::Ignore), oriented);
+
+        // A half transparent red pixel turns pink on white and dark red on black
+        let image =
+            DynamicImage::ImageRgba8(image::ImageBuffer::from_pixel(4, 4, Rgba([255, 0, 0, 128])));
+        let luma = |policy| {
+            NormalizePipeline::new(FilterType::Nearest)
+                .with_alpha(policy)
+                .apply(&image, Orientation::NoTransforms, Some((2, 2)))
+                .unwrap()
+                .to_luma8()
+                .get_pixel(0, 0)[0]
+        };
+        assert!(luma(AlphaPolicy::White) > luma(AlphaPolicy::Ignore));
+        assert!(luma(AlphaPolicy::Black) < luma(AlphaPolicy::Ignore));
+    }
+
+    #[test]
+    fn test_find_border() {
+        let image = create_letterboxed_image();
+        let trim = find_border(&image, 0);
+        assert_eq!(
+            trim,
+            Trim {
+                left: 4,
+                top: 2,
+                right: 4,
+                bottom: 2
+            }
+        );
+        assert!(!trim.is_empty());
+        assert_eq!(trim.apply(&image), trim_border(&image, 0));
+
+        // A uniform image is left alone
+        let flat =
+            DynamicImage::ImageRgba8(image::ImageBuffer::from_pixel(5, 5, Rgba([9, 9, 9, 255])));
+        assert!(find_border(&flat, 0).is_empty());
+        assert_eq!(Trim::default().apply(&flat), flat);
+
+        // Trimming runs after orienting and flattening, before resizing
+        let pipeline = NormalizePipeline::new(FilterType::Triangle)
+            .with_trim(Some(3))
+            .oriented()
+            .with_alpha(AlphaPolicy::White);
+        assert_eq!(
+            pipeline.steps,
+            vec![
+                NormalizeStep::Orient,
+                NormalizeStep::FlattenAlpha([255, 255, 255]),
+                NormalizeStep::TrimBorder { tolerance: 3 },
+                NormalizeStep::Resize(FilterType::Triangle, ResizeMode::Stretch),
+                NormalizeStep::Convert(ColorSpace::Luma),
+            ]
+        );
+        let pipeline = pipeline.with_trim(Some(5));
+        assert_eq!(
+            pipeline.steps[2],
+            NormalizeStep::TrimBorder { tolerance: 5 }
+        );
+        assert_eq!(pipeline.steps.len(), 5);
+        assert_eq!(pipeline.with_trim(None).steps.len(), 4);
+    }
+
+    #[test]
+    fn test_trim_border() {
+        let trimmed = trim_border(&create_letterboxed_image(), 0);
+        assert_eq!(trimmed.dimensions(), (12, 8));
+        assert_eq!(
+            trimmed.get_pixel(0, 0),
+            create_letterboxed_image().get_pixel(4, 2)
+        );
+
+        // A uniform image has no content to keep
+        let flat =
+            DynamicImage::ImageRgba8(image::ImageBuffer::from_pixel(5, 5, Rgba([9, 9, 9, 255])));
+        assert_eq!(trim_border(&flat, 0).dimensions(), (5, 5));
+
+        // Noise within the tolerance still counts as border
+        let noisy =
+            DynamicImage::ImageRgba8(image::ImageBuffer::from_fn(6, 6, |x, y| match (x, y) {
+                (2..4, 2..4) => Rgba([200, 200, 200, 255]),
+                _ => Rgba([(x + y) as u8 % 3, 0, 0, 255]),
+            }));
+        assert_eq!(trim_border(&noisy, 2).dimensions(), (2, 2));
+        assert_eq!(trim_border(&noisy, 0).dimensions(), (6, 6));
+    }
 }
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use image::imageops::FilterType;
+    use image::metadata::Orientation;
+    use image::{DynamicImage, GrayImage, Luma};
+    use imgddcore::hashing::ImageHash;
+    use imgddcore::normalize::NormalizePipeline;
+    use imgddcore::segment::*;
+
+    /// Creates a bright scene with three dark, textured shapes.
+    fn create_mock_scene() -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(600, 400, |x, y| {
+            let value = match (x, y) {
+                (40..200, 60..220) => (x - 40) / 2,
+                (250..380, 150..330) => 20 + (x / 16 + y / 16) % 2 * 60,
+                (480..580, 50..150) => 100 - (y - 50) / 2,
+                _ => 220,
+            };
+            Luma([value as u8])
+        }))
+    }
+
+    #[test]
+    fn test_crop_resistant_hash_segments() -> Result<()> {
+        let hash = crop_resistant_hash(
+            &create_mock_scene(),
+            FilterType::Triangle,
+            "dhash",
+            8,
+            &SegmentOptions::default(),
+        )?;
+
+        // The background and the three shapes
+        assert_eq!(hash.segment_hashes.len(), 4);
+        assert!(hash.segment_hashes.iter().all(|h| h.len() == 64));
+        assert_eq!(hash.to_string().split(',').count(), 4);
+
+        let options = SegmentOptions {
+            limit_segments: Some(2),
+            ..SegmentOptions::default()
+        };
+        let limited = crop_resistant_hash(
+            &create_mock_scene(),
+            FilterType::Triangle,
+            "dhash",
+            8,
+            &options,
+        )?;
+        assert_eq!(limited.segment_hashes.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crop_resistant_hash_matches_cropped_copy() -> Result<()> {
+        let scene = create_mock_scene();
+        let options = SegmentOptions::default();
+        let original = crop_resistant_hash(&scene, FilterType::Triangle, "dhash", 8, &options)?;
+
+        // Cut off the right shape and part of the background
+        let cropped = scene.crop_imm(0, 0, 420, 400);
+        let cropped = crop_resistant_hash(&cropped, FilterType::Triangle, "dhash", 8, &options)?;
+
+        let (matching, _) = original.hash_diff(&cropped, None);
+        assert!(matching >= 2, "Expected both remaining shapes to match");
+        assert!(original.matches(&cropped, 2, None));
+        assert!(!original.matches(&cropped, 5, None));
+
+        // A different, noisy image shares no segments
+        let other = DynamicImage::ImageLuma8(GrayImage::from_fn(600, 400, |x, y| {
+            let noise = ((x * 31 + y * 17) ^ (x * y)) % 40;
+            Luma([(if (x / 50 + y / 70) % 3 == 0 { 30 } else { 190 } + noise) as u8])
+        }));
+        let other = crop_resistant_hash(&other, FilterType::Triangle, "dhash", 8, &options)?;
+        assert!(!original.matches(&other, 1, Some(4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crop_resistant_hash_with_pipeline() -> Result<()> {
+        let scene = create_mock_scene();
+        let options = SegmentOptions::default();
+        let original = crop_resistant_hash(&scene, FilterType::Triangle, "dhash", 8, &options)?;
+
+        // A black letterbox is trimmed before the image is segmented
+        let mut letterboxed = DynamicImage::ImageLuma8(GrayImage::new(600, 520));
+        image::imageops::replace(&mut letterboxed, &scene, 0, 60);
+        let pipeline = NormalizePipeline::new(FilterType::Triangle).with_trim(Some(0));
+        let trimmed = crop_resistant_hash_with(
+            &letterboxed,
+            Orientation::NoTransforms,
+            "dhash",
+            8,
+            &options,
+            &pipeline,
+        )?;
+        assert_eq!(trimmed, original);
+
+        // Images are turned upright before they are segmented
+        let oriented = crop_resistant_hash_with(
+            &scene.rotate270(),
+            Orientation::Rotate90,
+            "dhash",
+            8,
+            &options,
+            &NormalizePipeline::new(FilterType::Triangle).oriented(),
+        )?;
+        assert_eq!(oriented, original);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crop_resistant_hash_flat_image() -> Result<()> {
+        let flat = DynamicImage::ImageLuma8(GrayImage::from_pixel(64, 48, Luma([90])));
+        let hash = crop_resistant_hash(
+            &flat,
+            FilterType::Triangle,
+            "ahash",
+            8,
+            &SegmentOptions::default(),
+        )?;
+
+        // The whole image is a single segment
+        assert_eq!(hash.segment_hashes, vec![ImageHash::from(0)]);
+
+        let empty = DynamicImage::ImageLuma8(GrayImage::new(0, 0));
+        assert!(crop_resistant_hash(
+            &empty,
+            FilterType::Triangle,
+            "ahash",
+            8,
+            &SegmentOptions::default()
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_diff() {
+        let multi = |hashes: &[u64]| ImageMultiHash {
+            segment_hashes: hashes.iter().map(|&h| ImageHash::from(h)).collect(),
+        };
+        let a = multi(&[0, u64::MAX, 0xFF]);
+        let b = multi(&[0b1, 0xF0F0_F0F0_F0F0_F0F0]);
+
+        // 0 and 0xFF are 1 and 7 bits from 0b1, u64::MAX is 32 bits from anything in b
+        assert_eq!(a.hash_diff(&b, None), (2, 8));
+        assert_eq!(a.hash_diff(&b, Some(1)), (1, 1));
+        assert_eq!(a.hash_diff(&b, Some(32)), (3, 40));
+        assert!(a.matches(&b, 2, None));
+        assert!(!a.matches(&b, 3, None));
+        assert_eq!(a.hash_diff(&multi(&[]), None), (0, 0));
+    }
+}
@@ -1,3 +1,3 @@
 from imgdd import *
 
-__all__ = ["hash", "dupes"]
\ No newline at end of file
+__all__ = ["hash", "hash_trimmed", "dupes", "dupes_dry_run", "undo", "DuplicateGroup", "DuplicateMember"]
\ No newline at end of file
@@ -1,10 +1,45 @@
-from typing import Literal, Dict
+from typing import Literal, Dict, Optional, Union, Any
+
+class DuplicateMember:
+    """
+    A file belonging to a `DuplicateGroup`.
+
+    Attributes:
+        path (str): The file path.
+        hash (str): The hash of the file as a hex string.
+        file_size (int): The size of the file in bytes.
+        dimensions (tuple[int, int] | None): The width and height of the image, if readable.
+    """
+    path: str
+    hash: str
+    file_size: int
+    dimensions: Optional[tuple[int, int]]
+
+class DuplicateGroup:
+    """
+    A group of duplicate images with unique members sorted by path.
+
+    Attributes:
+        hash (str): The hash the group was matched on as a hex string.
+        cluster_id (int): The cluster the group belongs to. Transitively linked near-duplicate groups share it.
+        members (list[DuplicateMember]): The files of the group, sorted by path.
+        paths (list[str]): The file paths of the group, sorted by path.
+    """
+    hash: str
+    cluster_id: int
+    members: list[DuplicateMember]
+    paths: list[str]
+    def __len__(self) -> int: ...
 
 def hash(
     path: str,
     filter: Literal["Nearest", "Triangle", "CatmullRom", "Gaussian", "Lanczos3"] = "Nearest",
-    algo: Literal["aHash", "mHash", "dHash", "pHash", "wHash"] = "dHash",
+    algo: Literal["aHash", "mHash", "dHash", "verticalDHash", "doubleDHash", "pHash", "wHash", "colorHash", "PDQ", "blockHash", "marrHildreth", "blockMean", "blockMeanOverlap"] = "dHash",
     sort: bool = False,
+    hash_size: int = 8,
+    orient: bool = False,
+    alpha: Union[Literal["ignore", "white", "black"], str] = "ignore",
+    resize: Literal["stretch", "center_crop", "pad"] = "stretch",
 ) -> Dict[str, str]:
     """
     Calculate the hash of images in a directory.
@@ -13,18 +48,61 @@ def hash(
         path (str): Path to the directory containing images.
         filter (str): Resize filter to use.
         algo (str): Hashing algorithm.
+        sort (bool): Whether to sort the results by hash values.
+        hash_size (int): Side length of the hash grid, e.g. 8 for 64-bit and 16 for 256-bit hashes.
+        orient (bool): Whether to turn images upright according to their EXIF orientation before hashing.
+        alpha (str): How transparent pixels are treated before hashing: "ignore", "white", "black"
+            or a background color in hex, e.g. "#808080".
+        resize (str): How images are fitted to the hash grid: "stretch", "center_crop" or "pad".
 
     Returns:
         Dict[str, str]: A dictionary mapping file paths to their hashes.
     """
     ...
 
+def hash_trimmed(
+    path: str,
+    filter: Literal["Nearest", "Triangle", "CatmullRom", "Gaussian", "Lanczos3"] = "Nearest",
+    algo: Literal["aHash", "mHash", "dHash", "verticalDHash", "doubleDHash", "pHash", "wHash", "colorHash", "PDQ", "blockHash", "marrHildreth", "blockMean", "blockMeanOverlap"] = "dHash",
+    sort: bool = False,
+    hash_size: int = 8,
+    orient: bool = False,
+    alpha: Union[Literal["ignore", "white", "black"], str] = "ignore",
+    tolerance: int = 10,
+    resize: Literal["stretch", "center_crop", "pad"] = "stretch",
+) -> Dict[str, Dict[str, Any]]:
+    """
+    Calculate the hash of images in a directory after cropping uniform borders, such as letterboxing.
+
+    Args:
+        path (str): Path to the directory containing images.
+        tolerance (int): Largest channel difference still counted as border.
+
+        All other arguments are the same as for `hash`.
+
+    Returns:
+        Dict[str, dict]: A dictionary mapping file paths to their `hash` and `trim`,
+            the number of pixels cropped from the `left`, `top`, `right` and `bottom` edges.
+    """
+    ...
+
 def dupes(
     path: str,
     filter: Literal["Nearest", "Triangle", "CatmullRom", "Gaussian", "Lanczos3"] = "Nearest",
-    algo: Literal["aHash", "mHash", "dHash", "pHash", "wHash"] = "dHash",
+    algo: Literal["aHash", "mHash", "dHash", "verticalDHash", "doubleDHash", "pHash", "wHash", "colorHash", "PDQ", "blockHash", "marrHildreth", "blockMean", "blockMeanOverlap"] = "dHash",
+    max_distance: int = 0,
     remove: bool = False,
-) -> Dict[str, list[str]]:
+    keep: Literal["first", "largest_resolution", "largest_size", "oldest", "newest", "format", "path"] = "first",
+    priority: Optional[list[str]] = None,
+    quarantine: Optional[str] = None,
+    journal: Optional[str] = None,
+    link: Optional[Literal["hard", "symbolic", "reflink"]] = None,
+    hash_size: int = 8,
+    orient: bool = False,
+    alpha: Union[Literal["ignore", "white", "black"], str] = "ignore",
+    trim: Optional[int] = None,
+    resize: Literal["stretch", "center_crop", "pad"] = "stretch",
+) -> list[DuplicateGroup]:
     """
     Find duplicate images in a directory.
 
@@ -32,9 +110,63 @@ def dupes(
         path (str): Path to the directory containing images.
         filter (str): Resize filter to use.
         algo (str): Hashing algorithm.
+        max_distance (int): Maximum Hamming distance for two images to be considered duplicates.
         remove (bool): Whether to remove duplicate files.
+        keep (str): Which duplicate survives removal.
+        priority (list[str]): Ordered file extensions (`format`) or directories (`path`) to prefer.
+        quarantine (str): Directory that removed duplicates are moved into instead of being deleted.
+            Must be outside of `path`, so quarantined files are not scanned again.
+        journal (str): JSON lines file recording every removed, moved or linked duplicate.
+            Only written when `remove` is set.
+        link (str): Replace removed duplicates with a link to the kept file instead of deleting them.
+            Options: "hard", "symbolic", "reflink". Cannot be combined with `quarantine`.
+        hash_size (int): Side length of the hash grid, e.g. 8 for 64-bit and 16 for 256-bit hashes.
+        orient (bool): Whether to turn images upright according to their EXIF orientation before hashing.
+        alpha (str): How transparent pixels are treated before hashing: "ignore", "white", "black"
+            or a background color in hex, e.g. "#808080".
+        trim (int): Tolerance for cropping uniform borders, such as letterboxing, before hashing.
+        resize (str): How images are fitted to the hash grid: "stretch", "center_crop" or "pad".
+
+    Returns:
+        list[DuplicateGroup]: The duplicate groups, each with unique members sorted by path.
+    """
+    ...
+
+def dupes_dry_run(
+    path: str,
+    filter: Literal["Nearest", "Triangle", "CatmullRom", "Gaussian", "Lanczos3"] = "Nearest",
+    algo: Literal["aHash", "mHash", "dHash", "verticalDHash", "doubleDHash", "pHash", "wHash", "colorHash", "PDQ", "blockHash", "marrHildreth", "blockMean", "blockMeanOverlap"] = "dHash",
+    max_distance: int = 0,
+    keep: Literal["first", "largest_resolution", "largest_size", "oldest", "newest", "format", "path"] = "first",
+    priority: Optional[list[str]] = None,
+    hash_size: int = 8,
+    orient: bool = False,
+    alpha: Union[Literal["ignore", "white", "black"], str] = "ignore",
+    trim: Optional[int] = None,
+    resize: Literal["stretch", "center_crop", "pad"] = "stretch",
+) -> list[Dict[str, Any]]:
+    """
+    Plan which duplicate images `dupes` would remove, without touching the filesystem.
+
+    Args:
+        path (str): Path to the directory containing images.
+
+        All other arguments are the same as for `dupes`.
+
+    Returns:
+        list[dict]: One plan entry per group of duplicates with the keys
+            `keep`, `remove`, `reason` and `bytes_reclaimed`.
+    """
+    ...
+
+def undo(journal: str) -> list[str]:
+    """
+    Restore quarantined duplicates recorded in a journal.
+
+    Args:
+        journal (str): Path to the JSON lines journal written by `dupes`.
 
     Returns:
-        Dict[str, list[str]]: A dictionary mapping hashes to lists of file paths.
+        list[str]: The restored file paths. Removed files cannot be restored.
     """
     ...
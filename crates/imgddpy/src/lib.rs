@@ -1,18 +1,22 @@
 use image::imageops::FilterType;
+use image::ImageFormat;
 use imgddcore::dedupe::*;
+use imgddcore::journal::{self, Journal};
+use imgddcore::normalize::{AlphaPolicy, NormalizePipeline, ResizeMode};
 use imgddcore::validate::*;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[inline]
 fn select_filter_type(filter: Option<&str>) -> FilterType {
     match filter.unwrap_or("nearest") {
-        ref f if f.eq_ignore_ascii_case("nearest") => FilterType::Nearest,
-        ref f if f.eq_ignore_ascii_case("triangle") => FilterType::Triangle,
-        ref f if f.eq_ignore_ascii_case("catmullrom") => FilterType::CatmullRom,
-        ref f if f.eq_ignore_ascii_case("gaussian") => FilterType::Gaussian,
-        ref f if f.eq_ignore_ascii_case("lanczos3") => FilterType::Lanczos3,
+        f if f.eq_ignore_ascii_case("nearest") => FilterType::Nearest,
+        f if f.eq_ignore_ascii_case("triangle") => FilterType::Triangle,
+        f if f.eq_ignore_ascii_case("catmullrom") => FilterType::CatmullRom,
+        f if f.eq_ignore_ascii_case("gaussian") => FilterType::Gaussian,
+        f if f.eq_ignore_ascii_case("lanczos3") => FilterType::Lanczos3,
         other => panic!("Unsupported filter type: {}", other),
     }
 }
@@ -21,14 +25,192 @@ fn select_filter_type(filter: Option<&str>) -> FilterType {
 fn select_algo(algo: Option<&str>) -> &'static str {
     match algo.unwrap_or("dhash") {
         input if input.eq_ignore_ascii_case("dhash") => "dhash",
+        input if input.eq_ignore_ascii_case("verticaldhash") => "verticaldhash",
+        input if input.eq_ignore_ascii_case("doubledhash") => "doubledhash",
         input if input.eq_ignore_ascii_case("ahash") => "ahash",
         input if input.eq_ignore_ascii_case("mhash") => "mhash",
         input if input.eq_ignore_ascii_case("phash") => "phash",
         input if input.eq_ignore_ascii_case("whash") => "whash",
+        input if input.eq_ignore_ascii_case("colorhash") => "colorhash",
+        input if input.eq_ignore_ascii_case("pdq") => "pdq",
+        input if input.eq_ignore_ascii_case("blockhash") => "blockhash",
+        input if input.eq_ignore_ascii_case("marrhildreth") => "marrhildreth",
+        input if input.eq_ignore_ascii_case("blockmean") => "blockmean",
+        input if input.eq_ignore_ascii_case("blockmeanoverlap") => "blockmeanoverlap",
         other => panic!("Unsupported algorithm: {}", other),
     }
 }
 
+#[inline]
+fn select_keep_policy(keep: Option<&str>, priority: Option<Vec<String>>) -> KeepPolicy {
+    let priority = priority.unwrap_or_default();
+    match keep.unwrap_or("first") {
+        k if k.eq_ignore_ascii_case("first") => KeepPolicy::First,
+        k if k.eq_ignore_ascii_case("largest_resolution") => KeepPolicy::LargestResolution,
+        k if k.eq_ignore_ascii_case("largest_size") => KeepPolicy::LargestFileSize,
+        k if k.eq_ignore_ascii_case("oldest") => KeepPolicy::Oldest,
+        k if k.eq_ignore_ascii_case("newest") => KeepPolicy::Newest,
+        k if k.eq_ignore_ascii_case("format") => KeepPolicy::PreferredFormat(
+            priority
+                .iter()
+                .map(|ext| {
+                    ImageFormat::from_extension(ext)
+                        .unwrap_or_else(|| panic!("Unsupported image format: {}", ext))
+                })
+                .collect(),
+        ),
+        k if k.eq_ignore_ascii_case("path") => {
+            KeepPolicy::PathPriority(priority.into_iter().map(PathBuf::from).collect())
+        }
+        other => panic!("Unsupported keep policy: {}", other),
+    }
+}
+
+#[inline]
+fn select_alpha_policy(alpha: Option<&str>) -> AlphaPolicy {
+    match alpha.unwrap_or("ignore") {
+        a if a.eq_ignore_ascii_case("ignore") => AlphaPolicy::Ignore,
+        a if a.eq_ignore_ascii_case("white") => AlphaPolicy::White,
+        a if a.eq_ignore_ascii_case("black") => AlphaPolicy::Black,
+        other => AlphaPolicy::Color(
+            parse_hex_color(other).unwrap_or_else(|| panic!("Unsupported alpha policy: {}", other)),
+        ),
+    }
+}
+
+#[inline]
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[inline]
+fn select_resize_mode(resize: Option<&str>) -> ResizeMode {
+    match resize.unwrap_or("stretch") {
+        r if r.eq_ignore_ascii_case("stretch") => ResizeMode::Stretch,
+        r if r.eq_ignore_ascii_case("center_crop") => ResizeMode::CenterCrop,
+        r if r.eq_ignore_ascii_case("pad") => ResizeMode::Pad,
+        other => panic!("Unsupported resize mode: {}", other),
+    }
+}
+
+#[inline]
+fn select_pipeline(
+    filter: Option<&str>,
+    orient: bool,
+    alpha: Option<&str>,
+    trim: Option<u8>,
+    resize: Option<&str>,
+) -> NormalizePipeline {
+    let pipeline = NormalizePipeline::new(select_filter_type(filter))
+        .with_resize_mode(select_resize_mode(resize));
+    let pipeline = if orient {
+        pipeline.oriented()
+    } else {
+        pipeline
+    };
+    pipeline
+        .with_alpha(select_alpha_policy(alpha))
+        .with_trim(trim)
+}
+
+#[inline]
+fn select_link_kind(link: Option<&str>) -> Option<LinkKind> {
+    link.map(|link| match link {
+        l if l.eq_ignore_ascii_case("hard") => LinkKind::Hard,
+        l if l.eq_ignore_ascii_case("symbolic") => LinkKind::Symbolic,
+        l if l.eq_ignore_ascii_case("reflink") => LinkKind::Reflink,
+        other => panic!("Unsupported link kind: {}", other),
+    })
+}
+
+/// A file belonging to a `DuplicateGroup`.
+///
+/// # Attributes
+/// - `path (str)`: The file path.
+/// - `hash (str)`: The hash of the file as a hex string.
+/// - `file_size (int)`: The size of the file in bytes.
+/// - `dimensions (tuple[int, int] | None)`: The width and height of the image, if readable.
+#[pyclass(name = "DuplicateMember", frozen, get_all)]
+#[derive(Clone)]
+pub struct PyDuplicateMember {
+    path: PathBuf,
+    hash: String,
+    file_size: u64,
+    dimensions: Option<(u32, u32)>,
+}
+
+#[pymethods]
+impl PyDuplicateMember {
+    fn __repr__(&self) -> String {
+        format!(
+            "DuplicateMember(path={:?}, hash='{}', file_size={}, dimensions={:?})",
+            self.path, self.hash, self.file_size, self.dimensions
+        )
+    }
+}
+
+/// A group of duplicate images with unique members sorted by path.
+///
+/// # Attributes
+/// - `hash (str)`: The hash the group was matched on as a hex string.
+/// - `cluster_id (int)`: The cluster the group belongs to. Transitively linked near-duplicate groups share it.
+/// - `members (list[DuplicateMember])`: The files of the group, sorted by path.
+/// - `paths (list[str])`: The file paths of the group, sorted by path.
+#[pyclass(name = "DuplicateGroup", frozen, get_all)]
+pub struct PyDuplicateGroup {
+    hash: String,
+    cluster_id: usize,
+    members: Vec<PyDuplicateMember>,
+}
+
+#[pymethods]
+impl PyDuplicateGroup {
+    #[getter]
+    fn paths(&self) -> Vec<PathBuf> {
+        self.members
+            .iter()
+            .map(|member| member.path.clone())
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.members.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DuplicateGroup(hash='{}', cluster_id={}, paths={:?})",
+            self.hash,
+            self.cluster_id,
+            self.paths()
+        )
+    }
+}
+
+impl From<DuplicateGroup> for PyDuplicateGroup {
+    fn from(group: DuplicateGroup) -> Self {
+        Self {
+            hash: group.hash.to_hex(),
+            cluster_id: group.cluster_id,
+            members: group
+                .members
+                .into_iter()
+                .map(|member| PyDuplicateMember {
+                    path: member.path,
+                    hash: member.hash.to_hex(),
+                    file_size: member.file_size,
+                    dimensions: member.dimensions,
+                })
+                .collect(),
+        }
+    }
+}
+
 /// ```python
 /// hash(path, filter="triangle", algo="dhash", sort=False)
 /// ```
@@ -41,13 +223,30 @@ fn select_algo(algo: Option<&str>) -> &'static str {
 ///     - **Options:** [`Nearest`, `Triangle`, `CatmullRom`, `Gaussian`, `Lanczos3`]
 ///     - **Default:** `Triangle`
 /// - `algo (str)`: Hashing algorithm.
-///     - **Options:** [`aHash`, `mHash`, `dHash`, `pHash`, `wHash`]
+///     - **Options:** [`aHash`, `mHash`, `dHash`, `verticalDHash`, `doubleDHash`, `pHash`, `wHash`, `colorHash`, `PDQ`, `blockHash`, `marrHildreth`, `blockMean`, `blockMeanOverlap`]
 ///     - **Default:** `dHash`
 /// - `sort (bool)`: Whether to sort the results by hash values.
 ///     - **Default:** `False`
+/// - `hash_size (int)`: Side length of the hash grid, e.g. `8` for 64-bit and `16` for 256-bit hashes.
+///     - `wHash` requires a power of two and `blockHash` an even size.
+///     - `doubleDHash` combines both dHash directions into hashes twice as long, e.g. 128 bits for 8.
+///     - `colorHash`, `PDQ`, `marrHildreth`, `blockMean` and `blockMeanOverlap` ignore it
+///       and always produce 42-bit, 256-bit, 576-bit, 256-bit and 961-bit hashes.
+///     - **Default:** `8`
+/// - `orient (bool)`: Whether to turn images upright according to their EXIF orientation before hashing.
+///     - **Default:** `False`
+/// - `alpha (str)`: How transparent pixels are treated before hashing.
+///     - **Options:** [`ignore`, `white`, `black`] or a background color in hex, e.g. `#808080`
+///     - **Default:** `ignore`
+/// - `resize (str)`: How images are fitted to the hash grid before hashing.
+///     - **Options:** [`stretch`, `center_crop`, `pad`]
+///     - `center_crop` matches thumbnails cut from the middle of wider images,
+///       `pad` keeps the whole image undistorted. Algorithms hashing the full image ignore it.
+///     - **Default:** `stretch`
 ///
 /// # Returns
 /// `Dict[str, str]`: A dictionary mapping file paths to their hashes.
+///
 /// # Usage
 ///
 /// ```python
@@ -61,19 +260,24 @@ fn select_algo(algo: Option<&str>) -> &'static str {
 /// )
 /// print(results)
 /// ```
-#[pyfunction(signature = (path, filter = None, algo = None, sort = false))]
+#[pyfunction(signature = (path, filter = None, algo = None, sort = false, hash_size = 8, orient = false, alpha = None, resize = None))]
+#[allow(clippy::too_many_arguments)]
 pub fn hash(
     path: PathBuf,
     filter: Option<&str>,
     algo: Option<&str>,
     sort: Option<bool>,
+    hash_size: u32,
+    orient: bool,
+    alpha: Option<&str>,
+    resize: Option<&str>,
 ) -> PyResult<HashMap<PathBuf, String>> {
     let validated_path = validate_path(&path)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}", e)))?;
-    let filter_type = select_filter_type(filter);
+    let pipeline = select_pipeline(filter, orient, alpha, None, resize);
     let algo = select_algo(algo);
 
-    let mut hash_paths = collect_hashes(&validated_path, filter_type, &algo)
+    let mut hash_paths = collect_hashes_with(validated_path, algo, hash_size, &pipeline)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
 
     // Optionally sort hashes
@@ -83,10 +287,83 @@ pub fn hash(
 
     Ok(hash_paths
         .into_iter()
-        .map(|(hash, path)| (path, format!("{:x}", hash)))
+        .map(|(hash, path)| (path, hash.to_hex()))
         .collect())
 }
 
+/// ```python
+/// hash_trimmed(path, filter="triangle", algo="dhash", tolerance=10)
+/// ```
+///
+/// Calculate the hash of images in a directory after cropping uniform borders, such as letterboxing.
+///
+/// # Arguments
+/// - `path (str)`: Path to the directory containing images.
+/// - `tolerance (int)`: Largest channel difference still counted as border.
+///     - **Default:** `10`
+///
+/// All other arguments are the same as for `hash`.
+///
+/// # Returns
+/// `Dict[str, dict]`: A dictionary mapping file paths to their `hash (str)` and `trim (dict)`,
+/// the number of pixels cropped from the `left`, `top`, `right` and `bottom` edges.
+///
+/// # Usage
+///
+/// ```python
+/// import imgdd as dd
+///
+/// results = dd.hash_trimmed(
+///     path="path/to/images",
+///     algo="dhash",
+///     tolerance=10
+/// )
+/// print(results)
+/// ```
+#[pyfunction(signature = (path, filter = None, algo = None, sort = false, hash_size = 8, orient = false, alpha = None, tolerance = 10, resize = None))]
+#[allow(clippy::too_many_arguments)]
+pub fn hash_trimmed(
+    py: Python<'_>,
+    path: PathBuf,
+    filter: Option<&str>,
+    algo: Option<&str>,
+    sort: Option<bool>,
+    hash_size: u32,
+    orient: bool,
+    alpha: Option<&str>,
+    tolerance: u8,
+    resize: Option<&str>,
+) -> PyResult<Py<PyDict>> {
+    let validated_path = validate_path(&path)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}", e)))?;
+    let pipeline = select_pipeline(filter, orient, alpha, None, resize);
+    let algo = select_algo(algo);
+
+    let mut hash_paths =
+        collect_trimmed_hashes(validated_path, algo, hash_size, tolerance, &pipeline)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+
+    // Optionally sort hashes
+    if sort.unwrap_or(false) {
+        hash_paths.sort_by(|(hash1, ..), (hash2, ..)| hash1.cmp(hash2));
+    }
+
+    let results = PyDict::new(py);
+    for (hash, path, trim) in hash_paths {
+        let trimmed = PyDict::new(py);
+        trimmed.set_item("left", trim.left)?;
+        trimmed.set_item("top", trim.top)?;
+        trimmed.set_item("right", trim.right)?;
+        trimmed.set_item("bottom", trim.bottom)?;
+
+        let entry = PyDict::new(py);
+        entry.set_item("hash", hash.to_hex())?;
+        entry.set_item("trim", trimmed)?;
+        results.set_item(path, entry)?;
+    }
+    Ok(results.unbind())
+}
+
 /// ```python
 /// dupes(path, filter="triangle", algo="dhash", remove=False)
 /// ```
@@ -99,13 +376,46 @@ pub fn hash(
 ///     - **Options:** [`Nearest`, `Triangle`, `CatmullRom`, `Gaussian`, `Lanczos3`]
 ///     - **Default:** `Triangle`
 /// - `algo (str)`: Hashing algorithm.
-///     - **Options:** [`aHash`, `mHash`, `dHash`, `pHash`, `wHash`]
+///     - **Options:** [`aHash`, `mHash`, `dHash`, `verticalDHash`, `doubleDHash`, `pHash`, `wHash`, `colorHash`, `PDQ`, `blockHash`, `marrHildreth`, `blockMean`, `blockMeanOverlap`]
 ///     - **Default:** `dHash`
+/// - `max_distance (int)`: Maximum Hamming distance for two images to be considered duplicates.
+///     - **Default:** `0` (exact matches only)
 /// - `remove (bool)`: Whether to remove duplicate files
 ///     - **Default:** `False`
+/// - `keep (str)`: Which duplicate survives removal.
+///     - **Options:** [`first`, `largest_resolution`, `largest_size`, `oldest`, `newest`, `format`, `path`]
+///     - **Default:** `first`
+/// - `priority (list[str])`: Ordered file extensions (`format`) or directories (`path`) to prefer.
+///     - **Default:** `None`
+/// - `quarantine (str)`: Directory that removed duplicates are moved into instead of being deleted.
+///     - Paths relative to `path` are preserved, e.g. `a/b/c.jpg` is moved to `quarantine/a/b/c.jpg`.
+///     - Must be outside of `path`, so quarantined files are not scanned again.
+///     - **Default:** `None`
+/// - `journal (str)`: JSON lines file recording every removed, moved or linked duplicate, see `undo`.
+///     - Only written when `remove` is set.
+///     - **Default:** `None`
+/// - `link (str)`: Replace removed duplicates with a link to the kept file instead of deleting them.
+///     - **Options:** [`hard`, `symbolic`, `reflink`]
+///     - Cannot be combined with `quarantine`.
+///     - **Default:** `None`
+/// - `hash_size (int)`: Side length of the hash grid, e.g. `8` for 64-bit and `16` for 256-bit hashes.
+///     - **Default:** `8`
+/// - `orient (bool)`: Whether to turn images upright according to their EXIF orientation before hashing.
+///     - **Default:** `False`
+/// - `alpha (str)`: How transparent pixels are treated before hashing.
+///     - **Options:** [`ignore`, `white`, `black`] or a background color in hex, e.g. `#808080`
+///     - **Default:** `ignore`
+/// - `trim (int)`: Tolerance for cropping uniform borders, such as letterboxing, before hashing.
+///     - **Default:** `None` (borders are kept)
+/// - `resize (str)`: How images are fitted to the hash grid before hashing.
+///     - **Options:** [`stretch`, `center_crop`, `pad`]
+///     - `center_crop` matches thumbnails cut from the middle of wider images,
+///       `pad` keeps the whole image undistorted. Algorithms hashing the full image ignore it.
+///     - **Default:** `stretch`
 ///
 /// # Returns
-/// `Dict[str, list[str]]`: A dictionary mapping hashes to lists of file paths.
+/// `list[DuplicateGroup]`: The duplicate groups, each with unique members sorted by path.
+///
 /// # Usage
 ///
 /// ```python
@@ -115,37 +425,184 @@ pub fn hash(
 ///     path="path/to/images",
 ///     algo="dhash",
 ///     filter="triangle",
-///     remove=False
+///     max_distance=0,
+///     remove=False,
+///     keep="first"
 /// )
 /// print(duplicates)
 /// ```
-#[pyfunction(signature = (path, filter = None, algo = None, remove = false))]
+#[pyfunction(signature = (path, filter = None, algo = None, max_distance = 0, remove = false, keep = None, priority = None, quarantine = None, journal = None, link = None, hash_size = 8, orient = false, alpha = None, trim = None, resize = None))]
+#[allow(clippy::too_many_arguments)]
 pub fn dupes(
     path: PathBuf,
     filter: Option<&str>,
     algo: Option<&str>,
+    max_distance: u32,
     remove: bool,
-) -> PyResult<HashMap<String, Vec<PathBuf>>> {
+    keep: Option<&str>,
+    priority: Option<Vec<String>>,
+    quarantine: Option<PathBuf>,
+    journal: Option<PathBuf>,
+    link: Option<&str>,
+    hash_size: u32,
+    orient: bool,
+    alpha: Option<&str>,
+    trim: Option<u8>,
+    resize: Option<&str>,
+) -> PyResult<Vec<PyDuplicateGroup>> {
     let validated_path = validate_path(&path)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}", e)))?;
-    let filter_type = select_filter_type(filter);
+    let pipeline = select_pipeline(filter, orient, alpha, trim, resize);
     let algo = select_algo(algo);
+    let keep_policy = select_keep_policy(keep, priority);
+    let action = match (quarantine, select_link_kind(link)) {
+        _ if !remove => DuplicateAction::Report,
+        (Some(_), Some(_)) => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Cannot both quarantine and link duplicates",
+            ))
+        }
+        (Some(dir), None) => {
+            check_quarantine_dir(validated_path, &dir)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}", e)))?;
+            DuplicateAction::Quarantine {
+                root: validated_path.clone(),
+                dir,
+            }
+        }
+        (None, Some(kind)) => DuplicateAction::Link(kind),
+        (None, None) => DuplicateAction::Remove,
+    };
+    // Only destructive runs are journaled, so a report never creates the journal file
+    let mut journal = journal
+        .filter(|_| action != DuplicateAction::Report)
+        .map(|journal_path| Journal::open(&journal_path, algo))
+        .transpose()
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
 
-    let mut hash_paths = collect_hashes(&validated_path, filter_type, &algo)
+    let mut hash_paths = collect_hashes_with(validated_path, algo, hash_size, &pipeline)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
     sort_hashes(&mut hash_paths);
 
-    let duplicates = find_duplicates(&hash_paths, remove)
+    let duplicates = find_duplicates(
+        &hash_paths,
+        max_distance,
+        &action,
+        &keep_policy,
+        journal.as_mut(),
+    )
+    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+
+    Ok(duplicates.into_iter().map(PyDuplicateGroup::from).collect())
+}
+
+/// ```python
+/// dupes_dry_run(path, filter="triangle", algo="dhash", keep="first")
+/// ```
+///
+/// Plan which duplicate images `dupes` would remove, without touching the filesystem.
+///
+/// # Arguments
+/// - `path (str)`: Path to the directory containing images.
+/// - `filter`, `algo`, `max_distance`, `keep`, `priority`, `hash_size`, `orient`, `alpha`, `trim`
+///   and `resize` are the same as for `dupes`.
+///
+/// # Returns
+/// `list[dict]`: One plan entry per group of duplicates with the keys `keep (str)`,
+/// `remove (list[str])`, `reason (str)` and `bytes_reclaimed (int)`.
+///
+/// # Usage
+///
+/// ```python
+/// import imgdd as dd
+///
+/// plan = dd.dupes_dry_run(
+///     path="path/to/images",
+///     algo="dhash",
+///     keep="largest_resolution"
+/// )
+/// print(plan)
+/// ```
+#[pyfunction(signature = (path, filter = None, algo = None, max_distance = 0, keep = None, priority = None, hash_size = 8, orient = false, alpha = None, trim = None, resize = None))]
+#[allow(clippy::too_many_arguments)]
+pub fn dupes_dry_run(
+    py: Python<'_>,
+    path: PathBuf,
+    filter: Option<&str>,
+    algo: Option<&str>,
+    max_distance: u32,
+    keep: Option<&str>,
+    priority: Option<Vec<String>>,
+    hash_size: u32,
+    orient: bool,
+    alpha: Option<&str>,
+    trim: Option<u8>,
+    resize: Option<&str>,
+) -> PyResult<Vec<Py<PyDict>>> {
+    let validated_path = validate_path(&path)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{}", e)))?;
+    let pipeline = select_pipeline(filter, orient, alpha, trim, resize);
+    let algo = select_algo(algo);
+    let keep_policy = select_keep_policy(keep, priority);
+
+    let mut hash_paths = collect_hashes_with(validated_path, algo, hash_size, &pipeline)
         .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+    sort_hashes(&mut hash_paths);
+
+    let duplicates = find_duplicates(
+        &hash_paths,
+        max_distance,
+        &DuplicateAction::Report,
+        &keep_policy,
+        None,
+    )
+    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
 
-    Ok(duplicates
+    plan_duplicates(&duplicates, &keep_policy)
         .into_iter()
-        .map(|(hash, paths)| (format!("{:x}", hash), paths))
-        .collect())
+        .map(|planned| {
+            let entry = PyDict::new(py);
+            entry.set_item("keep", planned.keep)?;
+            entry.set_item("remove", planned.remove)?;
+            entry.set_item("reason", planned.reason)?;
+            entry.set_item("bytes_reclaimed", planned.bytes_reclaimed)?;
+            Ok(entry.unbind())
+        })
+        .collect()
+}
+
+/// ```python
+/// undo(journal)
+/// ```
+///
+/// Restore quarantined duplicates recorded in a journal.
+///
+/// # Arguments
+/// - `journal (str)`: Path to the JSON lines journal written by `dupes`.
+///
+/// # Returns
+/// `list[str]`: The restored file paths. Removed files cannot be restored.
+/// # Usage
+///
+/// ```python
+/// import imgdd as dd
+///
+/// restored = dd.undo(journal="path/to/journal.jsonl")
+/// print(restored)
+/// ```
+#[pyfunction(signature = (journal))]
+pub fn undo(journal: PathBuf) -> PyResult<Vec<PathBuf>> {
+    journal::undo(&journal)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))
 }
 
 #[pymodule]
 fn imgdd(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDuplicateGroup>()?;
+    m.add_class::<PyDuplicateMember>()?;
     m.add_function(wrap_pyfunction!(dupes, m)?)?;
-    m.add_function(wrap_pyfunction!(hash, m)?)
+    m.add_function(wrap_pyfunction!(dupes_dry_run, m)?)?;
+    m.add_function(wrap_pyfunction!(undo, m)?)?;
+    m.add_function(wrap_pyfunction!(hash, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_trimmed, m)?)
 }
@@ -1,6 +1,9 @@
 import imgdd as dd
 from pathlib import Path
 import pytest
+import shutil
+import struct
+import zlib
 
 
 @pytest.fixture
@@ -15,7 +18,7 @@ def test_hash_required(test_images_path):
 
 def test_dupes_required(test_images_path):
     duplicates = dd.dupes(path=test_images_path)
-    assert isinstance(duplicates, dict), "Expected a dictionary of duplicates"
+    assert isinstance(duplicates, list), "Expected a list of duplicate groups"
     assert len(duplicates) >= 0, "Expected no errors for duplicates"
 
 # optional args
@@ -26,5 +29,247 @@ def test_hash_optional(test_images_path):
 
 def test_dupes_optional(test_images_path):
     duplicates = dd.dupes(path=test_images_path, filter="Nearest", algo="aHash", remove=True)
-    assert isinstance(duplicates, dict), "Expected a dictionary of duplicates"
-    assert len(duplicates) >= 0, "Expected no errors for duplicates"
\ No newline at end of file
+    assert isinstance(duplicates, list), "Expected a list of duplicate groups"
+    assert len(duplicates) >= 0, "Expected no errors for duplicates"
+
+def test_dupes_max_distance(test_images_path):
+    duplicates = dd.dupes(path=test_images_path, max_distance=64)
+    assert isinstance(duplicates, list), "Expected a list of duplicate groups"
+    assert len(duplicates) == 1, "Expected every image to fall within 64 bits"
+
+def test_dupes_keep_policy(test_images_path):
+    duplicates = dd.dupes(path=test_images_path, keep="format", priority=["png", "jpg"])
+    assert isinstance(duplicates, list), "Expected a list of duplicate groups"
+
+def test_dupes_quarantine(tmp_path):
+    source = Path(__file__).parent / "../../../../imgs/test/apple_pie/99556.jpg"
+    images = tmp_path / "images"
+    (images / "originals").mkdir(parents=True)
+    (images / "nested").mkdir(parents=True)
+    shutil.copy(source, images / "originals" / "a.jpg")
+    shutil.copy(source, images / "nested" / "b.jpg")
+
+    quarantine = tmp_path / "quarantine"
+    dd.dupes(
+        path=str(images),
+        remove=True,
+        keep="path",
+        priority=[str(images / "originals")],
+        quarantine=str(quarantine),
+    )
+    assert (images / "originals" / "a.jpg").exists(), "Expected original image to be kept"
+    assert (quarantine / "nested" / "b.jpg").exists(), "Expected duplicate to be quarantined"
+
+def test_dupes_quarantine_inside_path(tmp_path):
+    source = Path(__file__).parent / "../../../../imgs/test/apple_pie/99556.jpg"
+    shutil.copy(source, tmp_path / "a.jpg")
+    shutil.copy(source, tmp_path / "b.jpg")
+
+    with pytest.raises(ValueError):
+        dd.dupes(path=str(tmp_path), remove=True, quarantine=str(tmp_path / "quarantine"))
+    assert (tmp_path / "a.jpg").exists() and (tmp_path / "b.jpg").exists(), "Expected no files to be touched"
+
+def test_dupes_journal_undo(tmp_path):
+    source = Path(__file__).parent / "../../../../imgs/test/apple_pie/99556.jpg"
+    images = tmp_path / "images"
+    images.mkdir()
+    shutil.copy(source, images / "a.jpg")
+    shutil.copy(source, images / "b.jpg")
+
+    journal = tmp_path / "journal.jsonl"
+    dd.dupes(path=str(images), journal=str(journal))
+    assert not journal.exists(), "Expected no journal without remove"
+
+    dd.dupes(path=str(images), remove=True, quarantine=str(tmp_path / "quarantine"), journal=str(journal))
+    assert len(journal.read_text().splitlines()) == 1, "Expected one journal entry"
+
+    restored = dd.undo(journal=str(journal))
+    assert len(restored) == 1, "Expected one restored file"
+    assert (images / "a.jpg").exists() and (images / "b.jpg").exists(), "Expected both images after undo"
+
+def test_dupes_dry_run(tmp_path):
+    source = Path(__file__).parent / "../../../../imgs/test/apple_pie/99556.jpg"
+    shutil.copy(source, tmp_path / "a.jpg")
+    shutil.copy(source, tmp_path / "b.jpg")
+
+    plan = dd.dupes_dry_run(path=str(tmp_path), keep="first")
+    assert isinstance(plan, list), "Expected a list of plan entries"
+    assert len(plan) == 1, "Expected a single group"
+    assert len(plan[0]["remove"]) == 1, "Expected one file to be removed"
+    assert plan[0]["bytes_reclaimed"] == source.stat().st_size
+    assert (tmp_path / "a.jpg").exists() and (tmp_path / "b.jpg").exists(), "Expected no files to be touched"
+
+def test_dupes_link(tmp_path):
+    source = Path(__file__).parent / "../../../../imgs/test/apple_pie/99556.jpg"
+    shutil.copy(source, tmp_path / "a.jpg")
+    shutil.copy(source, tmp_path / "b.jpg")
+
+    dd.dupes(path=str(tmp_path), remove=True, link="hard")
+    assert (tmp_path / "a.jpg").exists() and (tmp_path / "b.jpg").exists(), "Expected both paths to remain"
+    assert (tmp_path / "a.jpg").stat().st_ino == (tmp_path / "b.jpg").stat().st_ino, "Expected a hard link"
+
+    with pytest.raises(ValueError):
+        dd.dupes(path=str(tmp_path), remove=True, link="hard", quarantine=str(tmp_path / "q"))
+
+def test_dupes_groups(tmp_path):
+    source = Path(__file__).parent / "../../../../imgs/test/apple_pie/99556.jpg"
+    shutil.copy(source, tmp_path / "b.jpg")
+    shutil.copy(source, tmp_path / "a.jpg")
+
+    duplicates = dd.dupes(path=str(tmp_path))
+    assert len(duplicates) == 1, "Expected a single group"
+    group = duplicates[0]
+    assert isinstance(group, dd.DuplicateGroup)
+    assert group.cluster_id == 0, "Expected the first cluster"
+    assert len(group) == 2, "Expected unique members"
+    assert [Path(p).name for p in group.paths] == ["a.jpg", "b.jpg"], "Expected members sorted by path"
+    for member in group.members:
+        assert member.hash == group.hash
+        assert member.file_size == source.stat().st_size
+        assert member.dimensions is not None
+
+def test_hash_size(test_images_path):
+    results = dd.hash(path=test_images_path, algo="phash", hash_size=16)
+    assert all(len(h) == 64 for h in results.values()), "Expected 256-bit hex hashes"
+
+    duplicates = dd.dupes(path=test_images_path, hash_size=16)
+    assert isinstance(duplicates, list), "Expected a list of duplicate groups"
+
+
+
+def test_dhash_variants(test_images_path):
+    results = dd.hash(path=test_images_path, algo="verticalDHash")
+    assert all(len(h) == 16 for h in results.values()), "Expected 64-bit hex hashes"
+
+    results = dd.hash(path=test_images_path, algo="doubleDHash")
+    assert all(len(h) == 32 for h in results.values()), "Expected 128-bit hex hashes"
+
+
+def test_colorhash(test_images_path):
+    results = dd.hash(path=test_images_path, algo="colorHash")
+    assert all(len(h) == 11 for h in results.values()), "Expected 42-bit hex hashes"
+
+
+def test_pdq(test_images_path):
+    results = dd.hash(path=test_images_path, algo="PDQ")
+    assert all(len(h) == 64 for h in results.values()), "Expected 256-bit hex hashes"
+
+
+def test_blockhash(test_images_path):
+    results = dd.hash(path=test_images_path, algo="blockHash", hash_size=16)
+    assert all(len(h) == 64 for h in results.values()), "Expected 256-bit hex hashes"
+
+
+def test_marr_hildreth(test_images_path):
+    results = dd.hash(path=test_images_path, algo="marrHildreth")
+    assert all(len(h) == 144 for h in results.values()), "Expected 576-bit hex hashes"
+
+
+def test_block_mean(test_images_path):
+    results = dd.hash(path=test_images_path, algo="blockMean")
+    assert all(len(h) == 64 for h in results.values()), "Expected 256-bit hex hashes"
+
+    results = dd.hash(path=test_images_path, algo="blockMeanOverlap")
+    assert all(len(h) == 241 for h in results.values()), "Expected 961-bit hex hashes"
+
+
+def test_orient(test_images_path, tmp_path):
+    source = Path(test_images_path) / "apple_pie" / "21063.jpg"
+    shutil.copy(source, tmp_path / "original.jpg")
+
+    # Rewrite the big-endian EXIF orientation tag from 1 (upright) to 6 (rotate 90° clockwise)
+    data = source.read_bytes()
+    tag = b"\x01\x12\x00\x03\x00\x00\x00\x01\x00"
+    assert data.count(tag + b"\x01") == 1
+    (tmp_path / "rotated.jpg").write_bytes(data.replace(tag + b"\x01", tag + b"\x06"))
+
+    raw = dd.hash(path=str(tmp_path))
+    assert raw[str(tmp_path / "original.jpg")] == raw[str(tmp_path / "rotated.jpg")]
+
+    oriented = dd.hash(path=str(tmp_path), orient=True)
+    assert oriented[str(tmp_path / "original.jpg")] == raw[str(tmp_path / "original.jpg")]
+    assert oriented[str(tmp_path / "original.jpg")] != oriented[str(tmp_path / "rotated.jpg")]
+
+
+def _write_rgba_png(path, width, height, pixel):
+    """Writes an 8-bit RGBA PNG whose pixel colors are given by `pixel(x, y)`."""
+    def chunk(kind, data):
+        return struct.pack(">I", len(data)) + kind + data + struct.pack(">I", zlib.crc32(kind + data))
+
+    rows = b"".join(
+        b"\x00" + b"".join(bytes(pixel(x, y)) for x in range(width)) for y in range(height)
+    )
+    header = struct.pack(">IIBBBBB", width, height, 8, 6, 0, 0, 0)
+    path.write_bytes(
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", header)
+        + chunk(b"IDAT", zlib.compress(rows))
+        + chunk(b"IEND", b"")
+    )
+
+
+def test_alpha(tmp_path):
+    def logo(hidden):
+        def pixel(x, y):
+            if 4 <= x < 12 and 4 <= y < 12:
+                return (255, 0, 0, 255)
+            return (*hidden(x, y), 0)
+        return pixel
+
+    # The same red square, once with noise hidden under its transparent background
+    _write_rgba_png(tmp_path / "clean.png", 16, 16, logo(lambda x, y: (0, 0, 0)))
+    _write_rgba_png(tmp_path / "noisy.png", 16, 16, logo(lambda x, y: ((x * 37 + y * 91) % 256, 255 - x * 16, y * 16)))
+    clean, noisy = str(tmp_path / "clean.png"), str(tmp_path / "noisy.png")
+
+    ignored = dd.hash(path=str(tmp_path), algo="aHash")
+    assert ignored[clean] != ignored[noisy]
+
+    for alpha in ["white", "black", "#0080ff"]:
+        composited = dd.hash(path=str(tmp_path), algo="aHash", alpha=alpha)
+        assert composited[clean] == composited[noisy], alpha
+
+
+def test_trim(tmp_path):
+    def content(x, y):
+        return (60 + x * 4, 60 + y * 5, 90, 255)
+
+    # The same gradient, once with black bars above and below
+    _write_rgba_png(tmp_path / "content.png", 40, 30, content)
+    _write_rgba_png(
+        tmp_path / "letterboxed.png", 40, 46,
+        lambda x, y: content(x, y - 8) if 8 <= y < 38 else (3, 3, 3, 255),
+    )
+    original, letterboxed = str(tmp_path / "content.png"), str(tmp_path / "letterboxed.png")
+
+    results = dd.hash_trimmed(path=str(tmp_path), algo="aHash", tolerance=10)
+    assert results[original]["hash"] == results[letterboxed]["hash"]
+    assert results[original]["trim"] == {"left": 0, "top": 0, "right": 0, "bottom": 0}
+    assert results[letterboxed]["trim"] == {"left": 0, "top": 8, "right": 0, "bottom": 8}
+
+    hashes = dd.hash(path=str(tmp_path), algo="aHash")
+    assert hashes[original] != hashes[letterboxed]
+    assert results[original]["hash"] == hashes[original]
+
+    assert len(dd.dupes(path=str(tmp_path), algo="aHash")) == 0
+    duplicates = dd.dupes(path=str(tmp_path), algo="aHash", trim=10)
+    assert len(duplicates) == 1 and len(duplicates[0]) == 2
+
+
+def test_resize(tmp_path):
+    def banner(x, y):
+        return ((x * 7 + y * 13) % 97 + (x // 10) * 15, 0, 0, 255)
+
+    # A square thumbnail cut from the middle of a wide banner
+    _write_rgba_png(tmp_path / "banner.png", 90, 30, banner)
+    _write_rgba_png(tmp_path / "thumbnail.png", 30, 30, lambda x, y: banner(x + 30, y))
+    wide, square = str(tmp_path / "banner.png"), str(tmp_path / "thumbnail.png")
+
+    stretched = dd.hash(path=str(tmp_path), algo="aHash")
+    assert stretched[wide] != stretched[square]
+
+    cropped = dd.hash(path=str(tmp_path), algo="aHash", resize="center_crop")
+    assert cropped[wide] == cropped[square]
+
+    padded = dd.hash(path=str(tmp_path), algo="aHash", resize="pad")
+    assert padded[wide] != padded[square]
+    assert len(dd.dupes(path=str(tmp_path), algo="aHash", resize="center_crop")) == 1
@@ -0,0 +1,418 @@
+"""Generates the img_hash fixtures and prints the regression hashes checked by hashing_tests.rs.
+
+The hashes are computed by a plain Python transcription of the algorithms of OpenCV's img_hash
+module (opencv_contrib, modules/img_hash/src), written independently of imgddcore. They catch
+regressions and slips in the Rust code, but a mistake shared by both transcriptions goes unnoticed,
+so they are not OpenCV reference vectors. When `cv2` with the contrib modules is importable,
+OpenCV's own hashes are printed next to them for comparison.
+
+Usage, from the repository root:
+
+    python3 imgs/img_hash/reference.py
+"""
+
+import math
+import os
+import struct
+import zlib
+from array import array
+from fractions import Fraction
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+
+def pattern(width, height, channel=0, noise=True):
+    """Smooth shapes with a little deterministic noise, so that no two blocks tie."""
+    seed = 12345 + channel
+    radius = 0.2 * min(width, height)
+    rows = []
+    for y in range(height):
+        row = []
+        for x in range(width):
+            value = 110 + 50 * math.sin((x + 17 * channel) / 37.0) * math.cos(y / 23.0)
+            if (x - 0.3 * width) ** 2 + (y - 0.4 * height) ** 2 < radius**2:
+                value += 60
+            if 0.55 * width < x < 0.85 * width and 0.6 * height < y < 0.8 * height:
+                value -= 70
+            seed = (seed * 1103515245 + 12345) & 0x7FFFFFFF
+            if noise:
+                value += (seed >> 16) % 9 - 4
+            row.append(min(255, max(0, int(math.floor(value + 0.5)))))
+        rows.append(row)
+    return rows
+
+
+def write_png(name, channels):
+    """Writes one (grayscale) or three (RGB) channels of equal size as an 8-bit PNG."""
+    height, width = len(channels[0]), len(channels[0][0])
+    raw = bytearray()
+    for y in range(height):
+        raw.append(0)
+        for x in range(width):
+            raw.extend(channel[y][x] for channel in channels)
+
+    def chunk(kind, data):
+        body = kind + data
+        return struct.pack(">I", len(data)) + body + struct.pack(">I", zlib.crc32(body))
+
+    color_type = 0 if len(channels) == 1 else 2
+    header = struct.pack(">IIBBBBB", width, height, 8, color_type, 0, 0, 0)
+    with open(os.path.join(HERE, name), "wb") as f:
+        f.write(b"\x89PNG\r\n\x1a\n")
+        f.write(chunk(b"IHDR", header))
+        f.write(chunk(b"IDAT", zlib.compress(bytes(raw), 9)))
+        f.write(chunk(b"IEND", b""))
+
+
+def bits_to_hex(bits):
+    """Hex string of a bit string, padded with leading zero bits like ImageHash::to_hex."""
+    bits = [False] * ((4 - len(bits) % 4) % 4) + list(bits)
+    return "".join(
+        "%x" % int("".join("1" if b else "0" for b in bits[i : i + 4]), 2)
+        for i in range(0, len(bits), 4)
+    )
+
+
+def f32(value):
+    return struct.unpack("f", struct.pack("f", value))[0]
+
+
+def reflect101(position, length):
+    """cv::borderInterpolate with BORDER_REFLECT_101, the default border of OpenCV's filters."""
+    last = length - 1
+    if last == 0:
+        return 0
+    while position < 0 or position > last:
+        position = -position if position < 0 else 2 * last - position
+    return position
+
+
+def gaussian_kernel(size, sigma):
+    """cv::getGaussianKernel, including its fixed tables for small kernels without a sigma."""
+    if sigma <= 0 and size == 3:
+        return [0.25, 0.5, 0.25]
+    if sigma <= 0 and size == 7:
+        return [w / 32.0 for w in (1.0, 3.5, 7.0, 9.0, 7.0, 3.5, 1.0)]
+    if sigma <= 0:
+        sigma = ((size - 1) * 0.5 - 1) * 0.3 + 0.8
+    radius = size // 2
+    weights = [math.exp(-(x * x) / (2.0 * sigma * sigma)) for x in range(-radius, radius + 1)]
+    total = sum(weights)
+    return [w / total for w in weights]
+
+
+def gaussian_blur(image, kernel):
+    """cv::GaussianBlur of an 8-bit image in floating point, rounding once at the end."""
+    height, width = len(image), len(image[0])
+    radius = len(kernel) // 2
+    horizontal = []
+    for row in image:
+        out = []
+        for x in range(width):
+            total = 0.0
+            for k, weight in enumerate(kernel):
+                total += weight * row[reflect101(x + k - radius, width)]
+            out.append(total)
+        horizontal.append(out)
+    blurred = []
+    for y in range(height):
+        out = []
+        for x in range(width):
+            total = 0.0
+            for k, weight in enumerate(kernel):
+                total += weight * horizontal[reflect101(y + k - radius, height)][x]
+            out.append(min(255, max(0, int(math.floor(total + 0.5)))))
+        blurred.append(out)
+    return blurred
+
+
+def equalize_hist(image):
+    """cv::equalizeHist, with its single precision scale."""
+    histogram = [0] * 256
+    for row in image:
+        for value in row:
+            histogram[value] += 1
+    total = sum(histogram)
+    first = next(i for i, count in enumerate(histogram) if count)
+    if histogram[first] == total:
+        return [[first] * len(row) for row in image]
+
+    scale = f32(255.0 / f32(total - histogram[first]))
+    lut = [0] * 256
+    running = 0
+    for level in range(first + 1, 256):
+        running += histogram[level]
+        lut[level] = min(255, max(0, round(f32(f32(running) * scale))))
+    return [[lut[value] for value in row] for row in image]
+
+
+def marr_hildreth(image, alpha=2.0, level=1.0):
+    """MarrHildrethHashImpl::compute for a 512x512 grayscale image, which OpenCV does not resize."""
+    blurred = gaussian_blur(image, gaussian_kernel(7, 0))
+    equalized = equalize_hist(blurred)
+
+    # getMHKernel
+    sigma = int(4 * alpha**level)
+    ratio = alpha ** (-level)
+    side = 2 * sigma + 1
+    kernel = []
+    for row in range(side):
+        y = ratio * (row - sigma)
+        kernel_row = []
+        for col in range(side):
+            x = ratio * (col - sigma)
+            a = x * x + y * y
+            kernel_row.append((2 - a) * math.exp(a / 2))
+        kernel.append(array("f", kernel_row))
+
+    # filter2D into CV_32F, only over the 496x496 pixels that fillBlocks reads
+    size = len(image)
+    covered = 31 * 16
+    padded = [
+        [equalized[reflect101(y - sigma, size)][reflect101(x - sigma, size)] for x in range(size + 2 * sigma)]
+        for y in range(size + 2 * sigma)
+    ]
+    filtered = []
+    for y in range(covered):
+        sums = [0.0] * covered
+        for ky in range(side):
+            padded_row = padded[y + ky]
+            for kx, weight in enumerate(kernel[ky]):
+                segment = padded_row[kx : kx + covered]
+                sums = [s + weight * v for s, v in zip(sums, segment)]
+        filtered.append(array("f", sums))
+
+    # fillBlocks: blocks(row, col) sums Rect(row * 16, col * 16, 16, 16), x before y
+    blocks = [[0.0] * 31 for _ in range(31)]
+    for row in range(31):
+        for col in range(31):
+            total = 0.0
+            for y in range(col * 16, col * 16 + 16):
+                for x in range(row * 16, row * 16 + 16):
+                    total += filtered[y][x]
+            blocks[row][col] = f32(total)
+
+    # createHash: 3x3 windows of blocks, 4 blocks apart, most significant bit first
+    bits = []
+    for row in range(0, 29, 4):
+        for col in range(0, 29, 4):
+            window = [blocks[row + i][col + j] for i in range(3) for j in range(3)]
+            average = f32(sum(window) / 9.0)
+            bits.extend(block > average for block in window)
+    return bits_to_hex(bits)
+
+
+def radial_variance(image, sigma=1.0, angles=180, coefficients=40):
+    """RadialVarianceHashImpl::compute for a grayscale image of any size."""
+    size = int(math.floor(sigma * 3 * 2 + 1 + 0.5)) | 1
+    blurred = gaussian_blur(image, gaussian_kernel(size, sigma))
+    height, width = len(blurred), len(blurred[0])
+
+    def rounded(value):
+        return int(math.floor(value + (0.5 if value >= 0 else -0.5)))
+
+    # radialProjections
+    length = max(width, height)
+    x_off, y_off = rounded(width / 2.0), rounded(height / 2.0)
+    projections = [[0] * length for _ in range(angles)]
+    counts = [0] * angles
+    for k in range(angles // 4 + 1):
+        alpha = math.tan(k * math.pi / angles)
+        for x in range(length):
+            y = rounded(alpha * (x - x_off))
+            if 0 <= y + y_off < height and x < width:
+                projections[k][x] = blurred[y + y_off][x]
+                counts[k] += 1
+            if 0 <= y + x_off < width and k != angles // 4 and x < height:
+                projections[angles // 2 - k][x] = blurred[x][y + x_off]
+                counts[angles // 2 - k] += 1
+    j = 0
+    for k in range(3 * angles // 4, angles):
+        alpha = math.tan(k * math.pi / angles)
+        for x in range(length):
+            y = rounded(alpha * (x - x_off))
+            if 0 <= y + y_off < height and x < width:
+                projections[k][x] = blurred[y + y_off][x]
+                counts[k] += 1
+            if 0 <= y_off - y < width and 0 <= 2 * y_off - x < height and k != 3 * angles // 4:
+                projections[k - j][x] = blurred[2 * y_off - x][y_off - y]
+                counts[k - j] += 1
+        j += 2
+
+    # findFeatureVector
+    features = []
+    for line, count in zip(projections, counts):
+        count += 0.00001
+        line_sum = float(sum(line))
+        line_squared = float(sum(v * v for v in line))
+        features.append(line_squared / count - line_sum * line_sum / (count * count))
+    total = sum(features)
+    squared = sum(f * f for f in features)
+    mean = total / angles
+    deviation = math.sqrt(squared / angles - total * total / (angles * angles))
+    features = [(f - mean) / deviation for f in features]
+
+    # hashCalculate: the lowest DCT coefficients, truncated to bytes
+    values = []
+    for k in range(coefficients):
+        total = sum(
+            f * math.cos(math.pi * (2 * i + 1) * k / (2 * angles)) for i, f in enumerate(features)
+        )
+        values.append(total * (1.0 if k == 0 else math.sqrt(2.0)) / math.sqrt(angles))
+    high, low = max(0.0, max(values)), min(0.0, min(values))
+    return "".join("%02x" % int(255 * (v - low) / (high - low)) for v in values)
+
+
+def block_mean(image, overlapping):
+    """BlockMeanHashImpl::compute for a 256x256 grayscale image, which OpenCV does not resize."""
+    step = 8 if overlapping else 16
+    means = []
+    for row in range(0, 256 - 16 + 1, step):
+        for col in range(0, 256 - 16 + 1, step):
+            total = sum(image[y][x] for y in range(row, row + 16) for x in range(col, col + 16))
+            means.append(total / 256.0)
+
+    # std::nth_element at the middle picks the upper median
+    median = sorted(means)[len(means) // 2]
+    # createHash sets a bit unless the block is below the median
+    return bits_to_hex([mean >= median for mean in means])
+
+
+def opencv_block_mean(image, mode, blocks):
+    """OpenCV packs bmHash bits least significant first, unlike ImageHash."""
+    import cv2
+
+    data = cv2.img_hash.blockMeanHash(image, mode=mode).tobytes()
+    bits = [byte >> i & 1 == 1 for byte in data for i in range(8)]
+    return bits_to_hex(bits[:blocks])
+
+
+def bgr2hsv(r, g, b):
+    """cv::cvtColor with COLOR_BGR2HSV for 8-bit pixels, using its fixed-point division tables."""
+    shift = 12
+    value = max(r, g, b)
+    diff = value - min(r, g, b)
+    sdiv = round((255 << shift) / value) if value else 0
+    hdiv = round((180 << shift) / (6.0 * diff)) if diff else 0
+    saturation = (diff * sdiv + (1 << (shift - 1))) >> shift
+    if value == r:
+        hue = g - b
+    elif value == g:
+        hue = b - r + 2 * diff
+    else:
+        hue = r - g + 4 * diff
+    hue = (hue * hdiv + (1 << (shift - 1))) >> shift
+    if hue < 0:
+        hue += 180
+    return hue, saturation, value
+
+
+def bgr2ycrcb(r, g, b):
+    """cv::cvtColor with COLOR_BGR2YCrCb for 8-bit pixels."""
+    shift = 14
+    half, delta = 1 << (shift - 1), 128 << shift
+    y = (r * 4899 + g * 9617 + b * 1868 + half) >> shift
+    cr = ((r - y) * 11682 + delta + half) >> shift
+    cb = ((b - y) * 9241 + delta + half) >> shift
+    return tuple(min(255, max(0, c)) for c in (y, cr, cb))
+
+
+def hu_moments(channel):
+    """cv::HuMoments of cv::moments, with the central moments computed exactly."""
+    m = {}
+    for p, q in ((0, 0), (1, 0), (0, 1), (2, 0), (1, 1), (0, 2), (3, 0), (2, 1), (1, 2), (0, 3)):
+        m[p, q] = sum(
+            value * x**p * y**q for y, row in enumerate(channel) for x, value in enumerate(row) if value
+        )
+    if m[0, 0] == 0:
+        return [0.0] * 7
+
+    cx, cy = Fraction(m[1, 0], m[0, 0]), Fraction(m[0, 1], m[0, 0])
+    mu = {
+        (2, 0): m[2, 0] - cx * m[1, 0],
+        (1, 1): m[1, 1] - cx * m[0, 1],
+        (0, 2): m[0, 2] - cy * m[0, 1],
+        (3, 0): m[3, 0] - 3 * cx * m[2, 0] + 2 * cx * cx * m[1, 0],
+        (2, 1): m[2, 1] - 2 * cx * m[1, 1] - cy * m[2, 0] + 2 * cx * cx * m[0, 1],
+        (1, 2): m[1, 2] - 2 * cy * m[1, 1] - cx * m[0, 2] + 2 * cy * cy * m[1, 0],
+        (0, 3): m[0, 3] - 3 * cy * m[0, 2] + 2 * cy * cy * m[0, 1],
+    }
+    nu = {
+        key: float(value / Fraction(m[0, 0]) ** (1 + sum(key) // 2))
+        / (math.sqrt(m[0, 0]) if sum(key) == 3 else 1.0)
+        for key, value in mu.items()
+    }
+
+    t0, t1 = nu[3, 0] + nu[1, 2], nu[2, 1] + nu[0, 3]
+    q0, q1 = t0 * t0, t1 * t1
+    n4 = 4 * nu[1, 1]
+    s, d = nu[2, 0] + nu[0, 2], nu[2, 0] - nu[0, 2]
+    hu = [0.0] * 7
+    hu[0] = s
+    hu[1] = d * d + n4 * nu[1, 1]
+    hu[3] = q0 + q1
+    hu[5] = d * (q0 - q1) + n4 * t0 * t1
+    t0 *= q0 - 3 * q1
+    t1 *= 3 * q0 - q1
+    q0, q1 = nu[3, 0] - 3 * nu[1, 2], 3 * nu[2, 1] - nu[0, 3]
+    hu[2] = q0 * q0 + q1 * q1
+    hu[4] = q0 * t0 + q1 * t1
+    hu[6] = q1 * t0 - q0 * t1
+    return hu
+
+
+def color_moment(channels):
+    """ColorMomentHashImpl::compute for a 512x512 RGB image, which OpenCV does not resize."""
+    red, green, blue = (gaussian_blur(channel, gaussian_kernel(3, 0)) for channel in channels)
+    pixels = [list(zip(r, g, b)) for r, g, b in zip(red, green, blue)]
+
+    moments = []
+    for convert in (bgr2hsv, bgr2ycrcb):
+        converted = [[convert(*pixel) for pixel in row] for row in pixels]
+        for channel in range(3):
+            moments.extend(hu_moments([[pixel[channel] for pixel in row] for row in converted]))
+    return ",".join(repr(moment) for moment in moments)
+
+
+def opencv_hashes(name):
+    """OpenCV's own hashes of a fixture, when cv2 with the contrib modules is installed."""
+    try:
+        import cv2
+    except ImportError:
+        return None
+    image = cv2.imread(os.path.join(HERE, name), cv2.IMREAD_UNCHANGED)
+    return {
+        "marr_hildreth": cv2.img_hash.marrHildrethHash(image).tobytes().hex(),
+        "radial_variance": cv2.img_hash.radialVarianceHash(image).tobytes().hex(),
+        "color_moment": ",".join(repr(m) for m in cv2.img_hash.colorMomentHash(image).ravel()),
+        "block_mean": opencv_block_mean(image, cv2.img_hash.BLOCK_MEAN_HASH_MODE_0, 256),
+        "block_mean_overlap": opencv_block_mean(image, cv2.img_hash.BLOCK_MEAN_HASH_MODE_1, 961),
+    }
+
+
+def main():
+    fixtures = [
+        ("gray_512.png", [pattern(512, 512)], ["marr_hildreth", "radial_variance"]),
+        ("gray_300x200.png", [pattern(300, 200, channel=1)], ["radial_variance"]),
+        ("rgb_512.png", [pattern(512, 512, c, noise=False) for c in (2, 3, 4)], ["color_moment"]),
+        ("gray_256.png", [pattern(256, 256, channel=5)], ["block_mean", "block_mean_overlap"]),
+    ]
+    algorithms = {
+        "marr_hildreth": lambda channels: marr_hildreth(channels[0]),
+        "radial_variance": lambda channels: radial_variance(channels[0]),
+        "color_moment": color_moment,
+        "block_mean": lambda channels: block_mean(channels[0], overlapping=False),
+        "block_mean_overlap": lambda channels: block_mean(channels[0], overlapping=True),
+    }
+
+    for name, channels, hashes in fixtures:
+        write_png(name, channels)
+        opencv = opencv_hashes(name)
+        for algorithm in hashes:
+            print(name, algorithm, algorithms[algorithm](channels))
+            if opencv is not None:
+                print(name, algorithm, "(opencv)", opencv[algorithm])
+
+
+if __name__ == "__main__":
+    main()